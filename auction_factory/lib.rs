@@ -0,0 +1,174 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Auction Factory Contract
+///
+/// Instantiates Dutch auctions from an uploaded auction code hash, one at a time with `create`
+/// or several in one transaction with `create_many`, e.g. one per NFT tier or per payment token.
+/// Ownership of every new auction is transferred to the caller, who configures and funds it as if
+/// they had instantiated it themselves.
+///
+/// A batch is all or nothing: if any auction can't be created, e.g. because of inconsistent
+/// parameters, the whole transaction fails.
+///
+/// Contract Storage:
+/// - auction_code_hash: The code hash new auctions are instantiated from.
+/// - auction_count: The number of auctions created, also salting their addresses.
+/// - auctions: The auctions created, in creation order.
+///
+/// Events:
+/// - AuctionCreated: Emitted for every auction created.
+///
+/// Messages:
+/// - create: Creates an auction owned by the caller.
+/// - create_many: Creates several auctions owned by the caller.
+/// - auction_code_hash, auction_count, auction: Views of the configuration and the created auctions.
+///
+/// Errors:
+/// - InvalidBatch: The batch is empty or larger than `MAX_BATCH_SIZE`.
+/// - Auction: The auction constructor rejected the parameters.
+/// - InstantiationFailed: The auction couldn't be instantiated, e.g. because the code hash is unknown.
+/// - Ownership: The ownership of the new auction couldn't be transferred to the caller.
+#[ink::contract]
+pub mod auction_factory {
+    use dutch_auction::dutch_auction::{AuctionParams, DutchAuctionRef};
+    use dutch_auction_traits::{DutchAuctionError, Ownable, OwnableError};
+    use ink::{
+        codegen::TraitCallBuilder, contract_ref, prelude::vec::Vec, storage::Mapping, ToAccountId,
+    };
+
+    /// Maximum number of auctions created by a single `create_many`.
+    pub const MAX_BATCH_SIZE: usize = 16;
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        InvalidBatch,
+        Auction(DutchAuctionError),
+        InstantiationFailed,
+        Ownership(OwnableError),
+    }
+
+    #[ink(storage)]
+    pub struct Factory {
+        auction_code_hash: Hash,
+        auction_count: u32,
+        auctions: Mapping<u32, AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct AuctionCreated {
+        #[ink(topic)]
+        auction: AccountId,
+        #[ink(topic)]
+        owner: AccountId,
+        index: u32,
+    }
+
+    impl Factory {
+        /// Constructor creating auctions from the uploaded code `auction_code_hash`.
+        #[ink(constructor)]
+        pub fn new(auction_code_hash: Hash) -> Self {
+            Self {
+                auction_code_hash,
+                auction_count: 0,
+                auctions: Mapping::default(),
+            }
+        }
+
+        /// Create an auction with `params`, owned by the caller. Returns its address.
+        #[ink(message)]
+        pub fn create(&mut self, params: AuctionParams) -> Result<AccountId, Error> {
+            self.instantiate(params)
+        }
+
+        /// Create an auction for each entry of `params`, owned by the caller, in one transaction.
+        /// Returns their addresses in the order of `params`.
+        #[ink(message)]
+        pub fn create_many(&mut self, params: Vec<AuctionParams>) -> Result<Vec<AccountId>, Error> {
+            if params.is_empty() || params.len() > MAX_BATCH_SIZE {
+                return Err(Error::InvalidBatch);
+            }
+
+            params
+                .into_iter()
+                .map(|params| self.instantiate(params))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn auction_code_hash(&self) -> Hash {
+            self.auction_code_hash
+        }
+
+        #[ink(message)]
+        pub fn auction_count(&self) -> u32 {
+            self.auction_count
+        }
+
+        /// The `index`-th auction created, if any.
+        #[ink(message)]
+        pub fn auction(&self, index: u32) -> Option<AccountId> {
+            self.auctions.get(index)
+        }
+
+        fn instantiate(&mut self, params: AuctionParams) -> Result<AccountId, Error> {
+            let index = self.auction_count;
+            let created = DutchAuctionRef::new(params)
+                .code_hash(self.auction_code_hash)
+                .endowment(0)
+                .salt_bytes(index.to_le_bytes())
+                .try_instantiate();
+            let auction = match created {
+                Ok(Ok(Ok(auction))) => auction.to_account_id(),
+                Ok(Ok(Err(error))) => return Err(Error::Auction(error)),
+                _ => return Err(Error::InstantiationFailed),
+            };
+
+            // The factory instantiated the auction, so it owns it until handing it over.
+            let owner = self.env().caller();
+            let mut ownable: contract_ref!(Ownable) = auction.into();
+            match ownable
+                .call_mut()
+                .transfer_ownership(Some(owner))
+                .try_invoke()
+            {
+                Ok(Ok(Ok(()))) => {}
+                Ok(Ok(Err(error))) => return Err(Error::Ownership(error)),
+                _ => return Err(Error::InstantiationFailed),
+            }
+
+            self.auctions.insert(index, &auction);
+            self.auction_count = index + 1;
+            // `dutch_auction` brings its own `EmitEvent` implementation into scope.
+            ink::codegen::EmitEvent::<Factory>::emit_event(
+                self.env(),
+                AuctionCreated {
+                    auction,
+                    owner,
+                    index,
+                },
+            );
+
+            Ok(auction)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn batches_are_bounded() {
+            let mut factory = Factory::new(Hash::from([7; 32]));
+            assert_eq!(factory.auction_code_hash(), Hash::from([7; 32]));
+            assert_eq!(factory.create_many(Vec::new()), Err(Error::InvalidBatch));
+
+            let batch = (0..=MAX_BATCH_SIZE)
+                .map(|_| AuctionParams::default())
+                .collect();
+            assert_eq!(factory.create_many(batch), Err(Error::InvalidBatch));
+            assert_eq!(factory.auction_count(), 0);
+            assert_eq!(factory.auction(0), None);
+        }
+    }
+}
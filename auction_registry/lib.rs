@@ -0,0 +1,285 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Auction Registry Contract
+///
+/// A directory of Dutch auctions for discovery UIs. Auctions register themselves through
+/// `AuctionRegistry::register`, e.g. with the auction's `register_in` message, and are listed
+/// under their asset and payment token pair. The registry reads the pair from the auction itself,
+/// so only contracts implementing `DutchAuction` can register.
+///
+/// Registered auctions stay active until closed. An auction can close itself at any time, anyone
+/// else can close it once it is past its `end_time` or sold out, or once it no longer answers
+/// `DutchAuction` calls, e.g. after being terminated.
+///
+/// Contract Storage:
+/// - auctions: The registration of each auction.
+/// - auction_count: The number of registered auctions.
+/// - active: The active auctions, indexed from 0 to `active_count - 1`.
+/// - active_index: The index of each active auction in `active`.
+/// - active_count: The number of active auctions.
+/// - pair_auctions: The auctions of each token pair in registration order.
+/// - pair_counts: The number of auctions of each token pair.
+///
+/// Events:
+/// - AuctionRegistered: Emitted when an auction registers.
+/// - AuctionClosed: Emitted when an auction is closed.
+///
+/// Messages:
+/// - register: Registers the calling auction.
+/// - close: Marks an auction as closed, removing it from the active auctions.
+/// - auction: Returns the registration of an auction.
+/// - auction_count, active_count, pair_count: Return the number of registered, active and pair auctions.
+/// - active_auctions: Returns a page of the active auctions.
+/// - pair_auctions: Returns a page of the auctions of a token pair, including closed ones.
+#[ink::contract]
+pub mod auction_registry {
+    use dutch_auction_traits::{AuctionRegistry, DutchAuction, RegistryError};
+    use ink::{codegen::TraitCallBuilder, contract_ref, prelude::vec::Vec, storage::Mapping};
+
+    /// An asset and payment token pair.
+    type Pair = (AccountId, AccountId);
+
+    /// Maximum number of auctions returned by a paginated query.
+    pub const MAX_PAGE_SIZE: u32 = 100;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum AuctionStatus {
+        Active,
+        Closed,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct AuctionEntry {
+        pub asset_token: AccountId,
+        pub payment_token: AccountId,
+        pub registered_at: BlockNumber,
+        pub status: AuctionStatus,
+    }
+
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct Registry {
+        auctions: Mapping<AccountId, AuctionEntry>,
+        auction_count: u32,
+        active: Mapping<u32, AccountId>,
+        active_index: Mapping<AccountId, u32>,
+        active_count: u32,
+        pair_auctions: Mapping<(Pair, u32), AccountId>,
+        pair_counts: Mapping<Pair, u32>,
+    }
+
+    #[ink(event)]
+    pub struct AuctionRegistered {
+        #[ink(topic)]
+        auction: AccountId,
+        #[ink(topic)]
+        asset_token: AccountId,
+        #[ink(topic)]
+        payment_token: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct AuctionClosed {
+        #[ink(topic)]
+        auction: AccountId,
+    }
+
+    impl AuctionRegistry for Registry {
+        #[ink(message)]
+        fn register(&mut self) -> Result<(), RegistryError> {
+            let auction = self.env().caller();
+            if self.auctions.contains(auction) {
+                return Err(RegistryError::AlreadyRegistered);
+            }
+
+            let auction_ref: contract_ref!(DutchAuction) = auction.into();
+            let asset_token = match auction_ref.call().asset_token().try_invoke() {
+                Ok(Ok(token)) => token,
+                _ => return Err(RegistryError::NotAnAuction),
+            };
+            let payment_token = match auction_ref.call().payment_token().try_invoke() {
+                Ok(Ok(token)) => token,
+                _ => return Err(RegistryError::NotAnAuction),
+            };
+
+            self.auctions.insert(
+                auction,
+                &AuctionEntry {
+                    asset_token,
+                    payment_token,
+                    registered_at: self.env().block_number(),
+                    status: AuctionStatus::Active,
+                },
+            );
+            self.auction_count += 1;
+
+            self.active.insert(self.active_count, &auction);
+            self.active_index.insert(auction, &self.active_count);
+            self.active_count += 1;
+
+            let pair = (asset_token, payment_token);
+            let pair_count = self.pair_count(asset_token, payment_token);
+            self.pair_auctions.insert((pair, pair_count), &auction);
+            self.pair_counts.insert(pair, &(pair_count + 1));
+
+            self.env().emit_event(AuctionRegistered {
+                auction,
+                asset_token,
+                payment_token,
+            });
+
+            Ok(())
+        }
+    }
+
+    impl Registry {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Mark `auction` as closed, removing it from the active auctions.
+        ///
+        /// The auction itself can close at any time, anyone else only once it is past its
+        /// `end_time`, sold out or no longer answers `DutchAuction` calls. Closing a closed
+        /// auction does nothing.
+        #[ink(message)]
+        pub fn close(&mut self, auction: AccountId) -> Result<(), RegistryError> {
+            let mut entry = self
+                .auctions
+                .get(auction)
+                .ok_or(RegistryError::NotRegistered)?;
+            if entry.status == AuctionStatus::Closed {
+                return Ok(());
+            }
+            if self.env().caller() != auction && self.is_selling(auction) {
+                return Err(RegistryError::AuctionActive);
+            }
+
+            entry.status = AuctionStatus::Closed;
+            self.auctions.insert(auction, &entry);
+
+            // Swap-remove from the active auctions.
+            let index = self.active_index.take(auction).unwrap_or_default();
+            self.active_count -= 1;
+            if index != self.active_count {
+                let last = self.active.get(self.active_count).unwrap_or(auction);
+                self.active.insert(index, &last);
+                self.active_index.insert(last, &index);
+            }
+            self.active.remove(self.active_count);
+
+            self.env().emit_event(AuctionClosed { auction });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn auction(&self, auction: AccountId) -> Option<AuctionEntry> {
+            self.auctions.get(auction)
+        }
+
+        #[ink(message)]
+        pub fn auction_count(&self) -> u32 {
+            self.auction_count
+        }
+
+        #[ink(message)]
+        pub fn active_count(&self) -> u32 {
+            self.active_count
+        }
+
+        #[ink(message)]
+        pub fn pair_count(&self, asset_token: AccountId, payment_token: AccountId) -> u32 {
+            self.pair_counts
+                .get((asset_token, payment_token))
+                .unwrap_or_default()
+        }
+
+        /// Up to `limit` active auctions starting at index `offset`, at most `MAX_PAGE_SIZE`.
+        ///
+        /// Closing an auction moves the last active auction into its place, so pages read across
+        /// closures may skip or repeat an auction.
+        #[ink(message)]
+        pub fn active_auctions(&self, offset: u32, limit: u32) -> Vec<(AccountId, AuctionEntry)> {
+            Self::page(offset, limit, self.active_count)
+                .filter_map(|index| self.active.get(index))
+                .filter_map(|auction| Some((auction, self.auctions.get(auction)?)))
+                .collect()
+        }
+
+        /// Up to `limit` auctions of the `asset_token` and `payment_token` pair in registration
+        /// order, starting at index `offset`, at most `MAX_PAGE_SIZE`. Includes closed auctions.
+        #[ink(message)]
+        pub fn pair_auctions(
+            &self,
+            asset_token: AccountId,
+            payment_token: AccountId,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<(AccountId, AuctionEntry)> {
+            let count = self.pair_count(asset_token, payment_token);
+            Self::page(offset, limit, count)
+                .filter_map(|index| {
+                    self.pair_auctions
+                        .get(((asset_token, payment_token), index))
+                })
+                .filter_map(|auction| Some((auction, self.auctions.get(auction)?)))
+                .collect()
+        }
+
+        fn page(offset: u32, limit: u32, count: u32) -> core::ops::Range<u32> {
+            let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(count);
+            offset.min(end)..end
+        }
+
+        /// Whether `auction` is before its `end_time` and has asset tokens left or hasn't sold any
+        /// yet. Auctions that fail to answer are not selling.
+        fn is_selling(&self, auction: AccountId) -> bool {
+            let auction: contract_ref!(DutchAuction) = auction.into();
+            let (Ok(Ok(end_time)), Ok(Ok(available)), Ok(Ok(sold))) = (
+                auction.call().end_time().try_invoke(),
+                auction.call().available_asset().try_invoke(),
+                auction.call().sold().try_invoke(),
+            ) else {
+                return false;
+            };
+
+            self.env().block_number() <= end_time && (available > 0 || sold == 0)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn pages_are_clamped() {
+            assert_eq!(Registry::page(0, 10, 5), 0..5);
+            assert_eq!(Registry::page(3, 10, 5), 3..5);
+            assert_eq!(Registry::page(7, 10, 5), 5..5);
+            assert_eq!(Registry::page(0, 1_000, 500), 0..MAX_PAGE_SIZE);
+            assert_eq!(
+                Registry::page(u32::MAX, u32::MAX, u32::MAX),
+                u32::MAX..u32::MAX
+            );
+        }
+
+        #[ink::test]
+        fn unregistered_auctions_cannot_be_closed() {
+            let mut registry = Registry::new();
+            let auction = AccountId::from([5; 32]);
+            assert_eq!(registry.close(auction), Err(RegistryError::NotRegistered));
+            assert_eq!(registry.auction(auction), None);
+            assert!(registry.active_auctions(0, 10).is_empty());
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Gas benchmarks for the Dutch auction contract.
+//!
+//! Deploys the auction and two `mock_psp22` tokens into a drink! sandbox, performs a growing
+//! number of purchases from distinct buyers and reports the gas consumed by `buy`, `price` and
+//! the owner-only messages at every step, so regressions in storage-heavy features show up as
+//! the history grows.
+//!
+//! Build the contract bundles first, then run the harness:
+//!
+//! ```Bash
+//! cargo contract build --release
+//! cargo contract build --release --manifest-path mock_psp22/Cargo.toml
+//! cargo run --release --manifest-path bench/Cargo.toml
+//! ```
+
+use drink::{
+    runtime::MinimalRuntime,
+    session::{contract_transcode::ContractMessageTranscoder, Session, NO_ARGS},
+    AccountId32,
+};
+use std::{error::Error, path::Path, rc::Rc};
+
+const AUCTION_BUNDLE: &str = "target/ink/dutch_auction.contract";
+const TOKEN_BUNDLE: &str = "target/ink/mock_psp22/mock_psp22.contract";
+
+/// Number of purchases performed before each measurement.
+const HISTORY_SIZES: [u32; 4] = [0, 10, 100, 1_000];
+const SUPPLY: u128 = u128::MAX / 2;
+
+struct Contract {
+    address: AccountId32,
+    transcoder: Rc<ContractMessageTranscoder>,
+}
+
+fn bundle(path: &str) -> Result<(Vec<u8>, Rc<ContractMessageTranscoder>), Box<dyn Error>> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let bundle = drink::session::ContractBundle::load(root.join(path))?;
+    Ok((bundle.wasm, bundle.transcoder))
+}
+
+fn deploy(
+    session: &mut Session<MinimalRuntime>,
+    path: &str,
+    args: &[String],
+    salt: u8,
+) -> Result<Contract, Box<dyn Error>> {
+    let (wasm, transcoder) = bundle(path)?;
+    let address = session.deploy(wasm, "new", args, vec![salt], None, &transcoder)?;
+    Ok(Contract {
+        address,
+        transcoder,
+    })
+}
+
+fn call(
+    session: &mut Session<MinimalRuntime>,
+    contract: &Contract,
+    message: &str,
+    args: &[String],
+) -> Result<u64, Box<dyn Error>> {
+    session.set_transcoder(contract.address.clone(), &contract.transcoder);
+    session.call_with_address::<_, ()>(contract.address.clone(), message, args, None)??;
+    Ok(session.record().last_call_result().gas_consumed.ref_time())
+}
+
+fn buyer(index: u32) -> AccountId32 {
+    let mut raw = [1u8; 32];
+    raw[..4].copy_from_slice(&index.to_le_bytes());
+    AccountId32::new(raw)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut session = Session::<MinimalRuntime>::new()?;
+    let owner = session.get_actor();
+
+    let asset = deploy(&mut session, TOKEN_BUNDLE, &[SUPPLY.to_string()], 0)?;
+    let payment = deploy(&mut session, TOKEN_BUNDLE, &[SUPPLY.to_string()], 1)?;
+    let auction = deploy(
+        &mut session,
+        AUCTION_BUNDLE,
+        &[format!(
+            "AuctionParams {{ asset_token: {}, payment_token: {}, start_price: 1000000, \
+             min_price: 1000, end_time: 1000000, weights: None, burn_proceeds: false, \
+             payment_fee_tolerance_bps: None, tick_size: 0, lot_size: 0, proceeds_cap: None, \
+             release_rate: None, grace_period: 0 }}",
+            asset.address, payment.address
+        )],
+        2,
+    )?;
+    call(
+        &mut session,
+        &asset,
+        "PSP22::transfer",
+        &[auction.address.to_string(), SUPPLY.to_string(), "[]".into()],
+    )?;
+
+    println!(
+        "{:>8} {:>14} {:>14} {:>14}",
+        "history", "buy", "price", "propose_change"
+    );
+
+    let mut purchases = 0;
+    for size in HISTORY_SIZES {
+        while purchases < size {
+            let buyer = buyer(purchases);
+            session.set_actor(owner.clone());
+            call(
+                &mut session,
+                &payment,
+                "PSP22::transfer",
+                &[buyer.to_string(), "1000000000".into(), "[]".into()],
+            )?;
+            session.set_actor(buyer);
+            call(
+                &mut session,
+                &payment,
+                "PSP22::approve",
+                &[auction.address.to_string(), "1000000000".into()],
+            )?;
+            call(
+                &mut session,
+                &auction,
+                "DutchAuction::buy",
+                &["1".into(), "None".into(), "false".into(), "None".into(), "[]".into()],
+            )?;
+            purchases += 1;
+        }
+
+        session.set_actor(buyer(0));
+        call(
+            &mut session,
+            &payment,
+            "PSP22::approve",
+            &[auction.address.to_string(), "1000000000".into()],
+        )?;
+        let buy = call(
+            &mut session,
+            &auction,
+            "DutchAuction::buy",
+            &["1".into(), "None".into(), "false".into(), "None".into(), "[]".into()],
+        )?;
+        purchases += 1;
+        let price = call(&mut session, &auction, "DutchAuction::price", NO_ARGS)?;
+
+        session.set_actor(owner.clone());
+        let propose_change = call(
+            &mut session,
+            &auction,
+            "propose_change",
+            &["MinPrice(1000)".into()],
+        )?;
+
+        println!("{size:>8} {buy:>14} {price:>14} {propose_change:>14}");
+    }
+
+    Ok(())
+}
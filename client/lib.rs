@@ -0,0 +1,501 @@
+//! Dutch Auction Client
+//!
+//! Typed access to a deployed Dutch auction for bots and backends written in Rust, without
+//! hand-written SCALE encoding.
+//!
+//! The client encodes message calls, decodes their results and decodes the events emitted by
+//! the auction. Talking to the chain is left to a `Transport`, typically a thin wrapper around
+//! subxt submitting the `Contracts::call` extrinsic and dry-running the `ContractsApi::call`
+//! runtime API with the signer's account.
+//!
+//! Types:
+//! - Transport: Dry-runs and submits contract calls on behalf of a signer.
+//! - ClientError: An error of the transport, of decoding or reported by a contract.
+//! - AuctionClient: Wraps the messages of one auction.
+//!
+//! Functions:
+//! - decode_event: Decodes the data of an event emitted by the auction.
+
+use dutch_auction::dutch_auction::{
+    AuctionState, BuyOrder, BuySimulation, Config, DutchAuction, Progress,
+};
+pub use dutch_auction_traits::DutchAuctionError;
+use ink::{primitives::AccountId, LangError};
+use psp22::PSP22Error;
+use scale::{Decode, Encode};
+
+type BlockNumber = u32;
+
+/// Any event emitted by the auction.
+pub type Event = <DutchAuction as ink::reflect::ContractEventBase>::Type;
+
+/// Submits contract calls to the chain on behalf of a signer.
+pub trait Transport {
+    type Error;
+
+    /// The account signing the submitted calls and used as the origin of dry runs.
+    fn signer(&self) -> AccountId;
+
+    /// Dry-runs a call of `contract` with the SCALE encoded `input`, selector first, and returns
+    /// the raw return data.
+    fn dry_run(&self, contract: AccountId, input: Vec<u8>) -> Result<Vec<u8>, Self::Error>;
+
+    /// Submits a call of `contract` with `input` transferring `value` and waits for its
+    /// inclusion. Returns the data of the events `contract` emitted in the call.
+    fn submit(
+        &mut self,
+        contract: AccountId,
+        input: Vec<u8>,
+        value: u128,
+    ) -> Result<Vec<Vec<u8>>, Self::Error>;
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ClientError<E> {
+    /// The transport failed to reach the chain or the call was reverted.
+    Transport(E),
+    /// The return data or an event couldn't be decoded.
+    Decode(scale::Error),
+    /// The contract couldn't dispatch the call, e.g. because of an unknown selector.
+    Lang(LangError),
+    /// The auction rejected the call.
+    Auction(DutchAuctionError),
+    /// The payment token rejected the approval.
+    Token(PSP22Error),
+}
+
+impl<E> From<scale::Error> for ClientError<E> {
+    fn from(error: scale::Error) -> Self {
+        ClientError::Decode(error)
+    }
+}
+
+impl<E> From<LangError> for ClientError<E> {
+    fn from(error: LangError) -> Self {
+        ClientError::Lang(error)
+    }
+}
+
+impl<E> From<DutchAuctionError> for ClientError<E> {
+    fn from(error: DutchAuctionError) -> Self {
+        ClientError::Auction(error)
+    }
+}
+
+impl<E> From<PSP22Error> for ClientError<E> {
+    fn from(error: PSP22Error) -> Self {
+        ClientError::Token(error)
+    }
+}
+
+/// Decodes the data of an event emitted by the auction.
+pub fn decode_event(mut data: &[u8]) -> Result<Event, scale::Error> {
+    Event::decode(&mut data)
+}
+
+/// Encodes a call of the message with `selector` and the SCALE encoded `args`.
+fn call_data(selector: [u8; 4], args: impl Encode) -> Vec<u8> {
+    let mut input = selector.to_vec();
+    args.encode_to(&mut input);
+    input
+}
+
+/// Wraps the messages of the auction deployed at `contract`.
+///
+/// The `DutchAuction` trait messages, the purchase helpers and the most used views have typed
+/// wrappers. Any other message can be called through `query` and `execute` with its selector,
+/// the Blake2x256 hash of its name, e.g. `ink::selector_bytes!("leaderboard")`.
+pub struct AuctionClient<T> {
+    contract: AccountId,
+    transport: T,
+}
+
+impl<T: Transport> AuctionClient<T> {
+    pub fn new(contract: AccountId, transport: T) -> Self {
+        Self {
+            contract,
+            transport,
+        }
+    }
+
+    /// The account ID of the auction.
+    pub fn contract(&self) -> AccountId {
+        self.contract
+    }
+
+    /// Dry-runs the message with `selector` of `contract` and decodes its return value.
+    fn dry_run<R: Decode>(
+        &self,
+        contract: AccountId,
+        selector: [u8; 4],
+        args: impl Encode,
+    ) -> Result<R, ClientError<T::Error>> {
+        let output = self
+            .transport
+            .dry_run(contract, call_data(selector, args))
+            .map_err(ClientError::Transport)?;
+
+        Ok(ink::MessageResult::<R>::decode(&mut &output[..])??)
+    }
+
+    /// Dry-runs the auction message with `selector` and decodes its return value.
+    pub fn query<R: Decode>(
+        &self,
+        selector: [u8; 4],
+        args: impl Encode,
+    ) -> Result<R, ClientError<T::Error>> {
+        self.dry_run(self.contract, selector, args)
+    }
+
+    /// Submits the auction message with `selector`, transferring `value`, and decodes the
+    /// events it emitted.
+    ///
+    /// The message is dry-run first, so an error returned by the auction is reported without
+    /// submitting a transaction.
+    pub fn execute(
+        &mut self,
+        selector: [u8; 4],
+        args: impl Encode + Clone,
+        value: u128,
+    ) -> Result<Vec<Event>, ClientError<T::Error>> {
+        self.query::<Result<(), DutchAuctionError>>(selector, args.clone())
+            .or_else(|error| match error {
+                // Messages returning values other than `()` fail to decode as `Ok(())`.
+                ClientError::Decode(_) => Ok(Ok(())),
+                error => Err(error),
+            })??;
+
+        self.transport
+            .submit(self.contract, call_data(selector, args), value)
+            .map_err(ClientError::Transport)?
+            .iter()
+            .map(|data| Ok(decode_event(data)?))
+            .collect()
+    }
+
+    pub fn asset_token(&self) -> Result<AccountId, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("DutchAuction::asset_token"), ())
+    }
+
+    pub fn payment_token(&self) -> Result<AccountId, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("DutchAuction::payment_token"), ())
+    }
+
+    pub fn start_block(&self) -> Result<BlockNumber, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("DutchAuction::start_block"), ())
+    }
+
+    pub fn end_time(&self) -> Result<BlockNumber, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("DutchAuction::end_time"), ())
+    }
+
+    pub fn price(&self) -> Result<u128, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("DutchAuction::price"), ())
+    }
+
+    pub fn price_at(&self, block: BlockNumber) -> Result<u128, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("DutchAuction::price_at"), block)
+    }
+
+    pub fn available_asset(&self) -> Result<u128, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("DutchAuction::available_asset"), ())
+    }
+
+    pub fn min_price(&self) -> Result<u128, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("DutchAuction::min_price"), ())
+    }
+
+    pub fn sold(&self) -> Result<u128, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("DutchAuction::sold"), ())
+    }
+
+    pub fn config(&self) -> Result<Config, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("config"), ())
+    }
+
+    pub fn state(&self) -> Result<AuctionState, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("state"), ())
+    }
+
+    pub fn progress(&self) -> Result<Progress, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("progress"), ())
+    }
+
+    pub fn events_emitted(&self) -> Result<u64, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("events_emitted"), ())
+    }
+
+    pub fn allocation_of(&self, account: AccountId) -> Result<u128, ClientError<T::Error>> {
+        self.query(ink::selector_bytes!("allocation_of"), account)
+    }
+
+    /// The cost breakdown of the signer buying `amount` asset tokens at the current block.
+    pub fn simulate_buy(&self, amount: u128) -> Result<BuySimulation, ClientError<T::Error>> {
+        let account = self.transport.signer();
+        self.query(ink::selector_bytes!("simulate_buy"), (account, amount))
+    }
+
+    /// The payment tokens the signer has approved the auction to spend.
+    pub fn allowance(&self) -> Result<u128, ClientError<T::Error>> {
+        let owner = self.transport.signer();
+        self.dry_run(
+            self.payment_token()?,
+            ink::selector_bytes!("PSP22::allowance"),
+            (owner, self.contract),
+        )
+    }
+
+    /// Approve the auction to spend `value` payment tokens of the signer.
+    pub fn approve(&mut self, value: u128) -> Result<(), ClientError<T::Error>> {
+        let token = self.payment_token()?;
+        let selector = ink::selector_bytes!("PSP22::approve");
+        self.dry_run::<Result<(), PSP22Error>>(token, selector, (self.contract, value))??;
+        self.transport
+            .submit(token, call_data(selector, (self.contract, value)), 0)
+            .map_err(ClientError::Transport)?;
+
+        Ok(())
+    }
+
+    /// Buy `amount` asset tokens by transferring `value` payment tokens to the auction along
+    /// with the order, without an approval.
+    ///
+    /// `value` caps the price paid, any remainder is credited to the signer by the auction.
+    pub fn buy_with_transfer(
+        &mut self,
+        amount: u128,
+        max_price: Option<u128>,
+        value: u128,
+    ) -> Result<(), ClientError<T::Error>> {
+        let token = self.payment_token()?;
+        let selector = ink::selector_bytes!("PSP22::transfer");
+        let args = (
+            self.contract,
+            value,
+            BuyOrder { amount, max_price }.encode(),
+        );
+        self.dry_run::<Result<(), PSP22Error>>(token, selector, &args)??;
+        self.transport
+            .submit(token, call_data(selector, &args), 0)
+            .map_err(ClientError::Transport)?;
+
+        Ok(())
+    }
+
+    /// Buy `amount` asset tokens at the current price, approving the payment first if the
+    /// signer's allowance doesn't cover it.
+    ///
+    /// The purchase is simulated first, so errors like an exceeded `max_price` or insufficient
+    /// supply are reported without submitting a transaction. Returns the events emitted by the
+    /// purchase, including `AssetBought`, which carries `data`.
+    pub fn buy(
+        &mut self,
+        amount: u128,
+        max_price: Option<u128>,
+        allow_partial: bool,
+        deliver_to: Option<AccountId>,
+        data: Vec<u8>,
+    ) -> Result<Vec<Event>, ClientError<T::Error>> {
+        let simulation = self.simulate_buy(amount)?;
+        if let Some(error) = simulation.error {
+            // Partial fills are re-quoted by the auction, which the simulation doesn't do.
+            if !(allow_partial && error == DutchAuctionError::InsufficientSupplyToken) {
+                return Err(error.into());
+            }
+        }
+        let required = max_price.unwrap_or(simulation.total).max(simulation.total);
+        if self.allowance()? < required {
+            self.approve(required)?;
+        }
+
+        let selector = ink::selector_bytes!("DutchAuction::buy");
+        let args = (amount, max_price, allow_partial, deliver_to, data);
+        self.query::<Result<u128, DutchAuctionError>>(selector, &args)??;
+        self.transport
+            .submit(self.contract, call_data(selector, &args), 0)
+            .map_err(ClientError::Transport)?
+            .iter()
+            .map(|data| Ok(decode_event(data)?))
+            .collect()
+    }
+
+    /// Claim the signer's allocation of an all-or-nothing or deferred-delivery sale.
+    pub fn claim_assets(&mut self) -> Result<Vec<Event>, ClientError<T::Error>> {
+        self.execute(ink::selector_bytes!("claim_assets"), (), 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Answers dry runs from canned return data, keyed by contract and selector, and records
+    /// submitted calls.
+    #[derive(Default)]
+    struct MockTransport {
+        responses: HashMap<(AccountId, [u8; 4]), Vec<u8>>,
+        submitted: Vec<(AccountId, Vec<u8>)>,
+    }
+
+    impl MockTransport {
+        fn respond(&mut self, contract: AccountId, selector: [u8; 4], value: impl Encode) {
+            self.responses
+                .insert((contract, selector), ink::MessageResult::Ok(value).encode());
+        }
+    }
+
+    impl Transport for MockTransport {
+        type Error = ();
+
+        fn signer(&self) -> AccountId {
+            AccountId::from([5; 32])
+        }
+
+        fn dry_run(&self, contract: AccountId, input: Vec<u8>) -> Result<Vec<u8>, ()> {
+            let selector = input[..4].try_into().unwrap();
+            self.responses.get(&(contract, selector)).cloned().ok_or(())
+        }
+
+        fn submit(
+            &mut self,
+            contract: AccountId,
+            input: Vec<u8>,
+            _value: u128,
+        ) -> Result<Vec<Vec<u8>>, ()> {
+            self.submitted.push((contract, input));
+            Ok(Vec::new())
+        }
+    }
+
+    const AUCTION: [u8; 32] = [1; 32];
+    const PAYMENT: [u8; 32] = [2; 32];
+
+    fn mock_client(allowance: u128, simulation: BuySimulation) -> AuctionClient<MockTransport> {
+        let (auction, payment) = (AccountId::from(AUCTION), AccountId::from(PAYMENT));
+        let mut transport = MockTransport::default();
+        transport.respond(
+            auction,
+            ink::selector_bytes!("DutchAuction::payment_token"),
+            payment,
+        );
+        transport.respond(auction, ink::selector_bytes!("simulate_buy"), simulation);
+        transport.respond(
+            auction,
+            ink::selector_bytes!("DutchAuction::buy"),
+            Ok::<u128, DutchAuctionError>(2),
+        );
+        transport.respond(payment, ink::selector_bytes!("PSP22::allowance"), allowance);
+        transport.respond(
+            payment,
+            ink::selector_bytes!("PSP22::approve"),
+            Ok::<(), PSP22Error>(()),
+        );
+
+        AuctionClient::new(auction, transport)
+    }
+
+    #[test]
+    fn buy_approves_missing_allowance_first() {
+        let simulation = BuySimulation {
+            total: 100,
+            ..Default::default()
+        };
+        let mut client = mock_client(99, simulation);
+        assert_eq!(
+            client
+                .buy(2, None, false, None, vec![7])
+                .map(|events| events.len()),
+            Ok(0)
+        );
+
+        let submitted = &client.transport.submitted;
+        assert_eq!(submitted.len(), 2);
+        assert_eq!(
+            submitted[0],
+            (
+                AccountId::from(PAYMENT),
+                call_data(
+                    ink::selector_bytes!("PSP22::approve"),
+                    (AccountId::from(AUCTION), 100u128)
+                )
+            )
+        );
+        assert_eq!(
+            submitted[1],
+            (
+                AccountId::from(AUCTION),
+                call_data(
+                    ink::selector_bytes!("DutchAuction::buy"),
+                    (2u128, None::<u128>, false, None::<AccountId>, vec![7u8])
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn buy_with_transfer_encodes_the_order() {
+        let mut client = mock_client(0, BuySimulation::default());
+        client.transport.respond(
+            AccountId::from(PAYMENT),
+            ink::selector_bytes!("PSP22::transfer"),
+            Ok::<(), PSP22Error>(()),
+        );
+        assert_eq!(client.buy_with_transfer(2, None, 100), Ok(()));
+
+        let order = BuyOrder {
+            amount: 2,
+            max_price: None,
+        };
+        assert_eq!(
+            client.transport.submitted,
+            vec![(
+                AccountId::from(PAYMENT),
+                call_data(
+                    ink::selector_bytes!("PSP22::transfer"),
+                    (AccountId::from(AUCTION), 100u128, order.encode())
+                )
+            )]
+        );
+    }
+
+    #[test]
+    fn buy_skips_approval_and_submission_when_not_needed() {
+        let simulation = BuySimulation {
+            total: 100,
+            ..Default::default()
+        };
+        let mut client = mock_client(100, simulation);
+        assert!(client.buy(2, None, false, None, vec![]).is_ok());
+        assert_eq!(client.transport.submitted.len(), 1);
+
+        let simulation = BuySimulation {
+            error: Some(DutchAuctionError::MaxPriceExceeded),
+            ..Default::default()
+        };
+        let mut client = mock_client(100, simulation);
+        assert_eq!(
+            client
+                .buy(2, Some(1), false, None, vec![])
+                .map(|events| events.len()),
+            Err(ClientError::Auction(DutchAuctionError::MaxPriceExceeded))
+        );
+        assert!(client.transport.submitted.is_empty());
+    }
+
+    #[test]
+    fn lang_errors_are_reported() {
+        let mut client = mock_client(0, BuySimulation::default());
+        client.transport.responses.insert(
+            (
+                AccountId::from(AUCTION),
+                ink::selector_bytes!("DutchAuction::price"),
+            ),
+            ink::MessageResult::<u128>::Err(LangError::CouldNotReadInput).encode(),
+        );
+        assert_eq!(
+            client.price(),
+            Err(ClientError::Lang(LangError::CouldNotReadInput))
+        );
+    }
+}
@@ -0,0 +1,417 @@
+//! End-to-end tests for the Dutch auction contract.
+//!
+//! The tests deploy two instances of `mock_psp22` (the asset and the payment token) and the
+//! auction itself to a running `substrate-contracts-node`, then exercise purchases, price decay
+//! across blocks, owner-only messages and the main failure paths. `treasury_buyer` is deployed
+//! to cover purchases by another contract, `auction_registry` to cover registration.
+//!
+//! Run with:
+//!
+//! ```Bash
+//! cd e2e
+//! cargo test --features e2e-tests
+//! ```
+
+#[cfg(all(test, feature = "e2e-tests"))]
+mod e2e_tests {
+    use auction_registry::auction_registry::{AuctionStatus, RegistryRef};
+    use dutch_auction::dutch_auction::{AuctionParams, DutchAuctionRef, Error, ParameterChange};
+    use dutch_auction_traits::{DutchAuction, Ownable};
+    use ink::primitives::AccountId;
+    use ink_e2e::{build_message, AccountKeyring, PolkadotConfig};
+    use mock_psp22::mock_psp22::TokenRef;
+    use psp22::PSP22;
+    use treasury_buyer::treasury_buyer::{Error as TreasuryError, TreasuryBuyerRef};
+
+    type E2EResult<T> = Result<T, Box<dyn std::error::Error>>;
+    type Client = ink_e2e::Client<PolkadotConfig, ink::env::DefaultEnvironment>;
+
+    const SUPPLY: u128 = 1_000_000_000;
+    const START_PRICE: u128 = 1_000;
+    const MIN_PRICE: u128 = 100;
+    const DURATION: u32 = 20;
+
+    struct Setup {
+        asset: AccountId,
+        payment: AccountId,
+        auction: AccountId,
+    }
+
+    /// Deploys the tokens and an auction owned by Alice, then funds the auction and Bob.
+    ///
+    /// The auction's `end_time` is set `DURATION` blocks after its start block.
+    async fn setup(client: &mut Client) -> E2EResult<Setup> {
+        let asset = client
+            .instantiate(
+                "mock_psp22",
+                &ink_e2e::alice(),
+                TokenRef::new(SUPPLY),
+                0,
+                None,
+            )
+            .await
+            .expect("asset instantiate failed")
+            .account_id;
+        let payment = client
+            .instantiate(
+                "mock_psp22",
+                &ink_e2e::alice(),
+                TokenRef::new(SUPPLY),
+                0,
+                None,
+            )
+            .await
+            .expect("payment instantiate failed")
+            .account_id;
+
+        let constructor = DutchAuctionRef::new(AuctionParams {
+            asset_token: asset,
+            payment_token: payment,
+            start_price: START_PRICE,
+            min_price: MIN_PRICE,
+            end_time: u32::MAX,
+            ..Default::default()
+        });
+        let auction = client
+            .instantiate("dutch_auction", &ink_e2e::alice(), constructor, 0, None)
+            .await
+            .expect("auction instantiate failed")
+            .account_id;
+
+        let start_block = build_message::<DutchAuctionRef>(auction).call(|a| a.start_block());
+        let start_block = client
+            .call_dry_run(&ink_e2e::alice(), &start_block, 0, None)
+            .await
+            .return_value();
+        let propose_end_time = build_message::<DutchAuctionRef>(auction)
+            .call(|a| a.propose_change(ParameterChange::EndTime(start_block + DURATION)));
+        let id = client
+            .call(&ink_e2e::alice(), propose_end_time, 0, None)
+            .await
+            .expect("propose_change failed")
+            .return_value()
+            .expect("end time rejected");
+        let execute = build_message::<DutchAuctionRef>(auction).call(|a| a.execute_change(id));
+        client
+            .call(&ink_e2e::alice(), execute, 0, None)
+            .await
+            .expect("execute_change failed");
+
+        let deposit =
+            build_message::<TokenRef>(asset).call(|t| t.transfer(auction, SUPPLY, Vec::new()));
+        client
+            .call(&ink_e2e::alice(), deposit, 0, None)
+            .await
+            .expect("asset deposit failed");
+
+        let fund_bob =
+            build_message::<TokenRef>(payment).call(|t| t.transfer(bob(), SUPPLY / 2, Vec::new()));
+        client
+            .call(&ink_e2e::alice(), fund_bob, 0, None)
+            .await
+            .expect("funding bob failed");
+
+        Ok(Setup {
+            asset,
+            payment,
+            auction,
+        })
+    }
+
+    fn bob() -> AccountId {
+        ink_e2e::account_id(AccountKeyring::Bob)
+    }
+
+    fn alice() -> AccountId {
+        ink_e2e::account_id(AccountKeyring::Alice)
+    }
+
+    async fn balance_of(client: &mut Client, token: AccountId, owner: AccountId) -> u128 {
+        let balance_of = build_message::<TokenRef>(token).call(|t| t.balance_of(owner));
+        client
+            .call_dry_run(&ink_e2e::alice(), &balance_of, 0, None)
+            .await
+            .return_value()
+    }
+
+    async fn price(client: &mut Client, auction: AccountId) -> u128 {
+        let price = build_message::<DutchAuctionRef>(auction).call(|a| a.price());
+        client
+            .call_dry_run(&ink_e2e::alice(), &price, 0, None)
+            .await
+            .return_value()
+    }
+
+    async fn approve_auction(client: &mut Client, setup: &Setup, value: u128) {
+        let approve =
+            build_message::<TokenRef>(setup.payment).call(|t| t.approve(setup.auction, value));
+        client
+            .call(&ink_e2e::bob(), approve, 0, None)
+            .await
+            .expect("approve failed");
+    }
+
+    #[ink_e2e::test(
+        additional_contracts = "../Cargo.toml ../mock_psp22/Cargo.toml ../treasury_buyer/Cargo.toml"
+    )]
+    async fn contracts_buy_through_the_trait(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+        let setup = setup(&mut client).await?;
+        let constructor = TreasuryBuyerRef::new(setup.auction, MIN_PRICE);
+        let treasury = client
+            .instantiate("treasury_buyer", &ink_e2e::alice(), constructor, 0, None)
+            .await
+            .expect("treasury instantiate failed")
+            .account_id;
+        let fund = build_message::<TokenRef>(setup.payment)
+            .call(|t| t.transfer(treasury, 100 * START_PRICE, Vec::new()));
+        client
+            .call(&ink_e2e::alice(), fund, 0, None)
+            .await
+            .expect("funding treasury failed");
+
+        let try_buy = build_message::<TreasuryBuyerRef>(treasury).call(|t| t.try_buy(10));
+        let result = client
+            .call_dry_run(&ink_e2e::bob(), &try_buy, 0, None)
+            .await
+            .return_value();
+        assert_eq!(result, Err(TreasuryError::PriceAboveTarget));
+
+        let set_target =
+            build_message::<TreasuryBuyerRef>(treasury).call(|t| t.set_target_price(START_PRICE));
+        client
+            .call(&ink_e2e::alice(), set_target, 0, None)
+            .await
+            .expect("set_target_price failed");
+        client
+            .call(&ink_e2e::bob(), try_buy, 0, None)
+            .await
+            .expect("try_buy failed");
+
+        assert_eq!(balance_of(&mut client, setup.asset, treasury).await, 10);
+        let allowance =
+            build_message::<TokenRef>(setup.payment).call(|t| t.allowance(treasury, setup.auction));
+        let allowance = client
+            .call_dry_run(&ink_e2e::alice(), &allowance, 0, None)
+            .await
+            .return_value();
+        assert_eq!(allowance, 0);
+
+        Ok(())
+    }
+
+    #[ink_e2e::test(additional_contracts = "../Cargo.toml ../mock_psp22/Cargo.toml")]
+    async fn buy_transfers_asset_and_payment(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+        let setup = setup(&mut client).await?;
+        approve_auction(&mut client, &setup, u128::MAX).await;
+        let owner_payment_before = balance_of(&mut client, setup.payment, alice()).await;
+
+        let buy = build_message::<DutchAuctionRef>(setup.auction)
+            .call(|a| a.buy(10, None, false, None, vec![]));
+        client
+            .call(&ink_e2e::bob(), buy, 0, None)
+            .await
+            .expect("buy failed");
+
+        assert_eq!(balance_of(&mut client, setup.asset, bob()).await, 10);
+        let owner_payment_after = balance_of(&mut client, setup.payment, alice()).await;
+        let charged = owner_payment_after - owner_payment_before;
+        assert!((10 * MIN_PRICE..=10 * START_PRICE).contains(&charged));
+
+        let sold = build_message::<DutchAuctionRef>(setup.auction).call(|a| a.sold());
+        let sold = client
+            .call_dry_run(&ink_e2e::alice(), &sold, 0, None)
+            .await
+            .return_value();
+        assert_eq!(sold, 10);
+
+        Ok(())
+    }
+
+    #[ink_e2e::test(additional_contracts = "../Cargo.toml ../mock_psp22/Cargo.toml")]
+    async fn buys_continue_after_renouncing(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+        let setup = setup(&mut client).await?;
+        approve_auction(&mut client, &setup, u128::MAX).await;
+
+        let renounce =
+            build_message::<DutchAuctionRef>(setup.auction).call(|a| a.renounce_ownership());
+        client
+            .call(&ink_e2e::alice(), renounce, 0, None)
+            .await
+            .expect("renounce_ownership failed");
+        let owner = build_message::<DutchAuctionRef>(setup.auction).call(|a| a.owner());
+        let owner = client
+            .call_dry_run(&ink_e2e::alice(), &owner, 0, None)
+            .await
+            .return_value();
+        assert_eq!(owner, None);
+
+        // The supply is escrowed by the auction, so it no longer depends on the owner account.
+        let treasury_before = balance_of(&mut client, setup.payment, alice()).await;
+        let buy = build_message::<DutchAuctionRef>(setup.auction)
+            .call(|a| a.buy(10, None, false, None, vec![]));
+        client
+            .call(&ink_e2e::bob(), buy, 0, None)
+            .await
+            .expect("buy failed");
+
+        assert_eq!(balance_of(&mut client, setup.asset, bob()).await, 10);
+        let treasury_after = balance_of(&mut client, setup.payment, alice()).await;
+        assert!(treasury_after > treasury_before);
+
+        Ok(())
+    }
+
+    #[ink_e2e::test(additional_contracts = "../Cargo.toml ../mock_psp22/Cargo.toml")]
+    async fn price_decays_across_blocks(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+        let setup = setup(&mut client).await?;
+        let first = price(&mut client, setup.auction).await;
+
+        // Every submitted extrinsic produces a new block on the dev node.
+        for _ in 0..3 {
+            approve_auction(&mut client, &setup, 1).await;
+        }
+        let second = price(&mut client, setup.auction).await;
+        assert!(second < first);
+        assert!(second >= MIN_PRICE);
+
+        let end_time = build_message::<DutchAuctionRef>(setup.auction).call(|a| a.end_time());
+        let end_time = client
+            .call_dry_run(&ink_e2e::alice(), &end_time, 0, None)
+            .await
+            .return_value();
+        let at_end = build_message::<DutchAuctionRef>(setup.auction).call(|a| a.price_at(end_time));
+        let at_end = client
+            .call_dry_run(&ink_e2e::alice(), &at_end, 0, None)
+            .await
+            .return_value();
+        assert_eq!(at_end, MIN_PRICE);
+
+        Ok(())
+    }
+
+    #[ink_e2e::test(additional_contracts = "../Cargo.toml ../mock_psp22/Cargo.toml")]
+    async fn only_owner_can_mutate(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+        let setup = setup(&mut client).await?;
+
+        let propose_min_price = build_message::<DutchAuctionRef>(setup.auction)
+            .call(|a| a.propose_change(ParameterChange::MinPrice(200)));
+        let result = client
+            .call_dry_run(&ink_e2e::bob(), &propose_min_price, 0, None)
+            .await
+            .return_value();
+        assert_eq!(result, Err(Error::NotAuctionOwner));
+
+        let id = client
+            .call(&ink_e2e::alice(), propose_min_price, 0, None)
+            .await
+            .expect("propose_change failed")
+            .return_value()
+            .expect("min price rejected");
+        let execute =
+            build_message::<DutchAuctionRef>(setup.auction).call(|a| a.execute_change(id));
+        let result = client
+            .call_dry_run(&ink_e2e::bob(), &execute, 0, None)
+            .await
+            .return_value();
+        assert_eq!(result, Err(Error::NotAuctionOwner));
+        client
+            .call(&ink_e2e::alice(), execute, 0, None)
+            .await
+            .expect("execute_change failed");
+        let min_price = build_message::<DutchAuctionRef>(setup.auction).call(|a| a.min_price());
+        let min_price = client
+            .call_dry_run(&ink_e2e::alice(), &min_price, 0, None)
+            .await
+            .return_value();
+        assert_eq!(min_price, 200);
+
+        Ok(())
+    }
+
+    #[ink_e2e::test(additional_contracts = "../Cargo.toml ../mock_psp22/Cargo.toml")]
+    async fn buy_failure_paths(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+        let setup = setup(&mut client).await?;
+
+        let buy = build_message::<DutchAuctionRef>(setup.auction)
+            .call(|a| a.buy(10, None, false, None, vec![]));
+        let result = client
+            .call_dry_run(&ink_e2e::bob(), &buy, 0, None)
+            .await
+            .return_value();
+        assert_eq!(result, Err(Error::InsufficientAllowance));
+
+        approve_auction(&mut client, &setup, u128::MAX).await;
+
+        let buy = build_message::<DutchAuctionRef>(setup.auction)
+            .call(|a| a.buy(0, None, false, None, vec![]));
+        let result = client
+            .call_dry_run(&ink_e2e::bob(), &buy, 0, None)
+            .await
+            .return_value();
+        assert_eq!(result, Err(Error::ZeroAmount));
+
+        let buy = build_message::<DutchAuctionRef>(setup.auction)
+            .call(|a| a.buy(10, Some(10 * MIN_PRICE - 1), false, None, vec![]));
+        let result = client
+            .call_dry_run(&ink_e2e::bob(), &buy, 0, None)
+            .await
+            .return_value();
+        assert_eq!(result, Err(Error::MaxPriceExceeded));
+
+        Ok(())
+    }
+
+    #[ink_e2e::test(
+        additional_contracts = "../Cargo.toml ../mock_psp22/Cargo.toml ../auction_registry/Cargo.toml"
+    )]
+    async fn auctions_register_themselves(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+        let setup = setup(&mut client).await?;
+        let registry = client
+            .instantiate(
+                "auction_registry",
+                &ink_e2e::alice(),
+                RegistryRef::new(),
+                0,
+                None,
+            )
+            .await
+            .expect("registry instantiate failed")
+            .account_id;
+
+        let register =
+            build_message::<DutchAuctionRef>(setup.auction).call(|a| a.register_in(registry));
+        let result = client
+            .call_dry_run(&ink_e2e::bob(), &register, 0, None)
+            .await
+            .return_value();
+        assert_eq!(result, Err(Error::NotAuctionOwner));
+        client
+            .call(&ink_e2e::alice(), register, 0, None)
+            .await
+            .expect("register_in failed");
+
+        let active = build_message::<RegistryRef>(registry).call(|r| r.active_auctions(0, 10));
+        let active = client
+            .call_dry_run(&ink_e2e::alice(), &active, 0, None)
+            .await
+            .return_value();
+        assert_eq!(active.len(), 1);
+        let (auction, entry) = &active[0];
+        assert_eq!(*auction, setup.auction);
+        assert_eq!(
+            (entry.asset_token, entry.payment_token),
+            (setup.asset, setup.payment)
+        );
+        assert_eq!(entry.status, AuctionStatus::Active);
+
+        // The auction is still selling, so only the auction itself could close it.
+        let close = build_message::<RegistryRef>(registry).call(|r| r.close(setup.auction));
+        let result = client
+            .call_dry_run(&ink_e2e::bob(), &close, 0, None)
+            .await
+            .return_value();
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+//! Fuzzes the auction parameters and sequences of setter calls, block advances and purchases
+//! against a model of the price curve.
+//!
+//! Runs in the off-chain environment, where cross-contract calls are unavailable. The auction
+//! mints on demand and runs an all-or-nothing sale, and purchases are made by the auction's own
+//! account, whose payments are escrowed from its own balance without calling the payment token.
+//! The charged total is read from `total_raised`. Failed messages aren't reverted off-chain, so
+//! a failed purchase ends the sequence.
+//!
+//! The target asserts that no message panics, that the charged total never exceeds the quoted
+//! total, the buyer's `max_price` or the model's ceiling and that the price never increases
+//! while the parameters are unchanged.
+//!
+//! Requires a nightly toolchain and cargo-fuzz:
+//!
+//! ```Bash
+//! cargo +nightly fuzz run buy
+//! ```
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use dutch_auction::dutch_auction::{AuctionParams, DutchAuction, ParameterChange, VolumeTier};
+use dutch_auction_traits::DutchAuction as _;
+use ink::{
+    env::{test, DefaultEnvironment},
+    primitives::AccountId,
+};
+use libfuzzer_sys::fuzz_target;
+
+const OWNER: [u8; 32] = [1; 32];
+
+#[derive(Arbitrary, Debug)]
+struct Params {
+    start_price: u128,
+    min_price: u128,
+    duration: u32,
+    sale_cap: u128,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    AdvanceBlocks(u16),
+    SetTickSize(u128),
+    SetLotSize(u128),
+    SetVolumeTiers(Vec<(u128, u16)>),
+    Buy {
+        amount: u128,
+        max_price: Option<u128>,
+    },
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    params: Params,
+    ops: Vec<Op>,
+}
+
+/// The sale parameters the auction is expected to price by.
+struct Model {
+    start_price: u128,
+    min_price: u128,
+    tick_size: u128,
+}
+
+impl Model {
+    /// No purchase may be charged more per asset token than the start price, rounded up to the
+    /// tick size.
+    fn ceiling(&self) -> u128 {
+        let price = self.start_price.max(self.min_price);
+        if self.tick_size <= 1 {
+            return price;
+        }
+
+        price
+            .div_ceil(self.tick_size)
+            .checked_mul(self.tick_size)
+            .unwrap_or(u128::MAX - u128::MAX % self.tick_size)
+    }
+}
+
+fn apply(auction: &mut DutchAuction, change: ParameterChange) -> bool {
+    auction
+        .propose_change(change)
+        .and_then(|id| auction.execute_change(id))
+        .is_ok()
+}
+
+fn run(input: Input) {
+    let Params {
+        start_price,
+        min_price,
+        duration,
+        sale_cap,
+    } = input.params;
+    test::set_caller::<DefaultEnvironment>(AccountId::from(OWNER));
+    let Ok(mut auction) = DutchAuction::new(AuctionParams {
+        asset_token: AccountId::from([2; 32]),
+        payment_token: AccountId::from([3; 32]),
+        start_price,
+        min_price,
+        end_time: duration,
+        ..Default::default()
+    }) else {
+        return;
+    };
+    auction
+        .set_mint_on_demand(Some(sale_cap))
+        .expect("owner can mint on demand before the sale");
+    auction
+        .set_raise_goal(Some(u128::MAX))
+        .expect("owner can escrow payments before the sale");
+
+    let mut model = Model {
+        start_price,
+        min_price,
+        tick_size: 0,
+    };
+    let mut last_price = None;
+    for op in input.ops {
+        match op {
+            Op::AdvanceBlocks(blocks) => {
+                for _ in 0..blocks {
+                    test::advance_block::<DefaultEnvironment>();
+                }
+            }
+            Op::SetTickSize(tick_size) => {
+                if apply(&mut auction, ParameterChange::TickSize(tick_size)) {
+                    model.tick_size = tick_size;
+                    last_price = None;
+                }
+            }
+            Op::SetLotSize(lot_size) => {
+                apply(&mut auction, ParameterChange::LotSize(lot_size));
+            }
+            Op::SetVolumeTiers(tiers) => {
+                let tiers = tiers
+                    .into_iter()
+                    .map(|(min_amount, discount_bps)| VolumeTier {
+                        min_amount,
+                        discount_bps,
+                    })
+                    .collect();
+                apply(&mut auction, ParameterChange::VolumeTiers(tiers));
+            }
+            Op::Buy { amount, max_price } => {
+                let quoted = auction.price_for_amount(amount);
+                let raised = auction.total_raised();
+                test::set_caller::<DefaultEnvironment>(test::callee::<DefaultEnvironment>());
+                let bought = auction.buy(amount, max_price, false, None, vec![]);
+                test::set_caller::<DefaultEnvironment>(AccountId::from(OWNER));
+                if bought.is_err() {
+                    return;
+                }
+
+                let charged = auction.total_raised() - raised;
+                assert!(
+                    charged <= quoted,
+                    "charged {charged} for {amount}, quoted {quoted}"
+                );
+                assert!(
+                    max_price.is_none_or(|max_price| charged <= max_price),
+                    "charged {charged} for {amount}, above {max_price:?}"
+                );
+                assert!(
+                    charged <= model.ceiling().saturating_mul(amount),
+                    "charged {charged} for {amount}, above the start price"
+                );
+            }
+        }
+
+        let price = auction.price();
+        assert!(
+            price <= model.ceiling(),
+            "price {price} above the start price"
+        );
+        if let Some(last_price) = last_price {
+            assert!(
+                price <= last_price,
+                "price rose from {last_price} to {price}"
+            );
+        }
+        last_price = Some(price);
+    }
+}
+
+fuzz_target!(|input: Input| {
+    test::run_test::<DefaultEnvironment, _>(|_| {
+        run(input);
+        Ok(())
+    })
+    .unwrap();
+});
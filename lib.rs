@@ -7,274 +7,8962 @@
 ///
 /// Contract Storage:
 /// - auction_owner: The account ID of the auction owner.
-/// - asset_token: The account ID of the token representing the asset being auctioned.
-/// - payment_token: The account ID of the token used for payment.
-/// - start_price: The starting price of the auction.
-/// - min_price: The minimum price of the auction.
-/// - start_time: The block number at which the auction starts.
-/// - end_time: The block number at which the auction ends.
+/// - treasury: The account receiving proceeds and returned asset tokens instead of the owner, if set.
+/// - config: The versioned sale parameters:
+///     - asset_token: The account ID of the token representing the asset being auctioned.
+///     - payment_token: The account ID of the token used for payment.
+///     - start_price: The starting price of the auction.
+///     - min_price: The minimum price of the auction.
+///     - start_time: The block number at which the auction starts.
+///     - end_time: The block number at which the auction ends.
+///     - price_strategy: The account ID of an optional external contract computing the price curve.
+///     - burn_unsold: Whether unsold asset tokens are burned instead of returned at finalization.
+///     - price_oracle: The account ID of an optional oracle converting prices from a reference unit into
+///        payment tokens.
+///     - max_rate_age: The maximum age, in blocks, of an oracle rate accepted for purchases.
+///     - weights: The optional liquidity-bootstrapping weight schedule deriving the price curve.
+///     - raise_goal: The minimum total raise of an all-or-nothing sale, or `None` for immediate settlement.
+///     - collect_orders: Whether purchases are collected as orders and filled pro-rata after the auction ends.
+///     - tick_size: The granularity of the price in payment tokens, prices are rounded up to a multiple of it.
+///     - lot_size: The number of asset tokens in a lot, purchases must be whole lots.
+///     - mint_on_demand: Whether purchased asset tokens are minted on delivery instead of taken from deposits.
+///     - burn_proceeds: Whether received payment tokens are burned instead of forwarded, fixed at construction.
+///     - proceeds_cap: The maximum total paid by buyers, after which the auction ends.
+///     - deferred_delivery: Whether purchased asset tokens are allocated and claimed once the owner opens claims.
+///     - grace_period: The number of blocks after `end_time` during which allowlisted buyers can buy the
+///        remaining asset tokens at the minimum price.
+///     - track_leaderboard: Whether purchases update the top-buyer leaderboard.
+///     - perpetual: The tranche size, duration and restart multiplier of a perpetual auction that restarts its
+///        curve tranche by tranche instead of ending.
+///     - release_rate: The asset tokens released for sale per block since the start, capping `available_asset`.
+///     - wrapped_native: Whether the payment token is the chain's wrapped native token, letting `buy` wrap
+///        native value sent with the call.
+///     - sale_policy: The account ID of an optional compliance contract that has to approve every purchase.
+///     - payment_fee_tolerance_bps: The share of a payment, in basis points, a fee-on-transfer payment token may
+///        withhold, or `None` to skip verifying the amounts received.
+/// - metadata: The name, description and links describing the sale to explorers and launchpads.
+/// - strategy_data: Strategy specific configuration passed to the price strategy contract.
+/// - sale_cap: The total amount of asset tokens deposited for sale by the auction owner, or the amount that may
+///        be minted in mint-on-demand mode.
+/// - total_sold: The total amount of asset tokens sold.
+/// - finalized: Whether the auction has been finalized.
+/// - state: The lifecycle stage of the auction recorded by the latest state-changing call.
+/// - paused: Whether the auction owner paused purchases.
+/// - cancelled: Whether the auction owner cancelled the auction before any sale.
+/// - locked: Whether a purchase is in progress, guarding against reentrancy through its external calls.
+/// - reservation_terms: The number of blocks a reservation remains exercisable, zero disables reservations,
+///        and the deposit taken by `reserve`, in basis points of the reserved total.
+/// - reservations: The outstanding price-lock reservation of each account.
+/// - tracked_escrow: The asset token balance the contract expects to hold, given the deposits and transfers it
+///        made.
+/// - total_raised: The payments escrowed by an all-or-nothing sale.
+/// - allocated: The asset tokens owed to buyers of an all-or-nothing sale.
+/// - credited: The payment tokens owed to buyers who overpaid a purchase made by transferring payment tokens.
+/// - raise_withdrawn: Whether the owner has withdrawn the raise of a successful all-or-nothing sale.
+/// - held_payments: The payments held by the contract until finalization while the cooling-off window is enabled.
+/// - total_ordered: The asset tokens requested by the open orders.
+/// - order_supply: The asset tokens distributed to the orders, fixed by the first settlement.
+/// - open_orders: The number of orders not settled yet.
+/// - reserved: The total amount of asset tokens held for outstanding reservations.
+/// - observations: Cumulative purchase totals, recorded at most once per block, backing the TWAP oracle.
+/// - observation_count: The number of recorded observations.
+/// - checkpoints: The latest price checkpoints, recorded at most once per block in a ring buffer of
+///        `CHECKPOINT_CAPACITY` slots.
+/// - checkpoint_count: The number of checkpoints ever recorded.
+/// - price_histogram: The asset tokens bought in each unit price bucket, sorted by bucket.
+/// - contributions: The payments escrowed for each buyer of an all-or-nothing sale.
+/// - allocations: The asset tokens owed to each buyer of an all-or-nothing sale.
+/// - payment_credits: The payment tokens owed to each buyer who overpaid a purchase made by transferring payment
+///        tokens.
+/// - cooling_off: The number of blocks a purchase remains refundable and the refund penalty.
+/// - orders: The open order of each buyer.
+/// - bids: The standing bids, by ID.
+/// - open_bids: The IDs of the standing bids not filled or cancelled yet.
+/// - next_bid_id: The ID of the next standing bid.
+/// - keeper_fee_bps: The share of a filled bid's price, in basis points, paid to the account matching it.
+/// - proposals: The parameter changes proposed by the owner and not executed or cancelled yet.
+/// - next_proposal_id: The ID of the next proposed parameter change.
+/// - timelock: The number of blocks between proposing a parameter change and executing it.
+/// - termination_at: The block from which a scheduled forced termination can be executed.
+/// - min_price_increase_limit: The delay and maximum size of increases of the minimum price.
+/// - parameters_locked: Whether the owner permanently gave up changing the sale parameters.
+/// - purchases: Every purchase, indexed by buyer and the buyer's purchase number.
+/// - next_purchase_id: The ID of the next purchase, numbering the purchases of all buyers.
+/// - purchase_ids: The buyer and the buyer's purchase number of each purchase ID.
+/// - purchase_counts: The number of purchases made by each buyer.
+/// - buyers: Every distinct buyer, in the order of their first purchase.
+/// - buyer_count: The number of distinct buyers.
+/// - operator_allowances: The asset tokens each operator may still buy on behalf of each principal.
+/// - buyer_totals: The first purchase block and the totals bought and paid by each buyer.
+/// - leaderboard: The buyers that bought the most asset tokens, largest first.
+/// - proceeds: The total received from buyers, per payment token.
+/// - proceeds_tokens: Every payment token with recorded proceeds.
+/// - bonus_tiers: The bonus asset tokens granted to early or large purchases.
+/// - loyalty_tiers: The discounts granted to buyers by the asset tokens they bought before.
+/// - volume_tiers: The discounts granted to large purchases.
+/// - voucher_signer: The Ethereum-style address of the ECDSA key whose signatures authorize discount vouchers.
+/// - used_vouchers: The nonces of redeemed vouchers.
+/// - trusted_forwarder: The contract allowed to relay purchases signed off-chain by buyers.
+/// - relay_nonces: The nonce each buyer's next relayed purchase has to carry.
+/// - allowlist_root: The Merkle root of the accounts allowed to buy, when the sale is restricted.
+/// - blacklist: The accounts excluded from buying, e.g. sanctioned or exploiter addresses.
+/// - liquidity_config: The router, share of proceeds and asset tokens used to seed DEX liquidity at
+///        finalization.
+/// - donation: The recipient and share of every payment donated, e.g. to a charity.
+/// - finalize_bounty: The payment tokens held back from proceeds to reward the account finalizing the auction.
+/// - bounty_reserve: The part of the finalize bounty held back so far.
+/// - last_sale_price: The price per asset token paid by the most recent purchase.
+/// - tranche: The index, start block, start price and sales of the latest tranche of a perpetual auction.
+/// - event_seq: The number of events emitted so far, the `seq` of the next event.
+/// - liquidity_proceeds: The payment tokens held back from purchases to seed liquidity.
+/// - listeners: The contracts notified after every purchase.
+/// - subscribers: The observer contracts notified of parameter changes and state transitions.
+/// - subscription_deposits: The native deposit held for each subscriber.
+/// - subscription_deposit: The native deposit required to subscribe.
+/// - swap_router: The DEX router used to swap other tokens into the payment token for zap-in purchases.
+/// - phases: The sale phases, each with its own start block, access restriction, cap and discount.
+/// - phase_purchases: The amount of asset tokens bought by each account in each phase.
+/// - rounds: The rounds of a multi-round sale, each with its own start block, price curve, supply and cap.
+/// - round_stats: The asset tokens sold and payment raised in each round.
+/// - round_start_prices: The start price of each anchored round, fixed by its first purchase.
+/// - round_purchases: The amount of asset tokens bought by each account in each round.
+/// - claims_open: Whether buyers of a deferred-delivery sale can claim their allocations.
+/// - allocation_transfers_disabled: Whether the auction owner disabled transfers of deferred-delivery allocations.
+/// - flip_restricted: Whether allocations can't be claimed in the block they were bought in.
+/// - allocation_blocks: The block in which each account's allocation last grew, while claims are flip restricted.
 ///
-/// Contract Events:
-/// - AssetBought: Emitted when an asset is bought.
+/// Fields read by `buy` and `price` live in the root of the storage, rarely read configuration is kept
+/// behind `Lazy` or `Mapping` so that it is only loaded by the messages that need it.
+///
+/// Contract Events (each carries `seq`, its position among all events emitted by the contract, so consumers can
+/// detect missed events):
+/// - AuctionInitialized: Emitted by every constructor with the owner and all sale parameters.
+/// - AssetBought: Emitted when an asset is bought, with the contract-wide purchase ID as a topic.
+/// - AssetDeposited: Emitted when the auction owner deposits asset tokens into the contract.
+/// - OwnershipTransferred: Emitted when the auction owner changes or ownership is renounced.
+/// - StateChanged: Emitted when a call moves the auction to another lifecycle stage.
+/// - AuctionFinalized: Emitted when the auction is finalized, with the bounty paid to the caller.
+/// - TrancheStarted: Emitted when a perpetual auction sells out a tranche and restarts its curve.
+/// - ProceedsBurned: Emitted when received payment tokens are burned by a burn-proceeds auction.
+/// - DonationSent: Emitted when the donated share of a payment is transferred to the donation recipient.
+/// - MetadataUpdated: Emitted when the auction owner updates the sale metadata.
+/// - ParametersLocked: Emitted when the auction owner permanently locks the sale parameters.
+/// - ClaimsOpened: Emitted when the auction owner lets buyers of a deferred-delivery sale claim their allocations.
+/// - AllocationTransferred: Emitted when a buyer transfers part of a deferred-delivery allocation.
+/// - OperatorApproved: Emitted when a buyer authorizes an operator to buy on their behalf.
+/// - BlacklistUpdated: Emitted when the auction owner adds an account to or removes it from the blacklist.
+/// - UnsoldBurned: Emitted when unsold asset tokens are burned at finalization.
+/// - InventoryRolledOver: Emitted when unsold asset tokens are moved to a successor auction.
+/// - Reserved: Emitted when an account locks the current price with a reservation.
+/// - ReservationForfeited: Emitted when an expired reservation's deposit is forfeited to the owner.
+/// - EscrowReconciled: Emitted when the asset token balance of the contract differs from the tracked balance,
+///        e.g. after a rebase or burn.
+/// - OrderPlaced: Emitted when a buyer places or increases an order.
+/// - OrderSettled: Emitted when an order is filled pro-rata and the excess payment refunded.
+/// - BidPlaced: Emitted when a buyer places a standing bid.
+/// - BidFilled: Emitted when a standing bid is filled.
+/// - BidCancelled: Emitted when a standing bid is cancelled and its escrow refunded.
+/// - ChangeProposed: Emitted when the auction owner proposes a parameter change.
+/// - ChangeExecuted: Emitted when a proposed parameter change is applied.
+/// - ChangeCancelled: Emitted when a proposed parameter change is withdrawn.
+/// - Refunded: Emitted when a purchase is refunded during its cooling-off window.
+/// - LiquiditySeeded: Emitted when proceeds and asset tokens are deposited into a DEX pair at finalization.
+/// - TerminationScheduled: Emitted when the auction owner schedules a forced termination of a live auction.
 ///
 /// Error Types:
 /// - PSP22TokenCall: An error occurred while interacting with the PSP22 token contract.
+/// - TokenCallFailed: A PSP22 call trapped or returned unexpected data instead of a `PSP22Error`.
 /// - MaxPriceExceeded: The current price is higher than the limit set buy the payer.
 /// - InsufficientSupplyToken: The contract does not have enough tokens to fulfill the request.
 /// - NotAuctionOwner: The caller is not the auction owner.
+/// - AuctionNotStarted: The auction has not started yet.
+/// - AuctionNotEnded: The auction can't be finalized before `end_time` and its grace period unless it is sold out
+///        or its proceeds cap is reached.
+/// - AuctionEnded: The auction has ended or been finalized and no longer accepts purchases or changes.
+/// - InvalidSuccessor: The successor contract is not a Dutch auction selling the same asset token.
+/// - ArithmeticOverflow: The total price of the purchase doesn't fit in `u128`.
+/// - ZeroAmount: The purchase amount is zero.
+/// - InsufficientAllowance: The buyer has not approved enough payment tokens for the auction.
+/// - InsufficientBuyerBalance: The buyer does not hold enough payment tokens.
+/// - InvalidParameters: An auction parameter is inconsistent, `reason` tells which one.
+/// - ReservationsDisabled: The owner has not enabled reservations.
+/// - ReservationExists: The account already holds a reservation.
+/// - NoReservation: The account holds no reservation.
+/// - ReservationExpired: The reservation can no longer be exercised.
+/// - ReservationActive: The reservation has not expired yet.
+/// - StaleOracleRate: The oracle rate is older than `max_rate_age`.
+/// - InvalidSignature: The voucher or relayed purchase was not signed by the expected key.
+/// - NotVoucherBeneficiary: The voucher was issued to another account.
+/// - VoucherExpired: The voucher can no longer be redeemed.
+/// - VoucherUsed: The voucher nonce has already been redeemed.
+/// - NotAllowlisted: The sale is restricted to an allowlist the buyer couldn't prove membership of.
+/// - PhaseCapExceeded: The purchase exceeds the per-account cap of the current phase.
+/// - RouterCall: An error occurred while interacting with the DEX router.
+/// - SwapRouterNotSet: Zap-in purchases require a swap router.
+/// - SlippageExceeded: The swap output buys fewer asset tokens than the buyer's minimum.
+/// - TooManyListeners: The maximum number of purchase listeners is already registered.
+/// - AlreadySubscribed: The caller is already subscribed.
+/// - NotSubscribed: The caller is not subscribed.
+/// - InsufficientDeposit: The value transferred with `subscribe` is below the subscription deposit.
+/// - TooManySubscribers: The maximum number of subscribers is already registered.
+/// - NativeTransferFailed: Refunding a native deposit failed.
+/// - ReentrancyDetected: A purchase was attempted while another one is making external calls.
+/// - TransferFeeExceeded: The payment token withheld more of a payment than the configured tolerance.
+/// - SaleAlreadyStarted: The setting can't be changed once asset tokens have been sold.
+/// - GoalNotReached: The all-or-nothing sale didn't reach its raise goal.
+/// - GoalReached: The all-or-nothing sale reached its raise goal, so there are no refunds.
+/// - NothingToClaim: The caller has nothing to claim or withdraw.
+/// - FundsInEscrow: Buyers still have payments to claim from an all-or-nothing sale, allocations, reservations,
+///        open orders or payment credits.
+/// - RefundWindowClosed: The purchase can no longer be refunded.
+/// - NoPurchase: The caller has no purchase with the given ID.
+/// - AlreadyRefunded: The purchase has already been refunded.
+/// - OrderCollectionActive: Purchases are collected as orders, use `place_order`.
+/// - NoOrder: The account has no open order.
+/// - NoBid: There is no open bid with the given ID placed by the caller.
+/// - TooManyBids: The maximum number of open bids has been reached.
+/// - NoProposal: There is no pending parameter change with the given ID.
+/// - TimelockActive: The parameter change can't be executed before its timelock expires.
+/// - ParametersLocked: The sale parameters have been locked and can no longer be changed.
+/// - InvalidLotSize: The amount is not a whole number of lots.
+/// - CapReached: The purchase would take the total paid by buyers above the proceeds cap.
+/// - AuctionPaused: The auction owner paused purchases.
+/// - RoundCapExceeded: The purchase exceeds the per-account cap of the current round.
+/// - ClaimsNotOpen: The auction owner hasn't opened claims of the deferred-delivery sale yet.
+/// - AllocationsNotTransferable: Allocations are only transferable in a deferred-delivery sale that allows it.
+/// - InsufficientAllocation: The caller's allocation is smaller than the amount transferred.
+/// - NativePaymentNotAccepted: Native value was sent to `buy` but the payment token isn't the wrapped native
+///        token.
+/// - OperatorAllowanceExceeded: The operator isn't approved to buy that many asset tokens for the principal.
+/// - NotTrustedForwarder: Relayed purchases can only be submitted by the trusted forwarder.
+/// - RequestExpired: The relayed purchase is past its deadline.
+/// - InvalidNonce: The relayed purchase doesn't carry the buyer's next nonce.
+/// - Blacklisted: The buyer, payer or a recipient is on the blacklist.
+/// - PolicyRejected: The sale policy contract rejected the purchase.
+/// - RegistryCall: The auction registry rejected the registration.
+/// - InvalidTemplate: The auction to clone couldn't be read or has a different configuration layout version.
+/// - NotTerminable: The auction can only be terminated once finalized or cancelled, or forced after a scheduled
+///        termination.
+/// - InvalidSplit: The shares of a split purchase are empty, zero, too many or don't add up to the amount.
+/// - InvalidBuyOrder: The data of a payment token transfer doesn't decode as a `BuyOrder`.
+/// - SameBlockClaim: The caller's allocation was bought or received in the current block, see `flip_restricted`.
+/// - DeliveryNotRefundable: Purchases can't be delivered to other accounts while cooling-off refunds are enabled.
+///
+/// Constructors:
+/// - new: Initializes the auction from `AuctionParams`, failing on inconsistent parameters.
+/// - try_clone_from: Initializes an auction with the configuration and price strategy data of an existing one,
+///        overriding its prices and end time.
 ///
-/// Messages:
+/// Messages (`asset_token`, `payment_token`, `end_time`, `start_block`, `price`, `price_at`, `available_asset`,
+/// `min_price`, `sold` and `buy` are part of the `DutchAuction` trait from `dutch_auction_traits`):
+/// - config: Returns the versioned sale parameters.
+/// - events_emitted: Returns the number of events emitted so far.
+/// - metadata: Returns the name, description and links describing the sale.
+/// - set_metadata: Updates the name, description and links describing the sale. Only the auction owner can
+///        call this message.
+/// - asset_metadata: Returns the name, symbol and decimals of the asset token.
+/// - payment_metadata: Returns the name, symbol and decimals of the payment token.
+/// - asset_token: Returns the account ID of the asset token.
+/// - payment_token: Returns the account ID of the payment token.
 /// - end_time: Returns the block number at which the auction ends.
 /// - start_block: Returns the block number at which the auction starts.
 /// - price: Returns the current price of the asset.
-/// - available_asset: Returns the number of available asset tokens.
+/// - price_at: Returns the price of the asset at an arbitrary block.
+/// - available_asset: Returns the number of available asset tokens, within the release schedule.
 /// - min_price: Returns the minimum price of the auction.
-/// - set_min_price: Updates the minimum price of the auction. Only the auction owner can call this message.
-/// - set_end_time: Updates the end time of the auction. Only the auction owner can call this message.
+/// - proposal: Returns a pending parameter change and the block from which it can be executed.
+/// - timelock: Returns the number of blocks between proposing a parameter change and executing it.
+/// - propose_change: Proposes a change of the minimum price, end time, grace period, price strategy, price oracle,
+///        tick size, lot size, proceeds cap, release rate, fees, finalize bounty, timelock, minimum price increase
+///        limit, phases, volume or loyalty discounts, donation or liquidity seeding. Decreases of the minimum price
+///        apply at once. Only the auction owner can call this message.
+/// - min_price_increase_limit: Returns the delay and maximum size of increases of the minimum price, if limited.
+/// - execute_change: Applies a pending parameter change once its timelock expired. Only the auction owner can
+///        call this message.
+/// - cancel_change: Withdraws a pending parameter change. Only the auction owner can call this message.
+/// - is_locked: Returns whether the sale parameters are permanently locked.
+/// - lock_parameters: Permanently disables every setter and parameter change. Only the auction owner can call
+///        this message.
+/// - price_strategy: Returns the external price strategy contract, if any.
+/// - strategy_data: Returns the configuration passed to the external price strategy.
+/// - sale_cap: Returns the total amount of asset tokens deposited for sale, or mintable in mint-on-demand mode.
+/// - set_mint_on_demand: Switches between selling deposited asset tokens and minting them on delivery, up to a
+///        cap. Only the auction owner can call this message before any sale.
+/// - sold: Returns the total amount of asset tokens sold.
+/// - total_paid: Returns the total paid by buyers for all purchases, counted against the proceeds cap.
+/// - sold_percentage: Returns the percentage of the offered asset tokens that has been sold.
+/// - progress: Returns the blocks elapsed and remaining, and the percentages of price decay and supply sold.
+/// - lots_sold: Returns the number of whole lots sold.
+/// - reservation_terms: Returns the reservation window and deposit.
+/// - set_reservation_terms: Updates the reservation window and deposit. Only the auction owner can call this
+///        message.
+/// - reservation_of: Returns the outstanding reservation of an account.
+/// - reserve: Locks the current price for an amount of asset tokens against a refundable deposit.
+/// - exercise_reservation: Buys the reserved asset tokens at the locked price.
+/// - forfeit_reservation: Releases an expired reservation and forfeits its deposit to the owner.
+/// - remaining_blocks: Returns the number of blocks until `end_time`.
+/// - blocks_until_price: Returns the number of blocks until the price drops to a target.
+/// - purchase_count_of: Returns the number of purchases made by an account.
+/// - purchases_of: Returns a page of the purchases made by an account.
+/// - purchase: Returns the buyer and details of the purchase with an ID.
+/// - buyer_count: Returns the number of distinct buyers.
+/// - buyers: Returns a page of the distinct buyers with their totals, in the order of their first purchase.
+/// - buyer_totals_of: Returns the first purchase block and the totals bought and paid by an account.
+/// - leaderboard: Returns the buyers that bought the most asset tokens, largest first.
+/// - set_leaderboard: Enables or disables the leaderboard before the sale starts. Only the auction owner can call
+///        this message.
+/// - proceeds_of: Returns the total paid by buyers in a payment token.
+/// - proceeds: Returns the total paid by buyers for every payment token used.
+/// - twap: Returns the average realized purchase price over a window of recent blocks.
+/// - checkpoint: Records a price checkpoint for the current block. Callable by anyone.
+/// - checkpoint_count: Returns the number of price checkpoints ever recorded.
+/// - checkpoints: Returns a page of the retained price checkpoints within a range of blocks.
+/// - average_realized_price: Returns the average price paid per asset token over all purchases.
+/// - median_realized_price: Returns an approximate median price paid per asset token over all purchases.
+/// - bonus_tiers: Returns the bonus schedule.
+/// - set_bonus_tiers: Updates the bonus schedule. Only the auction owner can call this message.
+/// - loyalty_tiers: Returns the loyalty discount schedule.
+/// - price_for: Returns the current price of an asset token for an account, after its phase and loyalty discount.
+/// - volume_tiers: Returns the volume discount schedule.
+/// - price_for_amount: Returns the current total price of an amount of asset tokens, after the volume discount.
+/// - voucher_signer: Returns the address of the key authorized to sign discount vouchers.
+/// - set_voucher_signer: Updates the address of the key authorized to sign discount vouchers. Only the auction owner
+///        can call this message.
+/// - voucher_hash: Returns the hash a voucher signer has to sign.
+/// - buy_with_voucher: Buys asset tokens at the current price, discounted by a signed voucher.
+/// - allowlist_root: Returns the Merkle root of the allowlist, if the sale is restricted.
+/// - set_allowlist_root: Restricts the sale to an allowlist or rotates its root. Only the auction owner can
+///        call this message.
+/// - buy_with_proof: Buys asset tokens at the current price, proving the caller is on the allowlist.
+/// - is_blacklisted: Returns whether an account is excluded from buying.
+/// - set_blacklisted: Adds an account to or removes it from the blacklist. Only the auction owner can call this
+///        message.
+/// - operator_allowance: Returns the asset tokens an operator may still buy on behalf of a principal.
+/// - approve_operator: Authorizes an operator to buy up to an amount of asset tokens on the caller's behalf.
+/// - buy_as: Buys asset tokens on behalf of a principal that approved the caller, paid by the principal.
+/// - buy_split: Buys asset tokens paid by the caller and delivered to several recipients in given shares.
+/// - trusted_forwarder: Returns the contract allowed to relay signed purchases, if any.
+/// - set_trusted_forwarder: Updates the contract allowed to relay signed purchases. Only the auction owner can
+///        call this message.
+/// - relay_nonce: Returns the nonce of an account's next relayed purchase.
+/// - relayed_buy_hash: Returns the hash a buyer has to sign to have a purchase relayed.
+/// - buy_relayed: Buys asset tokens for a buyer who signed the request off-chain. Only the trusted forwarder
+///        can call this message.
+/// - listeners: Returns the contracts notified after every purchase.
+/// - add_listener: Registers a contract notified after every purchase. Only the auction owner can call this
+///        message.
+/// - remove_listener: Unregisters a purchase listener. Only the auction owner can call this message.
+/// - subscribers: Returns the observer contracts subscribed to notifications.
+/// - subscription_deposit: Returns the native deposit required to subscribe.
+/// - set_subscription_deposit: Updates the native deposit required to subscribe. Only the auction owner can
+///        call this message.
+/// - subscribe: Subscribes the caller to notifications of parameter changes and state transitions against
+///        a refundable deposit.
+/// - unsubscribe: Cancels the caller's subscription and refunds its deposit.
+/// - swap_router: Returns the DEX router used for zap-in purchases.
+/// - set_swap_router: Updates the DEX router used for zap-in purchases. Only the auction owner can call this
+///        message.
+/// - buy_with_any_token: Swaps an arbitrary token into the payment token and buys asset tokens with the
+///        output.
+/// - set_wrapped_native: Marks the payment token as the wrapped native token, letting `buy` accept native
+///        value. Only the auction owner can call this message.
+/// - set_sale_policy: Updates the compliance contract approving every purchase. Only the auction owner can
+///        call this message.
+/// - set_order_collection: Switches between immediate purchases and order collection. Only the auction owner
+///        can call this message before any sale.
+/// - order_of: Returns the open order of an account.
+/// - total_ordered: Returns the asset tokens requested by the open orders.
+/// - place_order: Locks payment for asset tokens at the caller's discounted current price, to be filled pro-rata.
+/// - place_order_with_proof: Places an order, proving the caller is on the allowlist.
+/// - settle_order: Fills an order pro-rata after the auction ends and refunds the excess payment.
+/// - bid: Returns a standing bid.
+/// - open_bids: Returns the IDs of the open standing bids.
+/// - keeper_fee_bps: Returns the share of a filled bid's price paid to the account matching it.
+/// - place_bid: Escrows payment for asset tokens to be bought once the price drops to a limit.
+/// - cancel_bid: Cancels an open bid and refunds its escrow.
+/// - match_bids: Fills the open bids whose limit the current price has reached.
+/// - cooling_off: Returns the cooling-off window and refund penalty.
+/// - set_cooling_off: Updates the cooling-off window and refund penalty. Only the auction owner can call this
+///        message before any sale.
+/// - refund: Returns a purchase, by its contract-wide ID, during its cooling-off window and refunds the payment
+///        minus the penalty. Allocated purchases are returned from the caller's allocation.
+/// - raise_goal: Returns the raise goal of an all-or-nothing sale.
+/// - set_raise_goal: Turns the auction into an all-or-nothing sale or back. Only the auction owner can call
+///        this message before any sale.
+/// - total_raised: Returns the payments escrowed by an all-or-nothing sale.
+/// - contribution_of: Returns the payments escrowed for an account.
+/// - allocation_of: Returns the asset tokens owed to an account.
+/// - payment_credit_of: Returns the payment tokens owed to an account for overpaying a purchase.
+/// - withdraw_payment_credit: Transfers the payment tokens owed to the caller for overpaying purchases.
+/// - claim_assets: Transfers the caller's allocation once an all-or-nothing sale succeeded or claims of a
+///        deferred-delivery sale are open.
+/// - set_deferred_delivery: Switches between immediate delivery and allocations claimed later. Only the auction
+///        owner can call this message before the sale starts.
+/// - claims_open: Returns whether buyers of a deferred-delivery sale can claim their allocations.
+/// - open_claims: Lets buyers of a deferred-delivery sale claim their allocations. Only the auction owner can
+///        call this message.
+/// - allocation_transfers_enabled: Returns whether buyers can transfer deferred-delivery allocations.
+/// - set_allocation_transfers: Enables or disables transfers of allocations. Only the auction owner can call
+///        this message.
+/// - transfer_allocation: Transfers part of the caller's deferred-delivery allocation to another account.
+/// - flip_restricted: Returns whether allocations can't be claimed in the block they were bought in.
+/// - set_flip_restriction: Enables or disables the same-block flip restriction. Only the auction owner can call
+///        this message.
+/// - claim_refund: Refunds the caller's payments once an all-or-nothing sale failed.
+/// - withdraw_raise: Transfers the raise to the owner once an all-or-nothing sale succeeded. Only the auction
+///        owner can call this message.
+/// - phases: Returns the sale phases.
+/// - active_phase: Returns the index and terms of the current sale phase.
+/// - rounds: Returns the rounds of a multi-round sale.
+/// - set_rounds: Replaces the rounds before the sale starts. Only the auction owner can call this message.
+/// - current_round: Returns the index and terms of the current round.
+/// - round_stats: Returns the asset tokens sold and payment raised in a round.
+/// - last_sale_price: Returns the price per asset token paid by the most recent purchase.
+/// - perpetual: Returns the terms of a perpetual auction.
+/// - set_perpetual: Turns the auction into a perpetual one, or back, before the sale starts. Only the auction
+///        owner can call this message.
+/// - current_tranche: Returns the tranche of a perpetual auction in effect at the current block.
+/// - can_buy: Checks whether an account could buy a given amount right now, returning the error `buy`
+///        would fail with.
+/// - simulate_buy: Returns the price, discount, bonus and payment split of a prospective purchase, along
+///        with the error `buy` would fail with.
+/// - reconcile: Compares the asset token balance of the contract with the tracked balance, emitting any
+///        discrepancy.
+/// - audit: Verifies the internal invariants of the auction and returns a report.
+/// - burn_unsold: Returns whether unsold asset tokens are burned at finalization.
+/// - set_burn_unsold: Updates whether unsold asset tokens are burned at finalization. Only the auction owner
+///        can call this message.
+/// - donation: Returns the donation recipient and share of every payment.
+/// - liquidity_config: Returns the DEX liquidity seeding configuration.
+/// - state: Returns the lifecycle stage of the auction.
+/// - pause: Suspends purchases, orders and bids. Only the auction owner can call this message.
+/// - unpause: Resumes purchases, orders and bids. Only the auction owner can call this message.
+/// - cancel: Cancels the auction before any sale and returns the deposited asset tokens. Only the auction owner
+///        can call this message.
+/// - finalize_bounty: Returns the payment tokens paid to the account finalizing the auction.
+/// - finalize: Seeds DEX liquidity, then burns or returns the unsold asset tokens after the auction ends, and
+///        pays the caller the finalize bounty. Callable by anyone.
+/// - rollover: Moves the unsold asset tokens to a successor auction after the auction ends. Only the auction
+///        owner can call this message.
 /// - buy: Buys a specified amount of asset tokens at the current price. The caller must provide approval
-///        for the `payment_token` before calling this message.
-/// - terminate: Terminates the contract. Only the auction owner can call this message.
+///        for the `payment_token` before calling this message. With `allow_partial`, buys as much of the
+///        amount as is available instead of failing, returning the amount bought. The asset tokens go to
+///        `deliver_to` if set, while the caller pays. The `data` payload is passed to the token transfers and
+///        included in `AssetBought`.
+/// - terminate: Returns the asset and payment tokens held by the contract to the owner and terminates the
+///        contract once finalized or cancelled. Only the auction owner can call this message.
+/// - termination_at: Returns the block from which a scheduled forced termination can be executed, if any.
+/// - schedule_termination: Announces a forced termination of a live auction, executable after the timelock.
+///        Only the auction owner can call this message.
+/// - before_received: PSP22Receiver hook recording asset deposits made by the auction owner, and buying asset
+///        tokens with payment tokens transferred along with a `BuyOrder`.
+/// - owner: Returns the auction owner, or `None` once ownership is renounced.
+/// - transfer_ownership: Transfers the auction ownership. Only the auction owner can call this message.
+/// - renounce_ownership: Permanently gives up the auction ownership, routing proceeds to the treasury. Only the
+///        auction owner can call this message.
+/// - treasury: Returns the account receiving proceeds instead of the owner, if any.
+/// - set_treasury: Updates the account receiving proceeds instead of the owner. Only the auction owner can
+///        call this message.
+/// - register_in: Lists the auction in an auction registry for discovery. Only the auction owner can call
+///        this message.
 ///
 /// Additional Functions:
-/// - current_price: Calculates the current price of the asset based on the starting price, minimum price,
-///        start time, end time, and current block number.
-/// - take_payment: Takes payment from the caller for the specified amount.
+/// - build, try_build: Initialize the storage, without and with validating the parameters.
+/// - from_template: Initializes an auction with a cloned configuration and price strategy data.
+/// - emit_initialized: Emits `AuctionInitialized` with the sale parameters set up by a constructor.
+/// - next_event_seq: Takes the sequence number of the next event.
+/// - current_price: Calculates the current price of the asset at the current block number.
+/// - price_at_block: Calculates the price of the asset in payment tokens at the given block number.
+/// - reference_price_at: Calculates the price of the asset based on the starting price, minimum price,
+///        start time, end time, and the given block number. Delegates to the price strategy contract when
+///        one is configured, falling back to the built-in curve if the call fails.
+/// - curve_params: Gets the parameters passed to the price strategy contract.
+/// - round_to_tick: Rounds a price up to a multiple of the tick size.
+/// - weighted_price: Calculates the price implied by the shifting asset and payment token weights.
+/// - oracle_rate: Fetches the latest conversion rate from the price oracle, if one is configured.
+/// - to_payment: Converts a reference-unit price into payment tokens.
+/// - collect_payment: Takes payment, holding it in the contract while the cooling-off window is enabled.
+/// - close_bid: Removes a standing bid from the open bids.
+/// - pro_rata: Calculates the pro-rata fill of an order and its cost.
+/// - mul_div: Multiplies and divides without overflowing in the intermediate product.
+/// - pull_payment: Transfers payment tokens from a buyer to the contract, returning the amount received.
+/// - wrap_native: Wraps native value sent with a purchase into payment tokens held by the contract.
+/// - release_held_payments: Forwards the held payments to the owner at finalization.
+/// - escrow_purchase: Escrows the payment of an all-or-nothing purchase.
+/// - buy_on_receipt: Buys asset tokens with the payment tokens being transferred to the contract.
+/// - share_bonus: Splits the bonus of a purchase among its recipients in proportion to their shares.
+/// - goal_reached: Checks whether an all-or-nothing sale has ended successfully.
+/// - unsold: Gets the asset tokens held by the contract that are not owed to buyers.
+/// - refunds_enabled: Checks whether purchases can be refunded during a cooling-off window.
+/// - allocate: Records asset tokens owed to a buyer of an all-or-nothing or deferred-delivery sale.
+/// - take_payment: Takes payment from the caller for the specified amount, sending the donation, holding back
+///        the liquidity share, burning the rest in burn-proceeds mode and verifying the amounts received.
+/// - record_proceeds: Adds a received payment to the proceeds of the payment token.
+/// - seed_liquidity: Deposits the held back proceeds and asset tokens not owed to buyers into a DEX pair.
 /// - give_asset: Transfers the specified amount of asset tokens to the caller.
-/// - asset_balance: Gets the balance of the asset token held by the contract.
+/// - deliver_asset: Delivers purchased asset tokens, minting them first in mint-on-demand mode.
+/// - reconcile_escrow: Measures the asset token balance of the contract and adopts it as the tracked balance.
+/// - record_purchase: Updates the purchase statistics and emits `AssetBought` for a purchase.
+/// - notify_listeners: Notifies the purchase listeners after a purchase.
+/// - check_change: Checks that a parameter change keeps the sale parameters consistent.
+/// - check_parameters_unlocked: Checks that the sale parameters have not been locked.
+/// - apply_change: Writes a parameter change to the sale parameters.
+/// - lock: Acquires the reentrancy lock.
+/// - unlock: Releases the reentrancy lock.
+/// - observation_at: Finds the latest observation recorded at or before a block.
+/// - record_checkpoint: Records the current price and total sold as the checkpoint of the current block.
+/// - checkpoint_slot: Gets the checkpoint with a sequence number, if it has not been overwritten.
+/// - record_price_bucket: Adds the asset tokens of a purchase to the bucket of its unit price.
+/// - price_bucket: Maps a unit price to its histogram bucket.
+/// - bucket_midpoint: Gets the price in the middle of a histogram bucket.
+/// - notify: Forwards a notification to every subscribed observer.
+/// - sweep: Transfers all asset and payment tokens held by the contract to the proceeds recipient.
+/// - token_metadata: Queries the `PSP22Metadata` extension of a token, tolerating tokens without it.
+/// - token_call: Maps a token call that trapped or returned undecodable data to `TokenCallFailed`.
+/// - transfer_token, transfer_token_from, approve_token: Make PSP22 calls that fail with an error instead of
+///        trapping.
+/// - proceeds_recipient: Gets the account receiving proceeds, the treasury if set, otherwise the owner.
+/// - escrow_balance: Gets the balance of the asset token held by the contract.
+/// - check_not_finalized: Checks that the auction has not been finalized yet.
+/// - check_not_paused: Checks that the auction owner has not paused the auction.
+/// - check_access: Checks that the active phase or allowlist admits a buyer.
+/// - check_purchase: Checks that an account may buy an amount at the current block, on every path selling asset
+///        tokens.
+/// - admit_purchase: Has the sale policy approve a sale and counts it against the per-account caps of the current
+///        phase and round and the round's statistics, on every path selling asset tokens.
+/// - update_state: Records the current lifecycle stage, emitting `StateChanged` when it moved.
+/// - check_ended: Checks that the auction is past `end_time` and its grace period, sold out or has reached its
+///        proceeds cap.
+/// - closes_at: Gets the last block of the grace period, or `end_time` without one. Perpetual auctions never
+///        close.
+/// - tranche_at: Gets the tranche of a perpetual auction in effect at a block, restarting timed out tranches.
+/// - record_tranche: Counts a sale against the current tranche, starting the next one when it sells out.
+/// - inventory: Gets the asset tokens left for sale, regardless of the release schedule. Without minting on demand,
+///        these are the escrowed asset tokens not reserved, owed to buyers or held back for liquidity.
+/// - liquidity_assets: Gets the escrowed asset tokens held back from the sale to seed liquidity at finalization.
+/// - round_start_price: Gets the start price of a round, derived from the last sale price for anchored rounds.
+/// - update_leaderboard: Moves a buyer to its position on the leaderboard after its total changed.
+/// - cap_reached: Checks whether the proceeds cap leaves no room for another asset token at the current price.
 /// - linear_decrease: Calculates the linear interpolation between two points.
 /// - check_owner: Checks if the caller is the auction owner.
+/// - quote: Checks the auction state and supply for a purchase and computes its total price and bonus.
+/// - quote_for: Quotes a purchase, admitting allowlisted buyers to restricted phases and the grace period.
+/// - discounted_quote: Quotes a purchase for a buyer after the phase, loyalty and caller-given discounts.
+/// - discount_bps_for: Gets the largest of the phase, loyalty and caller-given discounts of a buyer.
+/// - bonus_for: Calculates the bonus asset tokens granted to a purchase at the current block.
+/// - loyalty_discount_bps: Gets the loyalty discount an account qualifies for.
+/// - apply_volume_discount: Deducts the volume discount a purchase qualifies for from its total price.
+/// - redeem_voucher: Verifies a voucher for the caller and marks its nonce as used.
+/// - verify_relayed: Verifies a relayed purchase request and consumes the buyer's nonce.
+/// - execute_purchase: Takes payment, delivers the asset tokens and records a purchase at the current price,
+///        returning the total paid.
+/// - submit_order: Locks payment for an order at the caller's discounted price, admitting allowlisted callers to
+///        allowlist-only phases.
+/// - phase_at: Finds the sale phase in effect at a block.
+/// - allowlist_leaf: Calculates the Merkle leaf of an allowlisted account.
+/// - hash_pair: Calculates the parent of two Merkle nodes.
+/// - check_allowlist_proof: Checks a Merkle proof that an account is on the allowlist.
+/// - verify_proof: Checks a Merkle proof of membership against a root.
+/// - check_lot_size: Checks that an amount is a whole number of lots.
+/// - check_payment: Checks the allowance and balance of the payment token before a payment is pulled, telling
+///        which one falls short.
+/// - check_price_range: Checks that the minimum price doesn't exceed the starting price.
+/// - check_time_range: Checks that the end time is after the start time.
 
 #[ink::contract]
-mod dutch_auction {
-    use ink::{contract_ref, prelude::vec};
-    use psp22::{PSP22Error, PSP22};
+pub mod dutch_auction {
+    pub use dutch_auction_traits::DutchAuctionError as Error;
+    use dutch_auction_traits::{
+        AuctionListener, AuctionNotification, AuctionObserver, AuctionRegistry, CurveParams,
+        DexRouter, DutchAuction as DutchAuctionTrait, InvalidParameter, Ownable, OwnableError,
+        PSP22Receiver, PSP22ReceiverError, PriceOracle, PriceStrategy, RateData, SalePolicy,
+        WrappedNative, RATE_PRECISION,
+    };
+    use ink::{
+        codegen::TraitCallBuilder,
+        contract_ref,
+        env::{
+            call::{build_call, ExecutionInput, Selector},
+            CallFlags,
+        },
+        prelude::{format, string::String, vec::Vec},
+        storage::{Lazy, Mapping},
+    };
+    use psp22::{PSP22Burnable, PSP22Metadata, PSP22Mintable, PSP22};
 
     #[ink(storage)]
     pub struct DutchAuction {
+        // Hot path: decoded on every message.
         auction_owner: AccountId,
-        asset_token: contract_ref!(PSP22),
-        payment_token: contract_ref!(PSP22),
-        start_price: u128,
-        min_price: u128,
-        start_time: BlockNumber,
-        end_time: BlockNumber,
+        treasury: Option<AccountId>,
+        config: Config,
+        sale_cap: u128,
+        total_sold: u128,
+        finalized: bool,
+        state: AuctionState,
+        paused: bool,
+        cancelled: bool,
+        locked: bool,
+        tracked_escrow: u128,
+        total_raised: u128,
+        allocated: u128,
+        credited: u128,
+        raise_withdrawn: bool,
+        held_payments: u128,
+        total_ordered: u128,
+        order_supply: Option<u128>,
+        open_orders: u32,
+        reserved: u128,
+        observation_count: u32,
+        checkpoint_count: u32,
+        liquidity_proceeds: u128,
+        bounty_reserve: u128,
+        last_sale_price: u128,
+        tranche: Tranche,
+        event_seq: u64,
+        next_purchase_id: u64,
+        // Cold configuration and per-account data: loaded on demand.
+        metadata: Lazy<Metadata>,
+        strategy_data: Lazy<Vec<u8>>,
+        reservation_terms: Lazy<ReservationTerms>,
+        reservations: Mapping<AccountId, Reservation>,
+        observations: Mapping<u32, Observation>,
+        checkpoints: Mapping<u32, Checkpoint>,
+        price_histogram: Lazy<Vec<(u16, u128)>>,
+        contributions: Mapping<AccountId, u128>,
+        allocations: Mapping<AccountId, u128>,
+        payment_credits: Mapping<AccountId, u128>,
+        cooling_off: Lazy<CoolingOff>,
+        orders: Mapping<AccountId, Order>,
+        bids: Mapping<u32, Bid>,
+        open_bids: Lazy<Vec<u32>>,
+        next_bid_id: Lazy<u32>,
+        keeper_fee_bps: Lazy<u16>,
+        proposals: Mapping<u32, Proposal>,
+        next_proposal_id: Lazy<u32>,
+        timelock: Lazy<BlockNumber>,
+        min_price_increase_limit: Lazy<Option<MinPriceIncreaseLimit>>,
+        parameters_locked: Lazy<bool>,
+        purchases: Mapping<(AccountId, u32), Purchase>,
+        purchase_counts: Mapping<AccountId, u32>,
+        purchase_ids: Mapping<u64, (AccountId, u32)>,
+        buyers: Mapping<u32, AccountId>,
+        buyer_count: Lazy<u32>,
+        buyer_totals: Mapping<AccountId, BuyerTotals>,
+        operator_allowances: Mapping<(AccountId, AccountId), u128>,
+        leaderboard: Lazy<Vec<(AccountId, u128)>>,
+        proceeds: Mapping<AccountId, u128>,
+        proceeds_tokens: Lazy<Vec<AccountId>>,
+        bonus_tiers: Lazy<Vec<BonusTier>>,
+        loyalty_tiers: Lazy<Vec<LoyaltyTier>>,
+        volume_tiers: Lazy<Vec<VolumeTier>>,
+        voucher_signer: Lazy<Option<[u8; 20]>>,
+        used_vouchers: Mapping<u64, ()>,
+        trusted_forwarder: Lazy<Option<AccountId>>,
+        relay_nonces: Mapping<AccountId, u64>,
+        allowlist_root: Lazy<Option<[u8; 32]>>,
+        blacklist: Mapping<AccountId, ()>,
+        phases: Lazy<Vec<Phase>>,
+        phase_purchases: Mapping<(u32, AccountId), u128>,
+        rounds: Lazy<Vec<Round>>,
+        round_stats: Mapping<u32, RoundStats>,
+        round_start_prices: Mapping<u32, u128>,
+        round_purchases: Mapping<(u32, AccountId), u128>,
+        claims_open: Lazy<bool>,
+        allocation_transfers_disabled: Lazy<bool>,
+        flip_restricted: Lazy<bool>,
+        allocation_blocks: Mapping<AccountId, BlockNumber>,
+        liquidity_config: Lazy<Option<LiquidityConfig>>,
+        finalize_bounty: Lazy<u128>,
+        donation: Lazy<Option<Donation>>,
+        swap_router: Lazy<Option<AccountId>>,
+        listeners: Lazy<Vec<AccountId>>,
+        subscribers: Lazy<Vec<AccountId>>,
+        subscription_deposits: Mapping<AccountId, Balance>,
+        subscription_deposit: Lazy<Balance>,
+        termination_at: Lazy<Option<BlockNumber>>,
     }
 
-    #[derive(Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-    pub enum Error {
-        PSP22TokenCall(PSP22Error),
-        MaxPriceExceeded,
-        InsufficientSupplyToken,
-        NotAuctionOwner,
+    /// The lifecycle stage of the auction, see `state`.
+    #[derive(Clone, Copy, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum AuctionState {
+        /// Before `start_time`, or no asset tokens have been deposited yet.
+        Pending,
+        /// Accepting purchases.
+        Active,
+        /// Purchases suspended by the owner.
+        Paused,
+        /// Every offered asset token has been sold.
+        SoldOut,
+        /// Past `end_time` and its grace period or the proceeds cap, waiting for `finalize`.
+        Ended,
+        Finalized,
+        /// Cancelled by the owner before any sale.
+        Cancelled,
     }
 
-    #[ink(event)]
-    #[derive(Clone, Eq, PartialEq, Debug)]
-    pub struct AssetBought {
-        #[ink(topic)]
-        pub by: AccountId,
+    /// A single purchase made by a buyer.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Purchase {
+        /// Contract-wide ID of the purchase, see `purchase`.
+        pub id: u64,
+        pub block: BlockNumber,
+        /// Amount of asset tokens bought.
+        pub amount: u128,
+        /// Total price paid.
         pub price: u128,
+        /// Bonus asset tokens received on top of `amount`.
+        pub bonus: u128,
+        /// Whether the asset tokens were allocated to the buyer for a later claim instead of
+        /// delivered.
+        pub allocated: bool,
+        /// Whether the purchase was refunded during its cooling-off window.
+        pub refunded: bool,
+    }
+
+    /// Totals of all purchases made by a buyer, net of refunds.
+    #[derive(Clone, Default, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct BuyerTotals {
+        /// Block of the buyer's first purchase.
+        pub first_block: BlockNumber,
+        /// Asset tokens bought, including bonuses.
+        pub bought: u128,
+        /// Total price paid.
+        pub paid: u128,
+    }
+
+    /// Asset tokens requested by a buyer in order collection mode, and the payment locked for them.
+    #[derive(Clone, Default, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Order {
         pub amount: u128,
+        pub paid: u128,
     }
 
-    impl From<PSP22Error> for Error {
-        fn from(inner: PSP22Error) -> Self {
-            Error::PSP22TokenCall(inner)
-        }
+    /// A resting order to buy once the price drops to `limit_price`.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Bid {
+        pub bidder: AccountId,
+        pub amount: u128,
+        /// Highest price of a single asset token the bidder accepts.
+        pub limit_price: u128,
+        /// Payment held by the contract, `amount * limit_price`.
+        pub escrow: u128,
     }
 
-    impl DutchAuction {
-        /// Constructor that initializes the contract storage.
-        ///
-        /// Caller would be the auction_owner
-        #[ink(constructor)]
-        pub fn new(
-            asset_token: AccountId,
-            payment_token: AccountId,
-            start_price: u128,
-            min_price: u128,
-            end_time: BlockNumber,
-        ) -> Self {
-            Self {
-                auction_owner: Self::env().caller(),
-                asset_token: asset_token.into(),
-                payment_token: payment_token.into(),
-                start_price,
-                min_price,
-                start_time: Self::env().block_number(),
-                end_time,
-            }
-        }
+    /// Terms under which buyers can return their purchases.
+    #[derive(Clone, Default, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct CoolingOff {
+        /// Number of blocks a purchase remains refundable. Zero disables refunds.
+        pub window: BlockNumber,
+        /// Share of the price, in basis points, kept on refund.
+        pub penalty_bps: u16,
+    }
 
-        /// The block after which the price will no longer decrease.
-        ///
-        /// The contract will decrease the price linearly from start_price()
-        /// to `min_price()` over the period from `start_time()` to 'end_time()`.
-        /// The auction doesn't end after the period elapses -
-        /// the asset remains available for purchase at `min_price()`.
-        #[ink(message)]
-        pub fn end_time(&self) -> BlockNumber {
-            self.end_time
-        }
+    /// Extra asset tokens granted to purchases matching all of the tier's conditions.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct BonusTier {
+        /// Minimum amount of asset tokens bought.
+        pub min_amount: u128,
+        /// The purchase must happen before this block, if set.
+        pub before_block: Option<BlockNumber>,
+        /// Bonus in basis points of the amount bought.
+        pub bonus_bps: u16,
+    }
 
-        /// The block at which the auction starts
-        #[ink(message)]
-        pub fn start_block(&self) -> BlockNumber {
-            self.start_time
-        }
+    /// A discount granted to returning buyers.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct LoyaltyTier {
+        /// Minimum amount of asset tokens bought in earlier purchases, net of refunds.
+        pub min_bought: u128,
+        /// Discount in basis points of the total price.
+        pub discount_bps: u16,
+    }
 
-        /// The price the contract would charge when buying at the current block.
-        #[ink(message)]
-        pub fn price(&self) -> u128 {
-            self.current_price()
-        }
+    /// A discount granted to large purchases.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct VolumeTier {
+        /// Minimum amount of asset tokens bought.
+        pub min_amount: u128,
+        /// Discount in basis points of the total price.
+        pub discount_bps: u16,
+    }
 
-        /// Amount of tokens available for sale.
-        #[ink(message)]
-        pub fn available_asset(&self) -> u128 {
-            self.asset_balance()
-        }
+    /// How DEX liquidity is seeded when the auction is finalized.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct LiquidityConfig {
+        pub router: AccountId,
+        /// Share of every payment, in basis points, held back for liquidity.
+        pub proceeds_bps: u16,
+        /// Asset tokens held by the contract that are paired with the held back proceeds.
+        pub asset_amount: u128,
+    }
 
-        /// The minimal price the contract allows.
-        #[ink(message)]
-        pub fn min_price(&self) -> u128 {
-            self.min_price
-        }
+    /// A stage of the sale, in effect from `start_block` until the next phase starts.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Phase {
+        pub start_block: BlockNumber,
+        /// Only accounts proving allowlist membership can buy.
+        pub allowlist_only: bool,
+        /// Maximum amount of asset tokens a single account can buy during the phase.
+        pub max_per_account: Option<u128>,
+        /// Discount in basis points of the total price.
+        pub discount_bps: u16,
+    }
 
-        /// Update the minimal price.
-        ///
-        /// Requires auction_owner to execute.
-        #[ink(message)]
-        pub fn set_min_price(&mut self, value: u128) -> Result<(), Error> {
-            self.check_owner(self.env().caller())?;
-            self.min_price = value;
+    /// A round of a multi-round sale, in effect from `start_block` until the next round starts or
+    /// the auction ends.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Round {
+        pub start_block: BlockNumber,
+        /// Price at the start of the round, decreasing linearly until the round ends.
+        pub start_price: u128,
+        /// Derive the start price from the last sale before the round, multiplied by this value
+        /// in basis points, e.g. 12000 to start 20% above it. Falls back to `start_price` if
+        /// nothing was sold. Sale prices are in payment tokens, so anchoring assumes that no
+        /// price oracle is set.
+        pub anchor_bps: Option<u32>,
+        /// Price reached at the end of the round.
+        pub min_price: u128,
+        /// Maximum amount of asset tokens sold during the round, including bonuses.
+        pub supply: u128,
+        /// Maximum amount of asset tokens a single account can buy during the round.
+        pub max_per_account: Option<u128>,
+    }
 
-            Ok(())
-        }
+    /// Sales of a round.
+    #[derive(Clone, Default, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct RoundStats {
+        /// Asset tokens sold, including bonuses.
+        pub sold: u128,
+        /// Payment tokens paid by buyers.
+        pub raised: u128,
+    }
 
-        /// Update the length of the auction.
-        ///
-        /// Requires auction_owner to execute.
-        #[ink(message)]
-        pub fn set_end_time(&mut self, end_time: BlockNumber) -> Result<(), Error> {
-            self.check_owner(self.env().caller())?;
-            self.end_time = end_time;
+    /// A discount granted off-chain by the voucher signer.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Voucher {
+        /// The only account allowed to redeem the voucher.
+        pub beneficiary: AccountId,
+        /// Discount in basis points of the total price.
+        pub discount_bps: u16,
+        /// Last block at which the voucher can be redeemed.
+        pub expires_at: BlockNumber,
+        /// Unique number preventing the voucher from being redeemed twice.
+        pub nonce: u64,
+    }
 
-            Ok(())
-        }
+    /// A purchase signed off-chain by `buyer` and submitted by the trusted forwarder.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct RelayedBuy {
+        /// The account paying for and receiving the asset tokens.
+        pub buyer: AccountId,
+        /// The amount of asset tokens to buy.
+        pub amount: u128,
+        /// The maximum total price the buyer accepts.
+        pub max_price: Option<u128>,
+        /// Must equal `relay_nonce(buyer)`, so every request is submitted at most once.
+        pub nonce: u64,
+        /// Last block at which the request can be submitted.
+        pub deadline: BlockNumber,
+    }
 
-        /// Buy `asset_tokens` at the `current_price`.
+    /// A change of the sale parameters, proposed by the owner with `propose_change` and applied
+    /// by `execute_change`, so buyers and co-signers can review it before it takes effect.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum ParameterChange {
+        /// The minimal price, which can't exceed the starting price.
+        MinPrice(u128),
+        /// The end of the auction, which must be after the start block.
+        EndTime(BlockNumber),
+        /// The external price strategy and the configuration passed to it. `None` returns to
+        /// the built-in linear curve.
+        PriceStrategy {
+            strategy: Option<AccountId>,
+            data: Vec<u8>,
+        },
+        /// The oracle converting prices into payment tokens.
         ///
-        /// The caller should provide a positive `amount` of 'asset_tokens' to purchase.
+        /// When an oracle is set, `start_price` and `min_price` are expressed in the oracle's
+        /// reference unit and converted at the latest rate on every price query. Purchases fail
+        /// if the rate is more than `max_rate_age` blocks old. `None` prices directly in payment
+        /// tokens.
+        PriceOracle {
+            oracle: Option<AccountId>,
+            max_rate_age: BlockNumber,
+        },
+        /// The share of a payment, in basis points, the payment token may withhold as a transfer
+        /// fee.
         ///
-        /// The caller should make an approval for at least `price()*amount` reward tokens to make sure the
-        /// call will succeed. The caller can specify a `max_price` - the call will fail if the
-        /// current price is greater than that.
-        #[ink(message)]
-        pub fn buy(&mut self, amount: u128, max_price: Option<Balance>) -> Result<(), Error> {
-            if self.available_asset() < amount || amount < 1 {
-                return Err(Error::InsufficientSupplyToken);
-            }
+        /// When set, every payment is verified by measuring the balances of its recipients, the
+        /// purchase fails if less than the price minus the tolerance arrives, and proceeds are
+        /// accounted by the amount actually received. `None` skips the verification for tokens
+        /// without transfer fees, saving the balance queries.
+        PaymentFeeTolerance(Option<u16>),
+        /// The granularity of the price in payment tokens. Every quoted and charged price is
+        /// rounded up, against the buyer, to a multiple of it. Zero disables rounding.
+        TickSize(u128),
+        /// The number of asset tokens in a lot, purchases must be whole lots. Zero allows any
+        /// amount. Can't be changed once asset tokens have been sold or ordered.
+        LotSize(u128),
+        /// The maximum total paid by buyers, `None` for no cap. Purchases that would exceed it
+        /// fail with `CapReached`.
+        ProceedsCap(Option<u128>),
+        /// The asset tokens released for sale per block since the start, `None` to release the
+        /// whole inventory at once.
+        ReleaseRate(Option<u128>),
+        /// The payment tokens paid to the account calling `finalize`. They are held back from
+        /// the first proceeds until the bounty is covered.
+        FinalizeBounty(u128),
+        /// The share of a filled bid's price, in basis points, paid to the account calling
+        /// `match_bids`. The fee is deducted from the proceeds forwarded to the owner.
+        KeeperFee(u16),
+        /// The number of blocks after `end_time` during which only allowlisted buyers can buy
+        /// the remaining asset tokens, at the minimum price.
+        GracePeriod(BlockNumber),
+        /// The number of blocks between proposing a change and executing it. A new timelock
+        /// only applies to changes proposed after it was executed.
+        Timelock(BlockNumber),
+        /// The limit on increases of the minimum price, `None` to only apply the timelock.
+        MinPriceIncreaseLimit(Option<MinPriceIncreaseLimit>),
+        /// The sale phases, ordered by strictly increasing `start_block`. Before the first phase
+        /// starts, and when no phases are configured, the sale is only restricted by
+        /// `allowlist_root()`. Reservations are not subject to phases.
+        Phases(Vec<Phase>),
+        /// The volume discount schedule. A purchase receives the largest discount among the
+        /// tiers its amount qualifies for. The volume discount is part of the quoted total, and
+        /// phase, voucher and loyalty discounts apply on top of it. Empty disables it.
+        VolumeTiers(Vec<VolumeTier>),
+        /// The loyalty discount schedule. A purchase receives the largest discount among the
+        /// tiers its buyer qualifies for by the asset tokens bought in earlier purchases. Like
+        /// phase and voucher discounts, only the largest applicable discount is granted. Empty
+        /// disables it.
+        LoyaltyTiers(Vec<LoyaltyTier>),
+        /// The donation taken from every payment. While set, `bps` of every payment is
+        /// transferred to `recipient` before the remainder is split between the owner, the
+        /// liquidity share and burning. `None` stops donating.
+        Donation(Option<Donation>),
+        /// The DEX liquidity seeding configuration.
+        ///
+        /// While set, `proceeds_bps` of every payment is kept by the contract instead of being
+        /// forwarded to the owner, and `finalize` deposits it together with `asset_amount` asset
+        /// tokens from the contract's balance into the pair through `router`. Those asset tokens
+        /// are held back from the sale, and only the part not owed to buyers is deposited. The
+        /// liquidity tokens are minted to the owner. `None` forwards all proceeds again;
+        /// proceeds already held back are returned by `terminate`.
+        LiquidityConfig(Option<LiquidityConfig>),
+    }
 
-            let price = self.current_price().saturating_mul(amount);
-            if let Some(max_price) = max_price {
-                if price > max_price {
-                    return Err(Error::MaxPriceExceeded);
-                }
-            }
+    /// Protects buyers from the owner raising the floor as soon as demand appears.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct MinPriceIncreaseLimit {
+        /// Minimum number of blocks between proposing an increase and executing it, if longer
+        /// than the timelock.
+        pub delay: BlockNumber,
+        /// Maximum increase per change in basis points of the current minimum price.
+        pub max_increase_bps: u16,
+    }
 
-            let caller = self.env().caller();
+    /// A pending parameter change.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Proposal {
+        pub change: ParameterChange,
+        /// First block at which the change can be executed.
+        pub executable_at: BlockNumber,
+    }
 
-            self.take_payment(caller, price)?;
-            self.give_asset(caller, amount)?;
+    /// Human-readable description of the sale, rendered by explorers and launchpad UIs.
+    #[derive(Clone, Default, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Metadata {
+        /// At most `MAX_NAME_LEN` bytes.
+        pub name: String,
+        /// At most `MAX_DESCRIPTION_LEN` bytes.
+        pub description: String,
+        /// At most `MAX_URI_LEN` bytes.
+        pub logo_uri: String,
+        /// At most `MAX_URI_LEN` bytes.
+        pub terms_uri: String,
+    }
 
-            self.env().emit_event(AssetBought {
-                price,
-                by: caller,
-                amount,
-            });
+    /// The `PSP22Metadata` of a token, with `None` fields if the token doesn't implement the
+    /// extension.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct TokenMetadata {
+        pub token: AccountId,
+        pub name: Option<String>,
+        pub symbol: Option<String>,
+        pub decimals: Option<u8>,
+    }
 
-            Ok(())
-        }
+    /// Progress of the auction, as returned by `progress`.
+    #[derive(Default, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Progress {
+        /// Blocks since `start_block()`, zero before the start.
+        pub blocks_elapsed: BlockNumber,
+        /// Blocks until `end_time()`, zero once reached.
+        pub blocks_remaining: BlockNumber,
+        /// Percentage (0-100) of the decay from the start price to the minimum price completed.
+        pub price_decay_percentage: u8,
+        /// Percentage (0-100) of the offered asset tokens sold, as in `sold_percentage`.
+        pub sold_percentage: u8,
+    }
 
-        /// Terminates the contract
-        ///
-        /// Requires auction_owner to execute.
-        #[ink(message)]
-        pub fn terminate(&mut self) -> Result<(), Error> {
-            let caller = self.env().caller();
-            self.check_owner(caller)?;
-            self.env().terminate_contract(caller)
-        }
+    /// Cost breakdown of a prospective `buy`, as returned by `simulate_buy`.
+    ///
+    /// The payment split is the nominal one, before any transfer fee of the payment token.
+    /// The auction pays no royalties; the donation is the only share paid to a third party.
+    #[derive(Default, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct BuySimulation {
+        /// Payment price per asset token, after the volume discount but before any other.
+        pub unit_price: u128,
+        /// Payment charged to the buyer.
+        pub total: u128,
+        /// Payment waived by the phase or loyalty discount.
+        pub discount: u128,
+        /// Bonus asset tokens delivered on top of the purchased amount.
+        pub bonus: u128,
+        /// Share of the payment sent to the donation recipient.
+        pub donation: u128,
+        /// Share of the payment held back for the liquidity pool.
+        pub liquidity: u128,
+        /// Share of the payment held back to fund the finalize bounty.
+        pub bounty: u128,
+        /// Share of the payment burned.
+        pub burned: u128,
+        /// Share of the payment forwarded to the proceeds recipient.
+        pub proceeds: u128,
+        /// The error `buy` would fail with, if any.
+        pub error: Option<Error>,
+    }
 
-        fn current_price(&self) -> u128 {
-            Self::linear_decrease(
-                self.start_time.into(),
-                self.start_price,
-                self.end_time.into(),
-                self.min_price,
-                self.env().block_number().into(),
-            )
-            .max(self.min_price)
-        }
+    /// Total price and bonus of a prospective purchase.
+    struct Quote {
+        total: u128,
+        bonus: u128,
+    }
 
-        fn take_payment(&mut self, from: AccountId, amount: u128) -> Result<(), Error> {
-            let call = self
-                .payment_token
-                .transfer_from(from, self.auction_owner, amount, vec![]);
+    /// A purchase encoded in the `data` of a payment token transfer to the auction.
+    ///
+    /// The payment tokens transferred cap the price paid, along with `max_price` if set.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct BuyOrder {
+        pub amount: u128,
+        pub max_price: Option<u128>,
+    }
 
-            match call {
-                Err(psp22_err) => Err(Error::from(psp22_err)),
-                Ok(()) => Ok(()),
-            }
-        }
+    /// Where the payment of a purchase comes from.
+    #[derive(Clone, Copy, Eq, PartialEq)]
+    enum Payer {
+        /// Pulled from the account with `transfer_from`, or taken from the contract's own balance
+        /// if it's the auction itself.
+        Account(AccountId),
+        /// Being transferred to the auction by the transfer that called `before_received`.
+        Incoming,
+    }
+
+    /// Version of the `Config` layout written by this code.
+    pub const CONFIG_VERSION: u8 = 18;
+
+    /// The sale parameters of the auction.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Config {
+        /// Layout version, bumped whenever fields are added or reinterpreted.
+        pub version: u8,
+        pub asset_token: AccountId,
+        pub payment_token: AccountId,
+        pub start_price: u128,
+        pub min_price: u128,
+        pub start_time: BlockNumber,
+        pub end_time: BlockNumber,
+        pub price_strategy: Option<AccountId>,
+        pub burn_unsold: bool,
+        pub price_oracle: Option<AccountId>,
+        pub max_rate_age: BlockNumber,
+        pub weights: Option<WeightSchedule>,
+        pub payment_fee_tolerance_bps: Option<u16>,
+        pub raise_goal: Option<u128>,
+        pub collect_orders: bool,
+        /// Granularity of the price in payment tokens. Prices are rounded up to a multiple of it,
+        /// zero disables rounding.
+        pub tick_size: u128,
+        /// Number of asset tokens in a lot. Purchases, orders and bids must be whole lots, zero
+        /// allows any amount.
+        pub lot_size: u128,
+        /// Whether purchased asset tokens are minted through `PSP22Mintable` on delivery, up to
+        /// `sale_cap`, instead of being taken from deposits.
+        pub mint_on_demand: bool,
+        /// Whether received payment tokens are burned through `PSP22Burnable` instead of being
+        /// forwarded to the owner. Fixed at construction.
+        pub burn_proceeds: bool,
+        /// Maximum total paid by buyers. Purchases that would exceed it fail, and the auction
+        /// can be finalized once not even one more asset token fits.
+        pub proceeds_cap: Option<u128>,
+        /// Whether purchases only allocate asset tokens, claimed with `claim_assets` once the
+        /// owner calls `open_claims`, e.g. at the token generation event.
+        pub deferred_delivery: bool,
+        /// Number of blocks after `end_time` during which buyers proving allowlist membership
+        /// can buy the remaining asset tokens at `min_price`. The auction can't be finalized
+        /// before the grace period is over, unless sold out.
+        pub grace_period: BlockNumber,
+        /// Whether every purchase updates the top-buyer `leaderboard`. Disabling it saves the
+        /// gas of maintaining it.
+        pub track_leaderboard: bool,
+        /// Restart the price curve tranche by tranche instead of ending at `end_time`.
+        pub perpetual: Option<Perpetual>,
+        /// Asset tokens released for sale per block since `start_time`. Sales and reservations
+        /// can't exceed the released amount, so the inventory can't be swept at once.
+        pub release_rate: Option<u128>,
+        /// Whether the payment token is the chain's wrapped native token. Native value sent
+        /// with `buy` is then wrapped through its `deposit` message and used as payment.
+        pub wrapped_native: bool,
+        /// Compliance contract implementing `SalePolicy`, consulted before every purchase.
+        pub sale_policy: Option<AccountId>,
+    }
+
+    /// Terms of a perpetual auction, selling its inventory in tranches of `tranche_size`.
+    ///
+    /// Each tranche runs the curve from its start price down to `min_price` over `duration`
+    /// blocks. A tranche that sells out starts the next one at its last sale price multiplied by
+    /// `restart_bps`. A tranche that reaches the end of its curve restarts from the same start
+    /// price.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Perpetual {
+        /// Asset tokens sold per tranche, including bonuses.
+        pub tranche_size: u128,
+        /// Number of blocks the curve of a tranche takes to reach `min_price`.
+        pub duration: BlockNumber,
+        /// Multiplier in basis points applied to the last sale price of a sold out tranche to
+        /// get the start price of the next one.
+        pub restart_bps: u32,
+    }
+
+    /// A tranche of a perpetual auction.
+    #[derive(Clone, Default, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Tranche {
+        pub index: u32,
+        pub start_block: BlockNumber,
+        pub start_price: u128,
+        /// Asset tokens sold in the tranche, including bonuses.
+        pub sold: u128,
+    }
 
-        fn give_asset(&mut self, to: AccountId, amount: u128) -> Result<(), Error> {
-            let call = self.asset_token.transfer(to, amount, vec![]);
+    /// The parameters of a new auction, see `new`.
+    ///
+    /// Every field but the tokens, prices and `end_time` defaults to the setting of an auction
+    /// without the feature, e.g. no weights, rounding, lots, caps, release schedule or grace
+    /// period. The default tokens are the zero account, rejected as identical tokens.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct AuctionParams {
+        pub asset_token: AccountId,
+        pub payment_token: AccountId,
+        pub start_price: u128,
+        pub min_price: u128,
+        pub end_time: BlockNumber,
+        /// Price the auction by a liquidity-bootstrapping weight schedule instead of a straight
+        /// line.
+        pub weights: Option<WeightSchedule>,
+        /// Burn the payment tokens received instead of forwarding them. Can't be changed later.
+        pub burn_proceeds: bool,
+        pub payment_fee_tolerance_bps: Option<u16>,
+        pub tick_size: u128,
+        pub lot_size: u128,
+        pub proceeds_cap: Option<u128>,
+        pub release_rate: Option<u128>,
+        pub grace_period: BlockNumber,
+    }
 
-            match call {
-                Err(psp22_err) => Err(Error::from(psp22_err)),
-                Ok(()) => Ok(()),
+    impl Default for AuctionParams {
+        fn default() -> Self {
+            Self {
+                asset_token: AccountId::from([0; 32]),
+                payment_token: AccountId::from([0; 32]),
+                start_price: 0,
+                min_price: 0,
+                end_time: 0,
+                weights: None,
+                burn_proceeds: false,
+                payment_fee_tolerance_bps: None,
+                tick_size: 0,
+                lot_size: 0,
+                proceeds_cap: None,
+                release_rate: None,
+                grace_period: 0,
             }
         }
+    }
 
-        fn asset_balance(&self) -> u128 {
-            self.asset_token.balance_of(self.auction_owner)
-        }
+    /// Fields of a cloned auction's configuration to replace, see `try_clone_from`.
+    #[derive(Clone, Default, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct ConfigOverrides {
+        pub start_price: Option<u128>,
+        pub min_price: Option<u128>,
+        /// Defaults to the cloned auction's duration from the current block.
+        pub end_time: Option<BlockNumber>,
+    }
 
-        /// Returns (an approximation of) the linear function passing through `(x_start, y_start)` and `(x_end, y_end)` at
-        /// `x`. If `x` is outside the range of `x_start` and `x_end`, the value of `y` at the closest endpoint is returned.
-        fn linear_decrease(
-            x_start: u128,
-            y_start: u128,
-            x_end: u128,
-            y_end: u128,
-            x: u128,
-        ) -> u128 {
-            let steps = x.saturating_sub(x_start);
-            let x_span = x_end.saturating_sub(x_start);
-            let y_span = y_start.saturating_sub(y_end);
+    /// A share of every payment donated to `recipient`, on top of any other fees.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Donation {
+        pub recipient: AccountId,
+        pub bps: u16,
+    }
 
-            if x >= x_end {
-                y_end
-            } else if x <= x_start {
-                y_start
-            } else if y_span > x_span {
-                let y_per_x = y_span.saturating_div(x_span);
-                y_start.saturating_sub(steps.saturating_mul(y_per_x))
-            } else {
-                let x_per_y = x_span.saturating_div(y_span);
-                y_start.saturating_sub(steps.saturating_div(x_per_y))
-            }
+    /// Liquidity-bootstrapping weights of the asset token, in basis points, with the payment
+    /// token weighted by the remainder.
+    ///
+    /// The asset weight moves linearly from `start_asset_weight_bps` at `start_time` to
+    /// `end_asset_weight_bps` at `end_time`, and the price follows the ratio of the two
+    /// weights the way a weighted pool's spot price does for constant balances.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct WeightSchedule {
+        pub start_asset_weight_bps: u16,
+        pub end_asset_weight_bps: u16,
+    }
+
+    /// Terms applying to new reservations.
+    #[derive(Clone, Default, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct ReservationTerms {
+        /// Number of blocks a reservation remains exercisable. Zero disables reservations.
+        pub window: BlockNumber,
+        /// Deposit in basis points of the reserved total.
+        pub deposit_bps: u16,
+    }
+
+    /// Cumulative purchase totals as of the end of `block`.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Observation {
+        pub block: BlockNumber,
+        /// Sum of the total prices paid.
+        pub cumulative_paid: u128,
+        /// Sum of the asset tokens bought.
+        pub cumulative_amount: u128,
+    }
+
+    /// The price and total sold as of the end of `block`.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Checkpoint {
+        pub block: BlockNumber,
+        /// Price of a single asset token.
+        pub price: u128,
+        /// Asset tokens sold so far, including bonuses.
+        pub cumulative_sold: u128,
+    }
+
+    /// A price lock held by a buyer.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Reservation {
+        /// Amount of asset tokens reserved.
+        pub amount: u128,
+        /// Locked total price of `amount`, after the buyer's discounts.
+        pub total: u128,
+        /// Bonus asset tokens locked in with the price.
+        pub bonus: u128,
+        /// Deposit held by the contract, credited to the purchase when exercised.
+        pub deposit: u128,
+        /// Last block at which the reservation can be exercised.
+        pub expires_at: BlockNumber,
+    }
+
+    /// Result of checking the internal invariants of the auction.
+    #[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct AuditReport {
+        /// Asset tokens held by the contract.
+        pub escrowed: u128,
+        /// Asset tokens left for sale, regardless of the release schedule.
+        pub available: u128,
+        /// `escrowed >= available + reserved + allocated + liquidity`, i.e. the contract holds every
+        /// asset token it still offers, owes or holds back for liquidity. Always true when minting
+        /// on demand.
+        pub escrow_covers_available: bool,
+        /// `min_price <= start_price`.
+        pub price_range_valid: bool,
+        /// `start_time <= end_time`.
+        pub time_range_valid: bool,
+        /// `total_sold + available + reserved + liquidity == sale_cap`, when assets were deposited
+        /// through `PSP22Receiver`.
+        pub supply_consistent: bool,
+    }
+
+    impl AuditReport {
+        /// Whether all the checked invariants hold.
+        pub fn is_healthy(&self) -> bool {
+            self.escrow_covers_available
+                && self.price_range_valid
+                && self.time_range_valid
+                && self.supply_consistent
         }
+    }
 
-        fn check_owner(&self, account: AccountId) -> Result<(), Error> {
-            if account != self.auction_owner {
-                return Err(Error::NotAuctionOwner);
-            }
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct AssetBought {
+        #[ink(topic)]
+        pub by: AccountId,
+        #[ink(topic)]
+        pub purchase_id: u64,
+        pub price: u128,
+        pub amount: u128,
+        pub bonus: u128,
+        /// Payload passed to `buy`, empty for other kinds of purchases.
+        pub data: Vec<u8>,
+        pub seq: u64,
+    }
 
-            Ok(())
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct AssetDeposited {
+        #[ink(topic)]
+        pub from: AccountId,
+        pub amount: u128,
+        pub sale_cap: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct Reserved {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub amount: u128,
+        pub total: u128,
+        pub deposit: u128,
+        pub expires_at: BlockNumber,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct ReservationForfeited {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub amount: u128,
+        pub deposit: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct EscrowReconciled {
+        pub expected: u128,
+        pub actual: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct OrderPlaced {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub amount: u128,
+        pub paid: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct OrderSettled {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub filled: u128,
+        pub cost: u128,
+        pub refund: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct BidPlaced {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub id: u32,
+        pub amount: u128,
+        pub limit_price: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct BidFilled {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub id: u32,
+        pub price: u128,
+        #[ink(topic)]
+        pub keeper: AccountId,
+        pub keeper_fee: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct BidCancelled {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub id: u32,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct ChangeProposed {
+        #[ink(topic)]
+        pub id: u32,
+        pub change: ParameterChange,
+        pub executable_at: BlockNumber,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct ChangeExecuted {
+        #[ink(topic)]
+        pub id: u32,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct ChangeCancelled {
+        #[ink(topic)]
+        pub id: u32,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct Refunded {
+        #[ink(topic)]
+        pub by: AccountId,
+        #[ink(topic)]
+        pub purchase_id: u64,
+        pub amount: u128,
+        pub refund: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct LiquiditySeeded {
+        #[ink(topic)]
+        pub router: AccountId,
+        pub asset_amount: u128,
+        pub payment_amount: u128,
+        pub liquidity: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct TerminationScheduled {
+        pub executable_at: BlockNumber,
+        pub seq: u64,
+    }
+
+    /// Emitted once by every constructor, so indexers can reconstruct the configuration without
+    /// reading storage.
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct AuctionInitialized {
+        #[ink(topic)]
+        pub owner: AccountId,
+        pub config: Config,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        pub previous_owner: Option<AccountId>,
+        #[ink(topic)]
+        pub new_owner: Option<AccountId>,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct ParametersLocked {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct ClaimsOpened {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct AllocationTransferred {
+        #[ink(topic)]
+        pub from: AccountId,
+        #[ink(topic)]
+        pub to: AccountId,
+        pub amount: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct BlacklistUpdated {
+        #[ink(topic)]
+        pub account: AccountId,
+        pub blacklisted: bool,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct OperatorApproved {
+        #[ink(topic)]
+        pub principal: AccountId,
+        #[ink(topic)]
+        pub operator: AccountId,
+        pub max_amount: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct MetadataUpdated {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct StateChanged {
+        pub from: AuctionState,
+        pub to: AuctionState,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct TrancheStarted {
+        pub index: u32,
+        pub start_price: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct AuctionFinalized {
+        #[ink(topic)]
+        pub by: AccountId,
+        pub unsold: u128,
+        pub bounty: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct ProceedsBurned {
+        pub amount: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct DonationSent {
+        #[ink(topic)]
+        pub recipient: AccountId,
+        pub amount: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct UnsoldBurned {
+        pub amount: u128,
+        pub seq: u64,
+    }
+
+    #[ink(event)]
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct InventoryRolledOver {
+        #[ink(topic)]
+        pub new_auction: AccountId,
+        pub amount: u128,
+        pub seq: u64,
+    }
+
+    /// Maximum number of entries returned by paginated queries.
+    const MAX_PAGE_SIZE: u32 = 100;
+
+    /// Maximum length of the sale name, in bytes.
+    pub const MAX_NAME_LEN: usize = 64;
+
+    /// Maximum length of the sale description, in bytes.
+    pub const MAX_DESCRIPTION_LEN: usize = 1024;
+
+    /// Maximum length of the logo and terms URIs, in bytes.
+    pub const MAX_URI_LEN: usize = 256;
+
+    /// Number of price checkpoints retained, older checkpoints are overwritten.
+    pub const CHECKPOINT_CAPACITY: u32 = 256;
+
+    /// Number of low bits kept from the unit price after its highest set bit by the price
+    /// histogram: every power of two is split into 8 buckets, each within 1/8 of its lower bound.
+    const PRICE_BUCKET_BITS: u32 = 3;
+
+    /// Number of buyers ranked by the leaderboard.
+    const LEADERBOARD_SIZE: usize = 10;
+
+    /// Maximum number of open standing bids.
+    const MAX_OPEN_BIDS: usize = 64;
+
+    /// Maximum number of recipients of a split purchase.
+    const MAX_SPLIT_RECIPIENTS: usize = 16;
+
+    /// Maximum number of purchase listeners.
+    const MAX_LISTENERS: usize = 8;
+
+    /// Gas each purchase listener or observer may use per notification.
+    const LISTENER_GAS_LIMIT: u64 = 5_000_000_000;
+
+    /// Maximum number of subscribed observers.
+    const MAX_SUBSCRIBERS: usize = 16;
+
+    /// Denominator of values expressed in basis points.
+    const BPS: u128 = 10_000;
+
+    /// The `auction_owner` after ownership has been renounced.
+    const RENOUNCED_OWNER: [u8; 32] = [0; 32];
+
+    impl DutchAuction {
+        /// Constructor that initializes the contract storage after validating `params`.
+        ///
+        /// Fails if `min_price` is greater than `start_price`, if `end_time` is not after the
+        /// current block, if `asset_token` and `payment_token` are the same contract, if the
+        /// asset weight of `weights` is not within `(0, 10000)` basis points or increases over
+        /// time, or if `payment_fee_tolerance_bps` exceeds 10000.
+        ///
+        /// Caller would be the auction_owner
+        #[ink(constructor)]
+        pub fn new(params: AuctionParams) -> Result<Self, Error> {
+            if let Some(weights) = &params.weights {
+                let in_range = |weight: u16| weight > 0 && u128::from(weight) < BPS;
+                if !in_range(weights.start_asset_weight_bps)
+                    || !in_range(weights.end_asset_weight_bps)
+                    || weights.end_asset_weight_bps > weights.start_asset_weight_bps
+                {
+                    return Err(InvalidParameter::InvalidWeights.into());
+                }
+            }
+            if params
+                .payment_fee_tolerance_bps
+                .is_some_and(|bps| u128::from(bps) > BPS)
+            {
+                return Err(InvalidParameter::InvalidBasisPoints.into());
+            }
+
+            let mut auction = Self::try_build(
+                params.asset_token,
+                params.payment_token,
+                params.start_price,
+                params.min_price,
+                params.end_time,
+            )?;
+            auction.config.weights = params.weights;
+            auction.config.burn_proceeds = params.burn_proceeds;
+            auction.config.payment_fee_tolerance_bps = params.payment_fee_tolerance_bps;
+            auction.config.tick_size = params.tick_size;
+            auction.config.lot_size = params.lot_size;
+            auction.config.proceeds_cap = params.proceeds_cap;
+            auction.config.release_rate = params.release_rate;
+            auction.config.grace_period = params.grace_period;
+            auction.emit_initialized();
+
+            Ok(auction)
+        }
+
+        fn build(
+            asset_token: AccountId,
+            payment_token: AccountId,
+            start_price: u128,
+            min_price: u128,
+            end_time: BlockNumber,
+        ) -> Self {
+            Self {
+                auction_owner: Self::env().caller(),
+                treasury: None,
+                config: Config {
+                    version: CONFIG_VERSION,
+                    asset_token,
+                    payment_token,
+                    start_price,
+                    min_price,
+                    start_time: Self::env().block_number(),
+                    end_time,
+                    price_strategy: None,
+                    burn_unsold: false,
+                    price_oracle: None,
+                    max_rate_age: 0,
+                    weights: None,
+                    payment_fee_tolerance_bps: None,
+                    raise_goal: None,
+                    collect_orders: false,
+                    tick_size: 0,
+                    lot_size: 0,
+                    mint_on_demand: false,
+                    burn_proceeds: false,
+                    proceeds_cap: None,
+                    deferred_delivery: false,
+                    grace_period: 0,
+                    track_leaderboard: true,
+                    perpetual: None,
+                    release_rate: None,
+                    wrapped_native: false,
+                    sale_policy: None,
+                },
+                sale_cap: 0,
+                total_sold: 0,
+                finalized: false,
+                state: AuctionState::Pending,
+                paused: false,
+                cancelled: false,
+                locked: false,
+                tracked_escrow: 0,
+                total_raised: 0,
+                allocated: 0,
+                credited: 0,
+                raise_withdrawn: false,
+                held_payments: 0,
+                total_ordered: 0,
+                order_supply: None,
+                open_orders: 0,
+                reserved: 0,
+                observation_count: 0,
+                checkpoint_count: 0,
+                liquidity_proceeds: 0,
+                bounty_reserve: 0,
+                last_sale_price: 0,
+                tranche: Tranche::default(),
+                event_seq: 0,
+                next_purchase_id: 0,
+                metadata: Lazy::default(),
+                strategy_data: Lazy::default(),
+                reservation_terms: Lazy::default(),
+                reservations: Mapping::default(),
+                observations: Mapping::default(),
+                checkpoints: Mapping::default(),
+                price_histogram: Lazy::default(),
+                contributions: Mapping::default(),
+                allocations: Mapping::default(),
+                payment_credits: Mapping::default(),
+                cooling_off: Lazy::default(),
+                orders: Mapping::default(),
+                bids: Mapping::default(),
+                open_bids: Lazy::default(),
+                next_bid_id: Lazy::default(),
+                keeper_fee_bps: Lazy::default(),
+                proposals: Mapping::default(),
+                next_proposal_id: Lazy::default(),
+                timelock: Lazy::default(),
+                min_price_increase_limit: Lazy::default(),
+                parameters_locked: Lazy::default(),
+                purchases: Mapping::default(),
+                purchase_counts: Mapping::default(),
+                purchase_ids: Mapping::default(),
+                buyers: Mapping::default(),
+                buyer_count: Lazy::default(),
+                buyer_totals: Mapping::default(),
+                operator_allowances: Mapping::default(),
+                leaderboard: Lazy::default(),
+                proceeds: Mapping::default(),
+                proceeds_tokens: Lazy::default(),
+                bonus_tiers: Lazy::default(),
+                loyalty_tiers: Lazy::default(),
+                volume_tiers: Lazy::default(),
+                voucher_signer: Lazy::default(),
+                used_vouchers: Mapping::default(),
+                trusted_forwarder: Lazy::default(),
+                relay_nonces: Mapping::default(),
+                allowlist_root: Lazy::default(),
+                blacklist: Mapping::default(),
+                phases: Lazy::default(),
+                phase_purchases: Mapping::default(),
+                rounds: Lazy::default(),
+                round_stats: Mapping::default(),
+                round_start_prices: Mapping::default(),
+                round_purchases: Mapping::default(),
+                claims_open: Lazy::default(),
+                allocation_transfers_disabled: Lazy::default(),
+                flip_restricted: Lazy::default(),
+                allocation_blocks: Mapping::default(),
+                liquidity_config: Lazy::default(),
+                finalize_bounty: Lazy::default(),
+                donation: Lazy::default(),
+                swap_router: Lazy::default(),
+                listeners: Lazy::default(),
+                subscribers: Lazy::default(),
+                subscription_deposits: Mapping::default(),
+                subscription_deposit: Lazy::default(),
+                termination_at: Lazy::default(),
+            }
+        }
+
+        fn try_build(
+            asset_token: AccountId,
+            payment_token: AccountId,
+            start_price: u128,
+            min_price: u128,
+            end_time: BlockNumber,
+        ) -> Result<Self, Error> {
+            Self::check_price_range(start_price, min_price)?;
+            Self::check_time_range(Self::env().block_number(), end_time)?;
+            if asset_token == payment_token {
+                return Err(InvalidParameter::IdenticalTokens.into());
+            }
+
+            Ok(Self::build(
+                asset_token,
+                payment_token,
+                start_price,
+                min_price,
+                end_time,
+            ))
+        }
+
+        /// Constructor that initializes an auction with the configuration of the `existing` one,
+        /// e.g. to rerun a weekly sale, replacing the fields set in `overrides`.
+        ///
+        /// The sale starts at the current block and, unless overridden, runs as long as the
+        /// existing one. Only the configuration is copied: metadata, phases, tiers and other
+        /// settings have to be set up again. Fails if `existing` doesn't return a configuration of
+        /// the same layout version and on the same parameters as `new`.
+        ///
+        /// Caller would be the auction_owner
+        #[ink(constructor)]
+        pub fn try_clone_from(
+            existing: AccountId,
+            overrides: ConfigOverrides,
+        ) -> Result<Self, Error> {
+            let template = build_call::<Environment>()
+                .call(existing)
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                    "config"
+                ))))
+                .returns::<Config>()
+                .try_invoke();
+            let template = match template {
+                Ok(Ok(config)) => config,
+                _ => return Err(Error::InvalidTemplate),
+            };
+            let config = Self::cloned_config(template, overrides, Self::env().block_number())?;
+            // The configuration of a custom price strategy is kept outside of `Config`.
+            let strategy_data = if config.price_strategy.is_some() {
+                let data = build_call::<Environment>()
+                    .call(existing)
+                    .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                        "strategy_data"
+                    ))))
+                    .returns::<Vec<u8>>()
+                    .try_invoke();
+                match data {
+                    Ok(Ok(data)) => data,
+                    _ => return Err(Error::InvalidTemplate),
+                }
+            } else {
+                Vec::new()
+            };
+
+            Self::from_template(config, strategy_data)
+        }
+
+        /// An auction with the cloned `config`, priced by the template's `strategy_data`.
+        fn from_template(config: Config, strategy_data: Vec<u8>) -> Result<Self, Error> {
+            let mut auction = Self::try_build(
+                config.asset_token,
+                config.payment_token,
+                config.start_price,
+                config.min_price,
+                config.end_time,
+            )?;
+            auction.config = config;
+            auction.strategy_data.set(&strategy_data);
+            auction.emit_initialized();
+
+            Ok(auction)
+        }
+
+        /// The configuration of an auction starting at block `now`, cloned from `template`.
+        fn cloned_config(
+            template: Config,
+            overrides: ConfigOverrides,
+            now: BlockNumber,
+        ) -> Result<Config, Error> {
+            if template.version != CONFIG_VERSION {
+                return Err(Error::InvalidTemplate);
+            }
+            let duration = template.end_time.saturating_sub(template.start_time);
+
+            Ok(Config {
+                start_price: overrides.start_price.unwrap_or(template.start_price),
+                min_price: overrides.min_price.unwrap_or(template.min_price),
+                start_time: now,
+                end_time: overrides.end_time.unwrap_or(now.saturating_add(duration)),
+                ..template
+            })
+        }
+
+        fn next_event_seq(&mut self) -> u64 {
+            let seq = self.event_seq;
+            self.event_seq = seq.saturating_add(1);
+            seq
+        }
+
+        fn emit_initialized(&mut self) {
+            let seq = self.next_event_seq();
+            self.env().emit_event(AuctionInitialized {
+                owner: self.auction_owner,
+                config: self.config.clone(),
+                seq,
+            });
+        }
+
+        /// All sale parameters of the auction, tagged with the layout version.
+        #[ink(message)]
+        pub fn config(&self) -> Config {
+            self.config.clone()
+        }
+
+        /// The number of events emitted so far, which is also the `seq` of the next event.
+        ///
+        /// Every event carries its `seq`, counting from zero, so off-chain consumers can detect
+        /// missed events and order them deterministically.
+        #[ink(message)]
+        pub fn events_emitted(&self) -> u64 {
+            self.event_seq
+        }
+
+        /// The name, description and links describing the sale.
+        #[ink(message)]
+        pub fn metadata(&self) -> Metadata {
+            self.metadata.get().unwrap_or_default()
+        }
+
+        /// Update the name, description and links describing the sale.
+        ///
+        /// Each field is bounded, see `MAX_NAME_LEN`, `MAX_DESCRIPTION_LEN` and `MAX_URI_LEN`.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_metadata(&mut self, metadata: Metadata) -> Result<(), Error> {
+            let caller = self.env().caller();
+            self.check_owner(caller)?;
+            self.check_parameters_unlocked()?;
+            if metadata.name.len() > MAX_NAME_LEN
+                || metadata.description.len() > MAX_DESCRIPTION_LEN
+                || metadata.logo_uri.len() > MAX_URI_LEN
+                || metadata.terms_uri.len() > MAX_URI_LEN
+            {
+                return Err(InvalidParameter::MetadataTooLong.into());
+            }
+            self.metadata.set(&metadata);
+            let seq = self.next_event_seq();
+            self.env().emit_event(MetadataUpdated { by: caller, seq });
+
+            Ok(())
+        }
+
+        /// The name, symbol and decimals of the asset token, queried from the token.
+        #[ink(message)]
+        pub fn asset_metadata(&self) -> TokenMetadata {
+            self.token_metadata(self.config.asset_token)
+        }
+
+        /// The name, symbol and decimals of the payment token, queried from the token.
+        #[ink(message)]
+        pub fn payment_metadata(&self) -> TokenMetadata {
+            self.token_metadata(self.config.payment_token)
+        }
+
+        /// The pending parameter change with the given `id`, if any.
+        #[ink(message)]
+        pub fn proposal(&self, id: u32) -> Option<Proposal> {
+            self.proposals.get(id)
+        }
+
+        /// The number of blocks between proposing a parameter change and executing it.
+        #[ink(message)]
+        pub fn timelock(&self) -> BlockNumber {
+            self.timelock.get().unwrap_or_default()
+        }
+
+        /// Propose a change of the sale parameters, to be applied with `execute_change` once
+        /// `timelock()` blocks have passed.
+        ///
+        /// The change is validated against the current parameters and announced with
+        /// `ChangeProposed`. Returns the proposal ID.
+        ///
+        /// Increases of the minimum price wait for the delay of `min_price_increase_limit()`
+        /// instead, if longer, while decreases can only help buyers and are executed at once.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn propose_change(&mut self, change: ParameterChange) -> Result<u32, Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.check_change(&change)?;
+            let id = self.next_proposal_id.get().unwrap_or_default();
+            self.next_proposal_id.set(&(id + 1));
+            let lowers_floor = matches!(
+                change,
+                ParameterChange::MinPrice(min_price) if min_price <= self.config.min_price
+            );
+            let delay = match (&change, self.min_price_increase_limit()) {
+                _ if lowers_floor => 0,
+                (ParameterChange::MinPrice(_), Some(limit)) => self.timelock().max(limit.delay),
+                _ => self.timelock(),
+            };
+            let executable_at = self.env().block_number().saturating_add(delay);
+            self.proposals.insert(
+                id,
+                &Proposal {
+                    change: change.clone(),
+                    executable_at,
+                },
+            );
+            let seq = self.next_event_seq();
+            self.env().emit_event(ChangeProposed {
+                id,
+                change,
+                executable_at,
+                seq,
+            });
+            if lowers_floor {
+                self.execute_change(id)?;
+            }
+
+            Ok(id)
+        }
+
+        /// The delay and maximum size of increases of the minimum price, `None` if they only
+        /// wait for the timelock.
+        #[ink(message)]
+        pub fn min_price_increase_limit(&self) -> Option<MinPriceIncreaseLimit> {
+            self.min_price_increase_limit.get().flatten()
+        }
+
+        /// Apply the pending parameter change `id` once its timelock expired.
+        ///
+        /// The change is validated again, since other changes may have been applied since it
+        /// was proposed.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn execute_change(&mut self, id: u32) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            let proposal = self.proposals.get(id).ok_or(Error::NoProposal)?;
+            if self.env().block_number() < proposal.executable_at {
+                return Err(Error::TimelockActive);
+            }
+            self.check_change(&proposal.change)?;
+            self.proposals.remove(id);
+            self.apply_change(proposal.change);
+            let seq = self.next_event_seq();
+            self.env().emit_event(ChangeExecuted { id, seq });
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// Withdraw the pending parameter change `id`.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn cancel_change(&mut self, id: u32) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.proposals.take(id).ok_or(Error::NoProposal)?;
+            let seq = self.next_event_seq();
+            self.env().emit_event(ChangeCancelled { id, seq });
+
+            Ok(())
+        }
+
+        /// Whether the sale parameters are permanently locked by `lock_parameters`.
+        #[ink(message)]
+        pub fn is_locked(&self) -> bool {
+            self.parameters_locked.get().unwrap_or_default()
+        }
+
+        /// Permanently lock the sale parameters. Afterwards every setter, `propose_change` and
+        /// `execute_change` fail, guaranteeing buyers that the price curve, floor and fees no
+        /// longer move. Pending proposals can only be cancelled.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn lock_parameters(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            self.check_owner(caller)?;
+            self.check_parameters_unlocked()?;
+            self.parameters_locked.set(&true);
+            let seq = self.next_event_seq();
+            self.env().emit_event(ParametersLocked { by: caller, seq });
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// The external contract computing the price curve, if any.
+        ///
+        /// When `None`, or while calls to the strategy fail, the price follows the built-in curve
+        /// between `start_time()` and `end_time()`.
+        #[ink(message)]
+        pub fn price_strategy(&self) -> Option<AccountId> {
+            self.config.price_strategy
+        }
+
+        /// The configuration passed to the external price strategy.
+        #[ink(message)]
+        pub fn strategy_data(&self) -> Vec<u8> {
+            self.strategy_data.get().unwrap_or_default()
+        }
+
+        /// Total amount of asset tokens deposited for sale through `PSP22Receiver`, or the
+        /// amount that may be minted in mint-on-demand mode.
+        #[ink(message)]
+        pub fn sale_cap(&self) -> u128 {
+            self.sale_cap
+        }
+
+        /// Pass `Some(sale_cap)` to mint purchased asset tokens on delivery instead of escrowing
+        /// pre-minted supply, and `None` to sell the deposited asset tokens again.
+        ///
+        /// In mint-on-demand mode the auction must hold the mint rights of the asset token, and
+        /// `available_asset()` is what remains of `sale_cap` after sales and reservations.
+        ///
+        /// Requires auction_owner to execute before any asset tokens are sold.
+        #[ink(message)]
+        pub fn set_mint_on_demand(&mut self, sale_cap: Option<u128>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            if self.total_sold > 0 || self.reserved > 0 || self.open_orders > 0 {
+                return Err(Error::SaleAlreadyStarted);
+            }
+            self.config.mint_on_demand = sale_cap.is_some();
+            // Without sales, the deposits tracked so far are the whole supply for sale.
+            self.sale_cap = sale_cap.unwrap_or(self.tracked_escrow);
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// Number of whole lots sold so far, or `sold()` when no lot size is set.
+        ///
+        /// Bonus asset tokens are not counted.
+        #[ink(message)]
+        pub fn lots_sold(&self) -> u128 {
+            match self.config.lot_size {
+                0 => self.total_sold,
+                lot_size => self.total_sold / lot_size,
+            }
+        }
+
+        /// Total paid by buyers for all purchases, including refunded ones, counted against the
+        /// proceeds cap.
+        #[ink(message)]
+        pub fn total_paid(&self) -> u128 {
+            self.observation_count
+                .checked_sub(1)
+                .and_then(|index| self.observations.get(index))
+                .map_or(0, |observation| observation.cumulative_paid)
+        }
+
+        /// Percentage (0-100) of the offered asset tokens that has been sold so far.
+        ///
+        /// The offered amount is the sum of `sold()` and the asset tokens left for sale, whether
+        /// already released or not.
+        #[ink(message)]
+        pub fn sold_percentage(&self) -> u8 {
+            let offered = self.total_sold.saturating_add(self.inventory());
+            if offered == 0 {
+                return 0;
+            }
+
+            (self.total_sold.saturating_mul(100) / offered) as u8
+        }
+
+        /// Progress of the auction at the current block, so every frontend shows the same numbers.
+        ///
+        /// The price decay is measured on the reference price between the configured start and
+        /// minimum prices, before any oracle conversion or tick rounding.
+        #[ink(message)]
+        pub fn progress(&self) -> Progress {
+            let now = self.env().block_number();
+            let decay = self
+                .config
+                .start_price
+                .saturating_sub(self.config.min_price);
+            let decayed = self
+                .config
+                .start_price
+                .saturating_sub(self.reference_price_at(now));
+            let price_decay_percentage = match decay {
+                0 => 100,
+                decay => (decayed.min(decay).saturating_mul(100) / decay) as u8,
+            };
+
+            Progress {
+                blocks_elapsed: now.saturating_sub(self.config.start_time),
+                blocks_remaining: self.config.end_time.saturating_sub(now),
+                price_decay_percentage,
+                sold_percentage: self.sold_percentage(),
+            }
+        }
+
+        /// The number of blocks a reservation remains exercisable and the deposit it requires, in
+        /// basis points of the reserved total.
+        #[ink(message)]
+        pub fn reservation_terms(&self) -> (BlockNumber, u16) {
+            let terms = self.reservation_terms.get().unwrap_or_default();
+            (terms.window, terms.deposit_bps)
+        }
+
+        /// Update the reservation terms. A `window` of zero disables new reservations.
+        ///
+        /// Outstanding reservations keep their original terms.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_reservation_terms(
+            &mut self,
+            window: BlockNumber,
+            deposit_bps: u16,
+        ) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            if u128::from(deposit_bps) > BPS {
+                return Err(InvalidParameter::InvalidBasisPoints.into());
+            }
+            self.reservation_terms.set(&ReservationTerms {
+                window,
+                deposit_bps,
+            });
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// The outstanding reservation of `account`, if any.
+        #[ink(message)]
+        pub fn reservation_of(&self, account: AccountId) -> Option<Reservation> {
+            self.reservations.get(account)
+        }
+
+        /// Lock the current price for `amount` asset tokens.
+        ///
+        /// The caller pays a deposit of `reservation_terms()` basis points of the total and gets the
+        /// right to buy `amount` for that total until `reservation_window` blocks from now. The
+        /// total is quoted at the current price after the caller's phase and loyalty discounts.
+        /// The reserved tokens are not available to other buyers meanwhile. The deposit counts
+        /// towards the payment when exercised and is forfeited to the owner otherwise.
+        ///
+        /// The caller should make an approval for at least the deposit.
+        #[ink(message)]
+        pub fn reserve(&mut self, amount: u128) -> Result<(), Error> {
+            let terms = self.reservation_terms.get().unwrap_or_default();
+            if terms.window == 0 || self.config.raise_goal.is_some() {
+                return Err(Error::ReservationsDisabled);
+            }
+            let caller = self.env().caller();
+            if self.reservations.contains(caller) {
+                return Err(Error::ReservationExists);
+            }
+
+            let Quote { total, bonus } = self.discounted_quote(caller, amount, false, 0)?;
+            self.check_purchase(caller, amount, false)?;
+            let deposit = total
+                .checked_mul(terms.deposit_bps.into())
+                .ok_or(Error::ArithmeticOverflow)?
+                / BPS;
+            Self::transfer_token_from(
+                self.payment(),
+                caller,
+                self.env().account_id(),
+                deposit,
+                &[],
+            )?;
+
+            let reservation = Reservation {
+                amount,
+                total,
+                bonus,
+                deposit,
+                expires_at: self.env().block_number().saturating_add(terms.window),
+            };
+            self.reservations.insert(caller, &reservation);
+            self.reserved = self.reserved.saturating_add(amount.saturating_add(bonus));
+
+            let seq = self.next_event_seq();
+            self.env().emit_event(Reserved {
+                by: caller,
+                amount,
+                total,
+                deposit,
+                expires_at: reservation.expires_at,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        /// Buy the asset tokens reserved by the caller at the locked price.
+        ///
+        /// The caller should make an approval for at least the locked total minus the deposit.
+        #[ink(message)]
+        pub fn exercise_reservation(&mut self) -> Result<(), Error> {
+            self.lock()?;
+            self.check_not_finalized()?;
+            self.check_not_paused()?;
+            let caller = self.env().caller();
+            let reservation = self.reservations.get(caller).ok_or(Error::NoReservation)?;
+            if self.env().block_number() > reservation.expires_at {
+                return Err(Error::ReservationExpired);
+            }
+
+            let price = reservation.total;
+            let delivered = reservation.amount.saturating_add(reservation.bonus);
+            self.reservations.remove(caller);
+            self.reserved = self.reserved.saturating_sub(delivered);
+            self.admit_purchase(caller, reservation.amount, reservation.bonus, price)?;
+            self.record_purchase(
+                caller,
+                reservation.amount,
+                reservation.bonus,
+                price,
+                false,
+                &[],
+            );
+
+            self.collect_payment(caller, price.saturating_sub(reservation.deposit), &[])?;
+            if self.cooling_off.get().unwrap_or_default().window > 0 {
+                self.held_payments = self.held_payments.saturating_add(reservation.deposit);
+            } else {
+                Self::transfer_token(
+                    self.payment(),
+                    self.proceeds_recipient(),
+                    reservation.deposit,
+                    &[],
+                )?;
+                self.record_proceeds(reservation.deposit);
+            }
+            self.deliver_asset(caller, delivered, &[])?;
+            self.reconcile_escrow();
+            self.unlock();
+
+            self.notify_listeners(caller, reservation.amount, price);
+
+            Ok(())
+        }
+
+        /// Release the expired reservation of `account`, forfeiting its deposit to the owner.
+        ///
+        /// Callable by anyone.
+        #[ink(message)]
+        pub fn forfeit_reservation(&mut self, account: AccountId) -> Result<(), Error> {
+            let reservation = self.reservations.get(account).ok_or(Error::NoReservation)?;
+            if self.env().block_number() <= reservation.expires_at {
+                return Err(Error::ReservationActive);
+            }
+
+            self.reservations.remove(account);
+            self.reserved = self
+                .reserved
+                .saturating_sub(reservation.amount.saturating_add(reservation.bonus));
+            Self::transfer_token(
+                self.payment(),
+                self.proceeds_recipient(),
+                reservation.deposit,
+                &[],
+            )?;
+
+            let seq = self.next_event_seq();
+            self.env().emit_event(ReservationForfeited {
+                by: account,
+                amount: reservation.amount,
+                deposit: reservation.deposit,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        /// Number of blocks left until `end_time()`, zero once it has passed.
+        #[ink(message)]
+        pub fn remaining_blocks(&self) -> BlockNumber {
+            self.config
+                .end_time
+                .saturating_sub(self.env().block_number())
+        }
+
+        /// Number of blocks until the price drops to `target` or below.
+        ///
+        /// Returns `Some(0)` if the current price is already at or below `target` and `None` if the
+        /// price never reaches it. Assumes the configured curve never increases over time.
+        #[ink(message)]
+        pub fn blocks_until_price(&self, target: u128) -> Option<BlockNumber> {
+            let now = self.env().block_number();
+            let end = self.config.end_time.max(now);
+            if self.price_at_block(end) > target {
+                return None;
+            }
+
+            let (mut low, mut high) = (now, end);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if self.price_at_block(mid) <= target {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+
+            Some(low - now)
+        }
+
+        /// Number of purchases made by `account`.
+        #[ink(message)]
+        pub fn purchase_count_of(&self, account: AccountId) -> u32 {
+            self.purchase_counts.get(account).unwrap_or_default()
+        }
+
+        /// The buyer and details of the purchase with the contract-wide `id`, as emitted in
+        /// `AssetBought`. IDs are assigned in order starting at 0.
+        #[ink(message)]
+        pub fn purchase(&self, id: u64) -> Option<(AccountId, Purchase)> {
+            let (buyer, index) = self.purchase_ids.get(id)?;
+            Some((buyer, self.purchases.get((buyer, index))?))
+        }
+
+        /// Purchases made by `account`, oldest first, starting at its `from`-th purchase.
+        ///
+        /// At most `limit` purchases are returned, capped at 100 per call.
+        #[ink(message)]
+        pub fn purchases_of(&self, account: AccountId, from: u32, limit: u32) -> Vec<Purchase> {
+            let to = from
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.purchase_count_of(account));
+
+            (from..to)
+                .filter_map(|index| self.purchases.get((account, index)))
+                .collect()
+        }
+
+        /// Number of distinct accounts that bought asset tokens.
+        #[ink(message)]
+        pub fn buyer_count(&self) -> u32 {
+            self.buyer_count.get().unwrap_or_default()
+        }
+
+        /// Distinct buyers with their totals, in the order of their first purchase, starting at
+        /// the `from`-th buyer.
+        ///
+        /// At most `limit` buyers are returned, capped at 100 per call. Buyers that had all their
+        /// purchases refunded are included with zero totals.
+        #[ink(message)]
+        pub fn buyers(&self, from: u32, limit: u32) -> Vec<(AccountId, BuyerTotals)> {
+            let to = from
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.buyer_count());
+
+            (from..to)
+                .filter_map(|index| self.buyers.get(index))
+                .map(|buyer| (buyer, self.buyer_totals_of(buyer)))
+                .collect()
+        }
+
+        /// First purchase block and totals bought and paid by `account`.
+        #[ink(message)]
+        pub fn buyer_totals_of(&self, account: AccountId) -> BuyerTotals {
+            self.buyer_totals.get(account).unwrap_or_default()
+        }
+
+        /// The buyers that bought the most asset tokens, including bonuses and net of refunds,
+        /// with their totals, largest first.
+        ///
+        /// At most 10 buyers are ranked. A buyer dropping off after a refund is only replaced
+        /// by the next purchase of another buyer.
+        #[ink(message)]
+        pub fn leaderboard(&self) -> Vec<(AccountId, u128)> {
+            self.leaderboard.get().unwrap_or_default()
+        }
+
+        /// Enable or disable the top-buyer leaderboard, which is enabled by default.
+        ///
+        /// Requires auction_owner to execute before any asset tokens are sold.
+        #[ink(message)]
+        pub fn set_leaderboard(&mut self, enabled: bool) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            if self.total_sold > 0 || self.reserved > 0 || self.open_orders > 0 {
+                return Err(Error::SaleAlreadyStarted);
+            }
+            self.config.track_leaderboard = enabled;
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// Total amount paid by buyers in `token`.
+        #[ink(message)]
+        pub fn proceeds_of(&self, token: AccountId) -> u128 {
+            self.proceeds.get(token).unwrap_or_default()
+        }
+
+        /// Total amount paid by buyers for every payment token used so far.
+        #[ink(message)]
+        pub fn proceeds(&self) -> Vec<(AccountId, u128)> {
+            self.proceeds_tokens
+                .get()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|token| (token, self.proceeds_of(token)))
+                .collect()
+        }
+
+        /// Average price of a single asset token over the purchases made in the last
+        /// `window_blocks` blocks, weighted by the amount bought.
+        ///
+        /// Returns `None` if there were no purchases in the window.
+        #[ink(message)]
+        pub fn twap(&self, window_blocks: BlockNumber) -> Option<u128> {
+            let latest = self
+                .observations
+                .get(self.observation_count.checked_sub(1)?)?;
+            let (paid_before, amount_before) = self
+                .env()
+                .block_number()
+                .checked_sub(window_blocks)
+                .and_then(|since| self.observation_at(since))
+                .map(|o| (o.cumulative_paid, o.cumulative_amount))
+                .unwrap_or_default();
+
+            let amount = latest.cumulative_amount.saturating_sub(amount_before);
+            if amount == 0 {
+                return None;
+            }
+
+            Some(latest.cumulative_paid.saturating_sub(paid_before) / amount)
+        }
+
+        /// Record the current price and `sold()` as the checkpoint of the current block.
+        ///
+        /// Every purchase records a checkpoint as well, so keepers only need to call this while
+        /// nobody buys to keep the price history dense. Callable by anyone.
+        #[ink(message)]
+        pub fn checkpoint(&mut self) -> Result<Checkpoint, Error> {
+            self.check_not_finalized()?;
+            Ok(self.record_checkpoint())
+        }
+
+        /// Number of price checkpoints ever recorded, including overwritten ones.
+        #[ink(message)]
+        pub fn checkpoint_count(&self) -> u32 {
+            self.checkpoint_count
+        }
+
+        /// Retained checkpoints recorded from block `from` to block `to` inclusive, oldest first.
+        ///
+        /// Only the latest `CHECKPOINT_CAPACITY` checkpoints are retained. At most `limit`
+        /// checkpoints are returned, capped at 100 per call.
+        #[ink(message)]
+        pub fn checkpoints(
+            &self,
+            from: BlockNumber,
+            to: BlockNumber,
+            limit: u32,
+        ) -> Vec<Checkpoint> {
+            let (mut low, mut high) = (
+                self.checkpoint_count.saturating_sub(CHECKPOINT_CAPACITY),
+                self.checkpoint_count,
+            );
+            while low < high {
+                let mid = low + (high - low) / 2;
+                match self.checkpoint_slot(mid) {
+                    Some(checkpoint) if checkpoint.block < from => low = mid + 1,
+                    _ => high = mid,
+                }
+            }
+
+            (low..self.checkpoint_count)
+                .map_while(|n| self.checkpoint_slot(n))
+                .take_while(|checkpoint| checkpoint.block <= to)
+                .take(limit.min(MAX_PAGE_SIZE) as usize)
+                .collect()
+        }
+
+        /// Average price of a single asset token over all purchases, including refunded ones,
+        /// weighted by the amount bought.
+        ///
+        /// Returns `None` before the first purchase.
+        #[ink(message)]
+        pub fn average_realized_price(&self) -> Option<u128> {
+            let latest = self
+                .observations
+                .get(self.observation_count.checked_sub(1)?)?;
+            latest.cumulative_paid.checked_div(latest.cumulative_amount)
+        }
+
+        /// Approximate median price of a single asset token over all purchases, including
+        /// refunded ones, weighted by the amount bought.
+        ///
+        /// Unit prices are counted in buckets of at most 1/8 of their value, and the middle of the
+        /// bucket holding the median is returned, so the result is within 1/16 of the median of
+        /// the purchases' unit prices. Returns `None` before the first purchase.
+        #[ink(message)]
+        pub fn median_realized_price(&self) -> Option<u128> {
+            let histogram = self.price_histogram.get().unwrap_or_default();
+            let total = histogram
+                .iter()
+                .fold(0u128, |total, (_, amount)| total.saturating_add(*amount));
+            let mut below = 0u128;
+            histogram.into_iter().find_map(|(bucket, amount)| {
+                below = below.saturating_add(amount);
+                (below >= total.div_ceil(2)).then(|| Self::bucket_midpoint(bucket))
+            })
+        }
+
+        /// The bonus schedule applied to purchases.
+        #[ink(message)]
+        pub fn bonus_tiers(&self) -> Vec<BonusTier> {
+            self.bonus_tiers.get().unwrap_or_default()
+        }
+
+        /// Replace the bonus schedule.
+        ///
+        /// A purchase receives the largest bonus among the tiers it qualifies for, paid out in
+        /// additional asset tokens from the same inventory. Pass an empty list to disable bonuses.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_bonus_tiers(&mut self, tiers: Vec<BonusTier>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            if tiers.iter().any(|tier| u128::from(tier.bonus_bps) > BPS) {
+                return Err(InvalidParameter::InvalidBasisPoints.into());
+            }
+            self.bonus_tiers.set(&tiers);
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// The loyalty discount schedule applied to purchases.
+        #[ink(message)]
+        pub fn loyalty_tiers(&self) -> Vec<LoyaltyTier> {
+            self.loyalty_tiers.get().unwrap_or_default()
+        }
+
+        /// The current price of a single asset token for `account`, after the discount of the
+        /// active phase or its loyalty discount, whichever is larger.
+        #[ink(message)]
+        pub fn price_for(&self, account: AccountId) -> u128 {
+            let price = self.current_price();
+            let discount_bps = self.discount_bps_for(account, 0);
+
+            price - price.saturating_mul(discount_bps.into()) / BPS
+        }
+
+        /// The volume discount schedule applied to purchases.
+        #[ink(message)]
+        pub fn volume_tiers(&self) -> Vec<VolumeTier> {
+            self.volume_tiers.get().unwrap_or_default()
+        }
+
+        /// The current total price of `amount` asset tokens, after the volume discount.
+        #[ink(message)]
+        pub fn price_for_amount(&self, amount: u128) -> u128 {
+            self.apply_volume_discount(self.current_price().saturating_mul(amount), amount)
+        }
+
+        /// The Ethereum-style address of the ECDSA key authorized to sign vouchers, if any.
+        #[ink(message)]
+        pub fn voucher_signer(&self) -> Option<[u8; 20]> {
+            self.voucher_signer.get().flatten()
+        }
+
+        /// Update the Ethereum-style address of the ECDSA key authorized to sign vouchers.
+        ///
+        /// Pass `None` to stop accepting vouchers.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_voucher_signer(&mut self, signer: Option<[u8; 20]>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.voucher_signer.set(&signer);
+
+            Ok(())
+        }
+
+        /// The Blake2x256 hash of `voucher` the voucher signer has to sign.
+        ///
+        /// The hash commits to this contract's address, so vouchers can't be replayed against
+        /// other auctions.
+        #[ink(message)]
+        pub fn voucher_hash(&self, voucher: Voucher) -> [u8; 32] {
+            let mut hash = [0; 32];
+            ink::env::hash_encoded::<ink::env::hash::Blake2x256, _>(
+                &(self.env().account_id(), voucher),
+                &mut hash,
+            );
+            hash
+        }
+
+        /// Buy `amount` asset tokens at the current price, discounted by `voucher`.
+        ///
+        /// `signature` is the recoverable ECDSA signature of `voucher_hash(voucher)` by
+        /// `voucher_signer()`. The voucher must be issued to the caller and can only be
+        /// redeemed once.
+        #[ink(message)]
+        pub fn buy_with_voucher(
+            &mut self,
+            amount: u128,
+            voucher: Voucher,
+            signature: [u8; 65],
+        ) -> Result<(), Error> {
+            let discount_bps = voucher.discount_bps;
+            self.redeem_voucher(voucher, signature)?;
+            let caller = self.env().caller();
+            self.execute_purchase(
+                caller,
+                Payer::Account(caller),
+                &[(caller, amount)],
+                amount,
+                None,
+                discount_bps,
+                true,
+                &[],
+            )?;
+
+            Ok(())
+        }
+
+        /// Whether `account` is excluded from buying.
+        #[ink(message)]
+        pub fn is_blacklisted(&self, account: AccountId) -> bool {
+            self.blacklist.contains(account)
+        }
+
+        /// Add `account` to the blacklist, or remove it if `blacklisted` is `false`.
+        ///
+        /// Blacklisted accounts can't buy, pay for a purchase, reserve, order or bid, including
+        /// through operators or relayers. Purchases they made before keep being delivered and
+        /// refunded. Unlike the other settings the blacklist can be updated after
+        /// `lock_parameters`, to comply with sanctions issued during the sale.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_blacklisted(
+            &mut self,
+            account: AccountId,
+            blacklisted: bool,
+        ) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            if blacklisted {
+                self.blacklist.insert(account, &());
+            } else {
+                self.blacklist.remove(account);
+            }
+            let seq = self.next_event_seq();
+            self.env().emit_event(BlacklistUpdated {
+                account,
+                blacklisted,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        /// The Merkle root of the accounts allowed to buy, or `None` if the sale is public.
+        #[ink(message)]
+        pub fn allowlist_root(&self) -> Option<[u8; 32]> {
+            self.allowlist_root.get().flatten()
+        }
+
+        /// Restrict the sale to the accounts committed to by `root`, or rotate the root.
+        ///
+        /// While a root is set `buy` fails and buyers have to use `buy_with_proof` or a signed
+        /// voucher. Leaves are the
+        /// Blake2x256 hashes of the SCALE encoded account IDs and every parent is the Blake2x256
+        /// hash of its two children, smaller first. Pass `None` to open the sale to everyone.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_allowlist_root(&mut self, root: Option<[u8; 32]>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.allowlist_root.set(&root);
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// Buy `amount` asset tokens at the current price, proving with the Merkle `proof` that
+        /// the caller is on the allowlist.
+        ///
+        /// `proof` lists the sibling hashes from the caller's leaf up to the root. Fails if no
+        /// allowlist is configured.
+        #[ink(message)]
+        pub fn buy_with_proof(&mut self, amount: u128, proof: Vec<[u8; 32]>) -> Result<(), Error> {
+            let caller = self.env().caller();
+            self.check_allowlist_proof(caller, &proof)?;
+            self.execute_purchase(
+                caller,
+                Payer::Account(caller),
+                &[(caller, amount)],
+                amount,
+                None,
+                0,
+                true,
+                &[],
+            )?;
+
+            Ok(())
+        }
+
+        /// The asset tokens `operator` may still buy on behalf of `principal` with `buy_as`.
+        #[ink(message)]
+        pub fn operator_allowance(&self, principal: AccountId, operator: AccountId) -> u128 {
+            self.operator_allowances
+                .get((principal, operator))
+                .unwrap_or_default()
+        }
+
+        /// Authorize `operator`, e.g. a trading bot, to buy up to `max_amount` asset tokens on
+        /// the caller's behalf with `buy_as`. Replaces any previous approval, zero revokes it.
+        ///
+        /// The purchases are paid from the caller's payment tokens, so the caller also needs to
+        /// approve the auction for the payment.
+        #[ink(message)]
+        pub fn approve_operator(
+            &mut self,
+            operator: AccountId,
+            max_amount: u128,
+        ) -> Result<(), Error> {
+            let principal = self.env().caller();
+            if max_amount == 0 {
+                self.operator_allowances.remove((principal, operator));
+            } else {
+                self.operator_allowances
+                    .insert((principal, operator), &max_amount);
+            }
+            let seq = self.next_event_seq();
+            self.env().emit_event(OperatorApproved {
+                principal,
+                operator,
+                max_amount,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        /// Buy `amount` asset tokens at the current price on behalf of `principal`, who approved
+        /// the caller with `approve_operator`.
+        ///
+        /// The payment is pulled from `principal`, who receives the asset tokens and is recorded
+        /// as the buyer. Fails if `amount` exceeds the operator's remaining allowance or the
+        /// total price exceeds `max_price`. Returns the total paid.
+        #[ink(message)]
+        pub fn buy_as(
+            &mut self,
+            principal: AccountId,
+            amount: u128,
+            max_price: Option<Balance>,
+        ) -> Result<u128, Error> {
+            let operator = self.env().caller();
+            let remaining = self
+                .operator_allowance(principal, operator)
+                .checked_sub(amount)
+                .ok_or(Error::OperatorAllowanceExceeded)?;
+            self.operator_allowances
+                .insert((principal, operator), &remaining);
+
+            self.execute_purchase(
+                principal,
+                Payer::Account(principal),
+                &[(principal, amount)],
+                amount,
+                max_price,
+                0,
+                false,
+                &[],
+            )
+        }
+
+        /// Buy `amount` asset tokens at the current price, paid by the caller in a single payment
+        /// and delivered to `recipients`, each receiving its share of `amount`.
+        ///
+        /// The shares must be positive and add up to `amount`, for at most 16 recipients. Any
+        /// bonus is split in proportion to the shares. The caller is recorded as the buyer, so
+        /// other recipients are rejected while `cooling_off()` refunds are enabled.
+        /// Returns the total paid.
+        #[ink(message)]
+        pub fn buy_split(
+            &mut self,
+            amount: u128,
+            recipients: Vec<(AccountId, u128)>,
+        ) -> Result<u128, Error> {
+            let shared = recipients.iter().try_fold(0u128, |total, (_, share)| {
+                total.checked_add(*share).filter(|_| *share > 0)
+            });
+            if recipients.is_empty()
+                || recipients.len() > MAX_SPLIT_RECIPIENTS
+                || shared != Some(amount)
+            {
+                return Err(Error::InvalidSplit);
+            }
+
+            let caller = self.env().caller();
+            self.execute_purchase(
+                caller,
+                Payer::Account(caller),
+                &recipients,
+                amount,
+                None,
+                0,
+                false,
+                &[],
+            )
+        }
+
+        /// The contract allowed to submit purchases signed off-chain with `buy_relayed`, if any.
+        #[ink(message)]
+        pub fn trusted_forwarder(&self) -> Option<AccountId> {
+            self.trusted_forwarder.get().flatten()
+        }
+
+        /// Update the contract allowed to submit relayed purchases. Pass `None` to stop
+        /// accepting them.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_trusted_forwarder(&mut self, forwarder: Option<AccountId>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.trusted_forwarder.set(&forwarder);
+
+            Ok(())
+        }
+
+        /// The nonce the next relayed purchase of `account` has to carry.
+        #[ink(message)]
+        pub fn relay_nonce(&self, account: AccountId) -> u64 {
+            self.relay_nonces.get(account).unwrap_or_default()
+        }
+
+        /// The Blake2x256 hash of `request` the buyer has to sign.
+        ///
+        /// The hash commits to this contract's address, so requests can't be replayed against
+        /// other auctions.
+        #[ink(message)]
+        pub fn relayed_buy_hash(&self, request: RelayedBuy) -> [u8; 32] {
+            let mut hash = [0; 32];
+            ink::env::hash_encoded::<ink::env::hash::Blake2x256, _>(
+                &(self.env().account_id(), request),
+                &mut hash,
+            );
+            hash
+        }
+
+        /// Buy asset tokens for `request.buyer`, who signed the request off-chain, so buyers
+        /// without native tokens for fees can participate.
+        ///
+        /// `signature` is the recoverable ECDSA signature of `relayed_buy_hash(request)` by the
+        /// key of `request.buyer`, whose account ID is the Blake2x256 hash of the compressed
+        /// public key. The payment is pulled from the buyer, who must have approved the auction.
+        /// Returns the total paid.
+        ///
+        /// Requires the trusted forwarder to execute.
+        #[ink(message)]
+        pub fn buy_relayed(
+            &mut self,
+            request: RelayedBuy,
+            signature: [u8; 65],
+        ) -> Result<u128, Error> {
+            self.verify_relayed(&request, signature)?;
+            let RelayedBuy {
+                buyer,
+                amount,
+                max_price,
+                ..
+            } = request;
+
+            self.execute_purchase(
+                buyer,
+                Payer::Account(buyer),
+                &[(buyer, amount)],
+                amount,
+                max_price,
+                0,
+                false,
+                &[],
+            )
+        }
+
+        /// The contracts notified through `AuctionListener` after every purchase.
+        #[ink(message)]
+        pub fn listeners(&self) -> Vec<AccountId> {
+            self.listeners.get().unwrap_or_default()
+        }
+
+        /// Register `listener` to be notified after every purchase.
+        ///
+        /// At most 8 listeners can be registered. Each notification is limited in gas and a failing
+        /// listener doesn't affect the purchase.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn add_listener(&mut self, listener: AccountId) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            let mut listeners = self.listeners();
+            if listeners.contains(&listener) {
+                return Ok(());
+            }
+            if listeners.len() >= MAX_LISTENERS {
+                return Err(Error::TooManyListeners);
+            }
+            listeners.push(listener);
+            self.listeners.set(&listeners);
+
+            Ok(())
+        }
+
+        /// Stop notifying `listener` of purchases.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn remove_listener(&mut self, listener: AccountId) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            let mut listeners = self.listeners();
+            listeners.retain(|registered| *registered != listener);
+            self.listeners.set(&listeners);
+
+            Ok(())
+        }
+
+        /// The observer contracts notified through `AuctionObserver` of parameter changes and
+        /// state transitions.
+        #[ink(message)]
+        pub fn subscribers(&self) -> Vec<AccountId> {
+            self.subscribers.get().unwrap_or_default()
+        }
+
+        /// The native deposit required by `subscribe`.
+        #[ink(message)]
+        pub fn subscription_deposit(&self) -> Balance {
+            self.subscription_deposit.get().unwrap_or_default()
+        }
+
+        /// Update the native deposit required by `subscribe`. Existing subscribers keep the
+        /// deposit they paid.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_subscription_deposit(&mut self, deposit: Balance) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.subscription_deposit.set(&deposit);
+
+            Ok(())
+        }
+
+        /// Subscribe the calling contract to notifications of parameter changes and state
+        /// transitions through `AuctionObserver`.
+        ///
+        /// The caller has to transfer at least `subscription_deposit()` with the call, which is
+        /// refunded by `unsubscribe`. At most 16 observers can subscribe.
+        #[ink(message, payable)]
+        pub fn subscribe(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let deposit = self.env().transferred_value();
+            if self.subscription_deposits.contains(caller) {
+                return Err(Error::AlreadySubscribed);
+            }
+            if deposit < self.subscription_deposit() {
+                return Err(Error::InsufficientDeposit);
+            }
+            let mut subscribers = self.subscribers();
+            if subscribers.len() >= MAX_SUBSCRIBERS {
+                return Err(Error::TooManySubscribers);
+            }
+
+            subscribers.push(caller);
+            self.subscribers.set(&subscribers);
+            self.subscription_deposits.insert(caller, &deposit);
+
+            Ok(())
+        }
+
+        /// Cancel the caller's subscription and refund its deposit.
+        #[ink(message)]
+        pub fn unsubscribe(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let deposit = self
+                .subscription_deposits
+                .take(caller)
+                .ok_or(Error::NotSubscribed)?;
+            let mut subscribers = self.subscribers();
+            subscribers.retain(|subscriber| *subscriber != caller);
+            self.subscribers.set(&subscribers);
+
+            if deposit > 0 {
+                self.env()
+                    .transfer(caller, deposit)
+                    .map_err(|_| Error::NativeTransferFailed)?;
+            }
+
+            Ok(())
+        }
+
+        /// The DEX router used to swap into the payment token for `buy_with_any_token`, if any.
+        #[ink(message)]
+        pub fn swap_router(&self) -> Option<AccountId> {
+            self.swap_router.get().flatten()
+        }
+
+        /// Update the DEX router used for `buy_with_any_token`. Pass `None` to disable zap-in
+        /// purchases.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_swap_router(&mut self, router: Option<AccountId>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.swap_router.set(&router);
+
+            Ok(())
+        }
+
+        /// Swap `amount_in` of `token_in` into the payment token through `swap_router()` and buy
+        /// as many asset tokens as the output pays for at the current price, in one transaction.
+        ///
+        /// `route` is the swap path, starting with `token_in` and ending with the payment token.
+        /// The caller should make an approval for `amount_in` of `token_in`. The call fails if
+        /// fewer than `min_asset_out` asset tokens would be bought. Payment tokens left over
+        /// after the purchase are returned to the caller.
+        #[ink(message)]
+        pub fn buy_with_any_token(
+            &mut self,
+            token_in: AccountId,
+            amount_in: u128,
+            min_asset_out: u128,
+            route: Vec<AccountId>,
+        ) -> Result<(), Error> {
+            let router = self.swap_router().ok_or(Error::SwapRouterNotSet)?;
+            if route.first() != Some(&token_in)
+                || route.last() != Some(&self.config.payment_token)
+                || route.len() < 2
+            {
+                return Err(InvalidParameter::InvalidRoute.into());
+            }
+
+            let caller = self.env().caller();
+            let this = self.env().account_id();
+            let unit_price = self.current_price();
+            let token: contract_ref!(PSP22) = token_in.into();
+            Self::transfer_token_from(token.clone(), caller, this, amount_in, &[])?;
+            Self::approve_token(token.clone(), router, amount_in)?;
+
+            let mut dex: contract_ref!(DexRouter) = router.into();
+            let amounts = dex.swap_exact_tokens_for_tokens(
+                amount_in,
+                min_asset_out.saturating_mul(unit_price),
+                route,
+                this,
+                self.env().block_timestamp(),
+            )?;
+            // Don't leave an allowance behind if the router pulled less than approved.
+            Self::approve_token(token, router, 0)?;
+            let out = amounts.last().copied().unwrap_or_default();
+
+            let amount = out.checked_div(unit_price).unwrap_or_default();
+            if amount < min_asset_out {
+                return Err(Error::SlippageExceeded);
+            }
+            let paid = self.execute_purchase(
+                caller,
+                Payer::Account(this),
+                &[(caller, amount)],
+                amount,
+                Some(out),
+                0,
+                false,
+                &[],
+            )?;
+            if out > paid {
+                Self::transfer_token(self.payment(), caller, out - paid, &[])?;
+            }
+
+            Ok(())
+        }
+
+        /// Mark the payment token as the chain's wrapped native token, or unmark it.
+        ///
+        /// While set, buyers can send native value with `buy` instead of approving payment
+        /// tokens. The auction wraps it through the token's `deposit` message before settling.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_wrapped_native(&mut self, enabled: bool) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.config.wrapped_native = enabled;
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// Let `policy` approve every purchase through `SalePolicy::check`, or pass `None` to
+        /// stop consulting a policy.
+        ///
+        /// Compliance rules can evolve by replacing the policy contract without touching the
+        /// auction. A policy that fails to answer blocks purchases. Bid and order fills and
+        /// exercised reservations are checked when they are filled; a rejected bid stays open
+        /// and a rejected order is refunded in full.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_sale_policy(&mut self, policy: Option<AccountId>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.config.sale_policy = policy;
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// Switch between immediate purchases and order collection.
+        ///
+        /// In order collection mode buyers `place_order` until `end_time()`, locking the current
+        /// price for the asset tokens they request. Afterwards every order is settled with
+        /// `settle_order`: if the orders request more than `available_asset()`, each one is filled
+        /// pro-rata and the payment for the unfilled part is refunded. Bonuses and discounts
+        /// don't apply to orders, the access restrictions in effect when an order is placed do.
+        ///
+        /// Requires auction_owner to execute before any asset tokens are sold.
+        #[ink(message)]
+        pub fn set_order_collection(&mut self, enabled: bool) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            if self.total_sold > 0 || self.reserved > 0 || self.open_orders > 0 {
+                return Err(Error::SaleAlreadyStarted);
+            }
+            self.config.collect_orders = enabled;
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// The open order of `account`, if any.
+        #[ink(message)]
+        pub fn order_of(&self, account: AccountId) -> Option<Order> {
+            self.orders.get(account)
+        }
+
+        /// The asset tokens requested by the open orders.
+        #[ink(message)]
+        pub fn total_ordered(&self) -> u128 {
+            self.total_ordered
+        }
+
+        /// Order `amount` asset tokens at the current price, locking the payment in the contract.
+        ///
+        /// Placing another order adds to the caller's open order. The caller should make an
+        /// approval for at least `price_for(caller)*amount` payment tokens.
+        #[ink(message)]
+        pub fn place_order(&mut self, amount: u128) -> Result<(), Error> {
+            self.submit_order(amount, false)
+        }
+
+        /// Order `amount` asset tokens like `place_order`, proving with the Merkle `proof` that
+        /// the caller is on the allowlist, which admits orders in allowlist-only phases.
+        #[ink(message)]
+        pub fn place_order_with_proof(
+            &mut self,
+            amount: u128,
+            proof: Vec<[u8; 32]>,
+        ) -> Result<(), Error> {
+            self.check_allowlist_proof(self.env().caller(), &proof)?;
+            self.submit_order(amount, true)
+        }
+
+        fn submit_order(&mut self, amount: u128, allowlisted: bool) -> Result<(), Error> {
+            self.check_not_finalized()?;
+            self.check_not_paused()?;
+            let now = self.env().block_number();
+            if !self.config.collect_orders {
+                return Err(Error::NoOrder);
+            }
+            if now < self.config.start_time {
+                return Err(Error::AuctionNotStarted);
+            }
+            if now > self.config.end_time {
+                return Err(Error::AuctionEnded);
+            }
+            if amount == 0 {
+                return Err(Error::ZeroAmount);
+            }
+            self.check_lot_size(amount)?;
+
+            let caller = self.env().caller();
+            let price = self
+                .current_price()
+                .checked_mul(amount)
+                .ok_or(Error::ArithmeticOverflow)?;
+            let discount_bps = self.discount_bps_for(caller, 0);
+            let paid = price - price.saturating_mul(discount_bps.into()) / BPS;
+            let mut order = self.orders.get(caller).unwrap_or_else(|| {
+                self.open_orders += 1;
+                Order::default()
+            });
+            order.amount = order.amount.saturating_add(amount);
+            order.paid = order.paid.saturating_add(paid);
+            // Orders only count against the caps once filled, so check the whole open order.
+            self.check_purchase(caller, order.amount, allowlisted)?;
+            self.orders.insert(caller, &order);
+            self.total_ordered = self.total_ordered.saturating_add(amount);
+
+            let received = self.pull_payment(caller, paid, &[])?;
+            // Fee-on-transfer tokens only lock what the contract received.
+            let paid = if received < paid {
+                order.paid -= paid - received;
+                self.orders.insert(caller, &order);
+                received
+            } else {
+                paid
+            };
+            let seq = self.next_event_seq();
+            self.env().emit_event(OrderPlaced {
+                by: caller,
+                amount,
+                paid,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        /// Fill the open order of `account` after `end_time()`, pro-rata if the orders exceed the
+        /// supply, and refund the payment for the unfilled part.
+        ///
+        /// Callable by anyone.
+        #[ink(message)]
+        pub fn settle_order(&mut self, account: AccountId) -> Result<(), Error> {
+            if self.env().block_number() <= self.config.end_time {
+                return Err(Error::AuctionNotEnded);
+            }
+            let order = self.orders.take(account).ok_or(Error::NoOrder)?;
+            let supply = match self.order_supply {
+                Some(supply) => supply,
+                None => {
+                    let supply = self.available_asset();
+                    self.order_supply = Some(supply);
+                    supply
+                }
+            };
+
+            let (mut filled, mut cost) = Self::pro_rata(&order, supply, self.total_ordered);
+            self.open_orders -= 1;
+
+            self.lock()?;
+            // Orders the sale policy rejects are refunded in full.
+            if filled > 0 && self.admit_purchase(account, filled, 0, cost).is_err() {
+                (filled, cost) = (0, 0);
+            }
+            let refund = order.paid - cost;
+            if filled > 0 {
+                self.record_purchase(account, filled, 0, cost, false, &[]);
+                self.take_payment(self.env().account_id(), cost, &[])?;
+                self.deliver_asset(account, filled, &[])?;
+            }
+            if refund > 0 {
+                Self::transfer_token(self.payment(), account, refund, &[])?;
+            }
+            self.unlock();
+
+            let seq = self.next_event_seq();
+            self.env().emit_event(OrderSettled {
+                by: account,
+                filled,
+                cost,
+                refund,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        /// The standing bid with the given `id`, if it is still open.
+        #[ink(message)]
+        pub fn bid(&self, id: u32) -> Option<Bid> {
+            self.bids.get(id)
+        }
+
+        /// The IDs of the open standing bids, oldest first.
+        #[ink(message)]
+        pub fn open_bids(&self) -> Vec<u32> {
+            self.open_bids.get().unwrap_or_default()
+        }
+
+        /// The share of a filled bid's price, in basis points, paid to the account calling
+        /// `match_bids`.
+        #[ink(message)]
+        pub fn keeper_fee_bps(&self) -> u16 {
+            self.keeper_fee_bps.get().unwrap_or_default()
+        }
+
+        /// Place a standing bid for `amount` asset tokens, filled by `match_bids` once the price
+        /// is at or below `limit_price`.
+        ///
+        /// `amount * limit_price` payment tokens are escrowed until the bid is filled or cancelled,
+        /// and the difference to the price actually paid is refunded on fill. The caller should
+        /// make an approval for the escrow. Bids get no discounts and are only filled while the
+        /// bidder may buy, e.g. not during an allowlist-only phase.
+        /// Returns the bid ID.
+        #[ink(message)]
+        pub fn place_bid(&mut self, amount: u128, limit_price: u128) -> Result<u32, Error> {
+            self.check_not_finalized()?;
+            self.check_not_paused()?;
+            self.check_not_blacklisted(self.env().caller())?;
+            if self.config.collect_orders || self.config.raise_goal.is_some() {
+                return Err(Error::OrderCollectionActive);
+            }
+            if amount == 0 {
+                return Err(Error::ZeroAmount);
+            }
+            self.check_lot_size(amount)?;
+            let mut open_bids = self.open_bids();
+            if open_bids.len() >= MAX_OPEN_BIDS {
+                return Err(Error::TooManyBids);
+            }
+
+            let caller = self.env().caller();
+            let escrow = amount
+                .checked_mul(limit_price)
+                .ok_or(Error::ArithmeticOverflow)?;
+            let id = self.next_bid_id.get().unwrap_or_default();
+            self.next_bid_id.set(&(id + 1));
+            let mut bid = Bid {
+                bidder: caller,
+                amount,
+                limit_price,
+                escrow,
+            };
+            self.bids.insert(id, &bid);
+            open_bids.push(id);
+            self.open_bids.set(&open_bids);
+
+            let received = self.pull_payment(caller, escrow, &[])?;
+            // Fee-on-transfer tokens only escrow what the contract received.
+            if received < escrow {
+                bid.escrow = received;
+                self.bids.insert(id, &bid);
+            }
+            let seq = self.next_event_seq();
+            self.env().emit_event(BidPlaced {
+                by: caller,
+                id,
+                amount,
+                limit_price,
+                seq,
+            });
+
+            Ok(id)
+        }
+
+        /// Cancel the caller's open bid `id` and refund its escrow. Bids can be cancelled after
+        /// finalization too.
+        #[ink(message)]
+        pub fn cancel_bid(&mut self, id: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let bid = self
+                .bids
+                .get(id)
+                .filter(|bid| bid.bidder == caller)
+                .ok_or(Error::NoBid)?;
+            self.close_bid(id);
+
+            Self::transfer_token(self.payment(), caller, bid.escrow, &[])?;
+            let seq = self.next_event_seq();
+            self.env().emit_event(BidCancelled {
+                by: caller,
+                id,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        /// Fill every open bid whose limit price is at or above the current price, while supply
+        /// lasts, paying the caller `keeper_fee_bps()` of each filled price.
+        ///
+        /// Callable by anyone. Returns the number of bids filled.
+        #[ink(message)]
+        pub fn match_bids(&mut self) -> Result<u32, Error> {
+            if self.config.collect_orders || self.config.raise_goal.is_some() {
+                return Err(Error::OrderCollectionActive);
+            }
+            let keeper = self.env().caller();
+            let price = self.current_price();
+            let fee_bps = self.keeper_fee_bps();
+            let mut filled = 0;
+
+            self.lock()?;
+            for id in self.open_bids() {
+                let Some(bid) = self.bids.get(id) else {
+                    continue;
+                };
+                if bid.limit_price < price {
+                    continue;
+                }
+                let Ok(Quote { total, bonus }) = self.quote(bid.amount) else {
+                    continue;
+                };
+                // The escrow of a fee-on-transfer token may fall short of the limit price.
+                if total > bid.escrow {
+                    continue;
+                }
+                // Bids of accounts that may not buy at the moment stay open.
+                if self.check_purchase(bid.bidder, bid.amount, false).is_err()
+                    || self
+                        .admit_purchase(bid.bidder, bid.amount, bonus, total)
+                        .is_err()
+                {
+                    continue;
+                }
+                let keeper_fee = total.saturating_mul(fee_bps.into()) / BPS;
+                self.close_bid(id);
+                self.record_purchase(bid.bidder, bid.amount, bonus, total, false, &[]);
+
+                self.collect_payment(self.env().account_id(), total - keeper_fee, &[])?;
+                if keeper_fee > 0 {
+                    Self::transfer_token(self.payment(), keeper, keeper_fee, &[])?;
+                }
+                if bid.escrow > total {
+                    Self::transfer_token(self.payment(), bid.bidder, bid.escrow - total, &[])?;
+                }
+                self.deliver_asset(bid.bidder, bid.amount.saturating_add(bonus), &[])?;
+                let seq = self.next_event_seq();
+                self.env().emit_event(BidFilled {
+                    by: bid.bidder,
+                    id,
+                    price: total,
+                    keeper,
+                    keeper_fee,
+                    seq,
+                });
+                filled += 1;
+            }
+            self.reconcile_escrow();
+            self.unlock();
+
+            Ok(filled)
+        }
+
+        /// The number of blocks a purchase remains refundable and the share of the price, in
+        /// basis points, kept on refund.
+        #[ink(message)]
+        pub fn cooling_off(&self) -> (BlockNumber, u16) {
+            let terms = self.cooling_off.get().unwrap_or_default();
+            (terms.window, terms.penalty_bps)
+        }
+
+        /// Update the cooling-off terms. A `window` of zero disables refunds.
+        ///
+        /// While refunds are enabled, payments are held by the contract and forwarded to the owner
+        /// at finalization, and purchases can only be delivered to the buyer, who returns them.
+        /// Not available for all-or-nothing sales.
+        ///
+        /// Requires auction_owner to execute before any asset tokens are sold.
+        #[ink(message)]
+        pub fn set_cooling_off(
+            &mut self,
+            window: BlockNumber,
+            penalty_bps: u16,
+        ) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            if self.total_sold > 0 || self.reserved > 0 {
+                return Err(Error::SaleAlreadyStarted);
+            }
+            if u128::from(penalty_bps) > BPS {
+                return Err(InvalidParameter::InvalidBasisPoints.into());
+            }
+            self.cooling_off.set(&CoolingOff {
+                window,
+                penalty_bps,
+            });
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// Return the caller's purchase with the contract-wide ID `purchase_id`, as emitted in
+        /// `AssetBought`, during its cooling-off window, before finalization.
+        ///
+        /// The caller should make an approval for the asset tokens received, including any bonus.
+        /// Purchases of a deferred-delivery sale are returned from the caller's allocation
+        /// instead. The price minus the `cooling_off()` penalty is refunded.
+        #[ink(message)]
+        pub fn refund(&mut self, purchase_id: u64) -> Result<(), Error> {
+            self.check_not_finalized()?;
+            let caller = self.env().caller();
+            if !self.refunds_enabled() {
+                return Err(Error::RefundWindowClosed);
+            }
+            let terms = self.cooling_off.get().unwrap_or_default();
+            let index = self
+                .purchase_ids
+                .get(purchase_id)
+                .filter(|(buyer, _)| *buyer == caller)
+                .ok_or(Error::NoPurchase)?
+                .1;
+            let mut purchase = self
+                .purchases
+                .get((caller, index))
+                .ok_or(Error::NoPurchase)?;
+            if purchase.refunded {
+                return Err(Error::AlreadyRefunded);
+            }
+            if self.env().block_number() > purchase.block.saturating_add(terms.window) {
+                return Err(Error::RefundWindowClosed);
+            }
+
+            let returned = purchase.amount.saturating_add(purchase.bonus);
+            let allocation = if purchase.allocated {
+                let remaining = self
+                    .allocation_of(caller)
+                    .checked_sub(returned)
+                    .ok_or(Error::InsufficientAllocation)?;
+                Some(remaining)
+            } else {
+                None
+            };
+            let refund = purchase
+                .price
+                .saturating_sub(purchase.price.saturating_mul(terms.penalty_bps.into()) / BPS);
+            purchase.refunded = true;
+            self.purchases.insert((caller, index), &purchase);
+            self.total_sold = self.total_sold.saturating_sub(returned);
+            let mut totals = self.buyer_totals_of(caller);
+            totals.bought = totals.bought.saturating_sub(returned);
+            totals.paid = totals.paid.saturating_sub(refund);
+            self.buyer_totals.insert(caller, &totals);
+            if self.config.track_leaderboard {
+                self.update_leaderboard(caller, totals.bought);
+            }
+            self.held_payments = self.held_payments.saturating_sub(refund);
+
+            self.lock()?;
+            match allocation {
+                // The asset tokens never left the escrow.
+                Some(remaining) => {
+                    if remaining == 0 {
+                        self.allocations.remove(caller);
+                    } else {
+                        self.allocations.insert(caller, &remaining);
+                    }
+                    self.allocated = self.allocated.saturating_sub(returned);
+                }
+                None => {
+                    let this = self.env().account_id();
+                    Self::transfer_token_from(self.asset(), caller, this, returned, &[])?;
+                    self.tracked_escrow = self.tracked_escrow.saturating_add(returned);
+                }
+            }
+            if refund > 0 {
+                Self::transfer_token(self.payment(), caller, refund, &[])?;
+            }
+            self.unlock();
+
+            let seq = self.next_event_seq();
+            self.env().emit_event(Refunded {
+                by: caller,
+                purchase_id,
+                amount: returned,
+                refund,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        /// The minimum total raise of an all-or-nothing sale, if the auction is one.
+        #[ink(message)]
+        pub fn raise_goal(&self) -> Option<u128> {
+            self.config.raise_goal
+        }
+
+        /// Turn the auction into an all-or-nothing sale with the given `goal`, or back into a
+        /// regular sale with `None`.
+        ///
+        /// In an all-or-nothing sale payments are escrowed by the contract and asset tokens are
+        /// allocated instead of transferred. Once `end_time()` has passed or the asset is sold
+        /// out, buyers `claim_assets` and the owner can `withdraw_raise` if at least `goal` was
+        /// raised, otherwise buyers `claim_refund` and `finalize` returns the whole inventory.
+        /// Reservations are disabled in this mode.
+        ///
+        /// Requires auction_owner to execute before any asset tokens are sold.
+        #[ink(message)]
+        pub fn set_raise_goal(&mut self, goal: Option<u128>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            if self.total_sold > 0 || self.reserved > 0 {
+                return Err(Error::SaleAlreadyStarted);
+            }
+            self.config.raise_goal = goal;
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// The payments escrowed by an all-or-nothing sale.
+        #[ink(message)]
+        pub fn total_raised(&self) -> u128 {
+            self.total_raised
+        }
+
+        /// The payments escrowed for `account` by an all-or-nothing sale.
+        #[ink(message)]
+        pub fn contribution_of(&self, account: AccountId) -> u128 {
+            self.contributions.get(account).unwrap_or_default()
+        }
+
+        /// The asset tokens owed to `account` by an all-or-nothing or deferred-delivery sale.
+        #[ink(message)]
+        pub fn allocation_of(&self, account: AccountId) -> u128 {
+            self.allocations.get(account).unwrap_or_default()
+        }
+
+        /// The payment tokens owed to `account` for transferring more than the price of a
+        /// purchase made through `before_received`.
+        #[ink(message)]
+        pub fn payment_credit_of(&self, account: AccountId) -> u128 {
+            self.payment_credits.get(account).unwrap_or_default()
+        }
+
+        /// Transfer the payment tokens owed to the caller for overpaying purchases made through
+        /// `before_received`. Returns the amount withdrawn.
+        #[ink(message)]
+        pub fn withdraw_payment_credit(&mut self) -> Result<u128, Error> {
+            let caller = self.env().caller();
+            let amount = self
+                .payment_credits
+                .take(caller)
+                .ok_or(Error::NothingToClaim)?;
+            self.credited = self.credited.saturating_sub(amount);
+            Self::transfer_token(self.payment(), caller, amount, &[])?;
+
+            Ok(amount)
+        }
+
+        /// Transfer the asset tokens allocated to the caller once the all-or-nothing sale has
+        /// ended with its raise goal reached, and the owner opened claims of a deferred-delivery
+        /// sale.
+        #[ink(message)]
+        pub fn claim_assets(&mut self) -> Result<(), Error> {
+            if self.config.raise_goal.is_some() && !self.goal_reached()? {
+                return Err(Error::GoalNotReached);
+            }
+            if self.config.deferred_delivery && !self.claims_open() {
+                return Err(Error::ClaimsNotOpen);
+            }
+            let caller = self.env().caller();
+            if self.flip_restricted()
+                && self.allocation_blocks.get(caller) == Some(self.env().block_number())
+            {
+                return Err(Error::SameBlockClaim);
+            }
+            let amount = self.allocations.take(caller).ok_or(Error::NothingToClaim)?;
+            self.allocation_blocks.remove(caller);
+            self.allocated = self.allocated.saturating_sub(amount);
+
+            self.deliver_asset(caller, amount, &[])
+        }
+
+        /// Switch between delivering purchased asset tokens immediately and only allocating them.
+        ///
+        /// With deferred delivery, purchases are paid as usual but the asset tokens are recorded
+        /// in `allocation_of` and transferred by `claim_assets` once `open_claims` is called, which
+        /// decouples the sale window from the token distribution.
+        ///
+        /// Requires auction_owner to execute before any asset tokens are sold.
+        #[ink(message)]
+        pub fn set_deferred_delivery(&mut self, enabled: bool) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            if self.total_sold > 0 || self.reserved > 0 || self.open_orders > 0 {
+                return Err(Error::SaleAlreadyStarted);
+            }
+            self.config.deferred_delivery = enabled;
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// Whether buyers of a deferred-delivery sale can claim their allocations.
+        #[ink(message)]
+        pub fn claims_open(&self) -> bool {
+            self.claims_open.get().unwrap_or_default()
+        }
+
+        /// Let buyers of a deferred-delivery sale claim their allocations. Claims can't be
+        /// closed again.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn open_claims(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            self.check_owner(caller)?;
+            if !self.claims_open() {
+                self.claims_open.set(&true);
+                let seq = self.next_event_seq();
+                self.env().emit_event(ClaimsOpened { by: caller, seq });
+            }
+
+            Ok(())
+        }
+
+        /// Whether buyers of a deferred-delivery sale can transfer their allocations.
+        #[ink(message)]
+        pub fn allocation_transfers_enabled(&self) -> bool {
+            !self.allocation_transfers_disabled.get().unwrap_or_default()
+        }
+
+        /// Enable or disable transfers of deferred-delivery allocations, which are enabled by
+        /// default.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_allocation_transfers(&mut self, enabled: bool) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.allocation_transfers_disabled.set(&!enabled);
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// Whether allocations can't be claimed in the block they were bought in.
+        #[ink(message)]
+        pub fn flip_restricted(&self) -> bool {
+            self.flip_restricted.get().unwrap_or_default()
+        }
+
+        /// Enable or disable the same-block flip restriction, which rejects `claim_assets` in the
+        /// block the caller's allocation last grew, including through `transfer_allocation`.
+        ///
+        /// This blunts atomic buy-and-dump arbitrage against the curve. It only applies to
+        /// allocated purchases, i.e. deferred-delivery and all-or-nothing sales, as asset tokens
+        /// delivered immediately are out of the auction's control.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_flip_restriction(&mut self, enabled: bool) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.flip_restricted.set(&enabled);
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// Transfer `amount` of the caller's deferred-delivery allocation to `to`, who can claim
+        /// it in their place.
+        ///
+        /// Allocations of an all-or-nothing sale are backed by refundable contributions and
+        /// can't be transferred.
+        #[ink(message)]
+        pub fn transfer_allocation(&mut self, to: AccountId, amount: u128) -> Result<(), Error> {
+            if !self.config.deferred_delivery
+                || self.config.raise_goal.is_some()
+                || !self.allocation_transfers_enabled()
+            {
+                return Err(Error::AllocationsNotTransferable);
+            }
+            if amount == 0 {
+                return Err(Error::ZeroAmount);
+            }
+            let from = self.env().caller();
+            let remaining = self
+                .allocation_of(from)
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientAllocation)?;
+            if remaining == 0 {
+                self.allocations.remove(from);
+            } else {
+                self.allocations.insert(from, &remaining);
+            }
+            self.allocations
+                .insert(to, &self.allocation_of(to).saturating_add(amount));
+            if let Some(block) = self.allocation_blocks.get(from) {
+                if self
+                    .allocation_blocks
+                    .get(to)
+                    .is_none_or(|to_block| to_block < block)
+                {
+                    self.allocation_blocks.insert(to, &block);
+                }
+            }
+            let seq = self.next_event_seq();
+            self.env().emit_event(AllocationTransferred {
+                from,
+                to,
+                amount,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        /// Refund the payments escrowed for the caller once the all-or-nothing sale has ended
+        /// without reaching its raise goal.
+        #[ink(message)]
+        pub fn claim_refund(&mut self) -> Result<(), Error> {
+            if self.goal_reached()? {
+                return Err(Error::GoalReached);
+            }
+            let caller = self.env().caller();
+            let amount = self
+                .contributions
+                .take(caller)
+                .ok_or(Error::NothingToClaim)?;
+            if let Some(allocation) = self.allocations.take(caller) {
+                self.allocated = self.allocated.saturating_sub(allocation);
+            }
+            self.total_raised = self.total_raised.saturating_sub(amount);
+
+            Self::transfer_token(self.payment(), caller, amount, &[])?;
+
+            Ok(())
+        }
+
+        /// Transfer the raise to the owner once the all-or-nothing sale has ended with its goal
+        /// reached, holding back the liquidity share if `liquidity_config()` is set.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn withdraw_raise(&mut self) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            if !self.goal_reached()? {
+                return Err(Error::GoalNotReached);
+            }
+            if self.raise_withdrawn {
+                return Err(Error::NothingToClaim);
+            }
+            self.raise_withdrawn = true;
+
+            self.take_payment(self.env().account_id(), self.total_raised, &[])
+        }
+
+        /// The sale phases, ordered by start block.
+        #[ink(message)]
+        pub fn phases(&self) -> Vec<Phase> {
+            self.phases.get().unwrap_or_default()
+        }
+
+        /// The index and terms of the phase in effect at the current block, if any.
+        #[ink(message)]
+        pub fn active_phase(&self) -> Option<(u32, Phase)> {
+            self.phase_at(self.env().block_number())
+        }
+
+        /// The rounds of a multi-round sale, ordered by start block.
+        #[ink(message)]
+        pub fn rounds(&self) -> Vec<Round> {
+            self.rounds.get().unwrap_or_default()
+        }
+
+        /// Replace the rounds of a multi-round sale.
+        ///
+        /// Rounds must be ordered by strictly increasing `start_block` before `end_time`. Each
+        /// round replaces the price curve of the auction with its own, from its start until the
+        /// next round starts or the auction ends, and sells at most its `supply`. When rounds are
+        /// configured, nothing can be bought before the first one starts.
+        ///
+        /// Requires auction_owner to execute. Fails once the sale started.
+        #[ink(message)]
+        pub fn set_rounds(&mut self, rounds: Vec<Round>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            if self.total_sold > 0 || self.reserved > 0 || self.open_orders > 0 {
+                return Err(Error::SaleAlreadyStarted);
+            }
+            if rounds
+                .iter()
+                .any(|round| round.min_price > round.start_price)
+            {
+                return Err(InvalidParameter::MinPriceAboveStartPrice.into());
+            }
+            if rounds
+                .windows(2)
+                .any(|pair| pair[0].start_block >= pair[1].start_block)
+                || rounds
+                    .last()
+                    .is_some_and(|round| round.start_block >= self.config.end_time)
+            {
+                return Err(InvalidParameter::UnorderedRounds.into());
+            }
+            self.rounds.set(&rounds);
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// The index and terms of the round in effect at the current block, if any.
+        #[ink(message)]
+        pub fn current_round(&self) -> Option<(u32, Round)> {
+            self.round_at(self.env().block_number())
+        }
+
+        /// The asset tokens sold and payment raised in round `index`.
+        #[ink(message)]
+        pub fn round_stats(&self, index: u32) -> RoundStats {
+            self.round_stats.get(index).unwrap_or_default()
+        }
+
+        /// The price per asset token paid by the most recent purchase, after discounts, or 0
+        /// if nothing was sold.
+        #[ink(message)]
+        pub fn last_sale_price(&self) -> u128 {
+            self.last_sale_price
+        }
+
+        /// The terms of a perpetual auction, if the auction is one.
+        #[ink(message)]
+        pub fn perpetual(&self) -> Option<Perpetual> {
+            self.config.perpetual.clone()
+        }
+
+        /// Turn the auction into a perpetual one with the given terms, or back into a regular
+        /// one with `None`.
+        ///
+        /// A perpetual auction ignores `end_time`, rounds and the configured price curve, and
+        /// keeps selling tranche by tranche until its inventory runs out. The first tranche
+        /// starts at `start_price` when the auction starts.
+        ///
+        /// Requires auction_owner to execute before any asset tokens are sold.
+        #[ink(message)]
+        pub fn set_perpetual(&mut self, perpetual: Option<Perpetual>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            if self.total_sold > 0 || self.reserved > 0 || self.open_orders > 0 {
+                return Err(Error::SaleAlreadyStarted);
+            }
+            if perpetual
+                .as_ref()
+                .is_some_and(|perpetual| perpetual.tranche_size == 0 || perpetual.duration == 0)
+            {
+                return Err(InvalidParameter::InvalidTranche.into());
+            }
+            self.config.perpetual = perpetual;
+            self.tranche = Tranche {
+                index: 0,
+                start_block: self.config.start_time.max(self.env().block_number()),
+                start_price: self.config.start_price,
+                sold: 0,
+            };
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// The tranche of a perpetual auction in effect at the current block.
+        #[ink(message)]
+        pub fn current_tranche(&self) -> Option<Tranche> {
+            let perpetual = self.config.perpetual.as_ref()?;
+            Some(self.tranche_at(perpetual, self.env().block_number()))
+        }
+
+        /// Check whether `account` could buy `amount` asset tokens at the current block.
+        ///
+        /// Performs the same checks as `buy`, including the payment token allowance and balance,
+        /// and returns the error `buy` would fail with, so UIs can warn users before submitting
+        /// a transaction.
+        #[ink(message)]
+        pub fn can_buy(&self, account: AccountId, amount: u128) -> Result<(), Error> {
+            let quote = self.quote(amount)?;
+            self.check_purchase(account, amount, false)?;
+            self.check_payment(account, quote.total)
+        }
+
+        /// Simulate `account` buying `amount` asset tokens at the current block.
+        ///
+        /// Returns the price, discount, bonus and how the payment would be split, as far as they
+        /// could be determined, along with the error `buy` would fail with, if any.
+        #[ink(message)]
+        pub fn simulate_buy(&self, account: AccountId, amount: u128) -> BuySimulation {
+            let mut simulation = BuySimulation::default();
+            if let Err(error) = self.simulate_purchase(account, amount, &mut simulation) {
+                simulation.error = Some(error);
+            }
+
+            simulation
+        }
+
+        /// Compare the asset tokens held by the contract with the balance expected from its own
+        /// deposits and transfers, and adopt the measured balance.
+        ///
+        /// Emits `EscrowReconciled` if they differ, e.g. because the asset token rebased or
+        /// burned part of the balance. Purchases reconcile automatically; this message lets
+        /// anyone do it in between. Returns the expected and the measured balance.
+        #[ink(message)]
+        pub fn reconcile(&mut self) -> (u128, u128) {
+            let expected = self.tracked_escrow;
+            (expected, self.reconcile_escrow())
+        }
+
+        /// Check the internal invariants of the auction.
+        ///
+        /// Monitoring bots can call this to detect accounting drift, e.g. after an upgrade.
+        #[ink(message)]
+        pub fn audit(&self) -> AuditReport {
+            let escrowed = self.escrow_balance();
+            let available = self.inventory();
+            let required = if self.config.mint_on_demand {
+                0
+            } else {
+                available
+                    .saturating_add(self.reserved)
+                    .saturating_add(self.allocated)
+                    .saturating_add(self.liquidity_assets())
+            };
+
+            AuditReport {
+                escrowed,
+                available,
+                escrow_covers_available: escrowed >= required,
+                price_range_valid: self.config.min_price <= self.config.start_price,
+                time_range_valid: self.config.start_time <= self.config.end_time,
+                supply_consistent: self.sale_cap == 0
+                    || self
+                        .total_sold
+                        .saturating_add(available)
+                        .saturating_add(self.reserved)
+                        .saturating_add(self.liquidity_assets())
+                        == self.sale_cap,
+            }
+        }
+
+        /// Whether unsold asset tokens are burned instead of returned to the owner at finalization.
+        #[ink(message)]
+        pub fn burn_unsold(&self) -> bool {
+            self.config.burn_unsold
+        }
+
+        /// Update whether unsold asset tokens are burned at finalization.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_burn_unsold(&mut self, value: bool) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.check_not_finalized()?;
+            self.config.burn_unsold = value;
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// The DEX liquidity seeding configuration, if any.
+        #[ink(message)]
+        pub fn liquidity_config(&self) -> Option<LiquidityConfig> {
+            self.liquidity_config.get().flatten()
+        }
+
+        /// The recipient and share, in basis points, of every payment donated, if any.
+        #[ink(message)]
+        pub fn donation(&self) -> Option<Donation> {
+            self.donation.get().flatten()
+        }
+
+        /// The lifecycle stage of the auction at the current block.
+        ///
+        /// Stages driven by time or supply, e.g. `Active` to `Ended`, take effect without a
+        /// transaction; `StateChanged` is emitted by the next call that records them.
+        #[ink(message)]
+        pub fn state(&self) -> AuctionState {
+            let now = self.env().block_number();
+            if self.cancelled {
+                AuctionState::Cancelled
+            } else if self.finalized {
+                AuctionState::Finalized
+            } else if self.paused {
+                AuctionState::Paused
+            } else if now < self.config.start_time {
+                AuctionState::Pending
+            } else if now > self.closes_at() || self.cap_reached() {
+                AuctionState::Ended
+            } else if self.inventory() == 0 && self.total_sold == 0 {
+                AuctionState::Pending
+            } else if self.inventory() == 0 {
+                AuctionState::SoldOut
+            } else {
+                AuctionState::Active
+            }
+        }
+
+        /// Suspend purchases, orders and bids until `unpause`. Refunds, claims and
+        /// cancellations remain possible.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_not_finalized()?;
+            self.paused = true;
+            self.update_state();
+
+            Ok(())
+        }
+
+        /// Resume purchases, orders and bids.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_not_finalized()?;
+            self.paused = false;
+            self.update_state();
+
+            Ok(())
+        }
+
+        /// Cancel the auction before any asset tokens are sold, reserved, ordered or bid for,
+        /// returning the deposited asset tokens. No purchases are possible afterwards.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn cancel(&mut self) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_not_finalized()?;
+            if self.total_sold > 0
+                || self.reserved > 0
+                || self.open_orders > 0
+                || !self.open_bids().is_empty()
+            {
+                return Err(Error::SaleAlreadyStarted);
+            }
+            self.cancelled = true;
+            self.finalized = true;
+            self.update_state();
+            self.notify(AuctionNotification::Finalized);
+
+            let deposited = self.escrow_balance();
+            if deposited > 0 {
+                self.give_asset(self.proceeds_recipient(), deposited, &[])?;
+            }
+
+            Ok(())
+        }
+
+        /// Wind down the auction once `end_time()` has passed or the asset is sold out.
+        ///
+        /// If `liquidity_config()` is set, the held back proceeds and asset tokens are deposited
+        /// into the DEX pair first. The whole call fails if the router doesn't accept the exact
+        /// amounts, e.g. because the pair already exists at a different ratio.
+        ///
+        /// The asset tokens remaining in the contract are burned through the `PSP22Burnable`
+        /// extension when `burn_unsold()` is set, otherwise they are returned to the owner.
+        /// No purchases are possible afterwards.
+        ///
+        /// Callable by anyone, so finalization doesn't depend on the owner. The caller receives
+        /// the part of `finalize_bounty()` held back from proceeds.
+        #[ink(message)]
+        pub fn finalize(&mut self) -> Result<(), Error> {
+            self.check_not_finalized()?;
+            self.check_ended()?;
+            if self.open_orders > 0 {
+                return Err(Error::FundsInEscrow);
+            }
+
+            self.finalized = true;
+            self.update_state();
+            self.release_held_payments()?;
+            self.seed_liquidity()?;
+            self.notify(AuctionNotification::Finalized);
+
+            let unsold = self.unsold();
+            if unsold > 0 && self.config.burn_unsold {
+                let mut asset: contract_ref!(PSP22Burnable) = self.config.asset_token.into();
+                Self::token_call(asset.call_mut().burn(unsold).try_invoke())??;
+                self.tracked_escrow = self.tracked_escrow.saturating_sub(unsold);
+                let seq = self.next_event_seq();
+                self.env().emit_event(UnsoldBurned {
+                    amount: unsold,
+                    seq,
+                });
+            } else if unsold > 0 {
+                self.give_asset(self.proceeds_recipient(), unsold, &[])?;
+            }
+
+            let caller = self.env().caller();
+            let bounty = core::mem::take(&mut self.bounty_reserve);
+            if bounty > 0 {
+                Self::transfer_token(self.payment(), caller, bounty, &[])?;
+            }
+            let seq = self.next_event_seq();
+            self.env().emit_event(AuctionFinalized {
+                by: caller,
+                unsold,
+                bounty,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        /// The payment tokens paid to the account calling `finalize`, held back from the first
+        /// proceeds.
+        #[ink(message)]
+        pub fn finalize_bounty(&self) -> u128 {
+            self.finalize_bounty.get().unwrap_or_default()
+        }
+
+        /// The account receiving proceeds, forfeited deposits, liquidity tokens and returned asset
+        /// tokens instead of the owner, if any.
+        #[ink(message)]
+        pub fn treasury(&self) -> Option<AccountId> {
+            self.treasury
+        }
+
+        /// Update the account receiving proceeds instead of the owner. Pass `None` to pay the
+        /// owner directly.
+        ///
+        /// Set a treasury before `renounce_ownership` to keep proceeds flowing somewhere other
+        /// than the renouncing owner.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn set_treasury(&mut self, treasury: Option<AccountId>) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_parameters_unlocked()?;
+            self.treasury = treasury;
+            self.notify(AuctionNotification::ParametersChanged);
+
+            Ok(())
+        }
+
+        /// List the auction in `registry` under its asset and payment token pair, so it can be
+        /// discovered without crawling the chain.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn register_in(&mut self, registry: AccountId) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_not_finalized()?;
+
+            let mut registry: contract_ref!(AuctionRegistry) = registry.into();
+            // The registry reads the token pair back from the auction.
+            registry
+                .call_mut()
+                .register()
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .invoke()?;
+
+            Ok(())
+        }
+
+        /// Move the unsold asset tokens to `new_auction` once `end_time()` has passed or the asset is
+        /// sold out.
+        ///
+        /// `new_auction` must be a Dutch auction selling the same asset token. The auction is
+        /// finalized afterwards.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn rollover(&mut self, new_auction: AccountId) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_not_finalized()?;
+            self.check_ended()?;
+            if self.open_orders > 0 {
+                return Err(Error::FundsInEscrow);
+            }
+
+            let successor: contract_ref!(DutchAuctionTrait) = new_auction.into();
+            let successor_asset = successor.call().asset_token().try_invoke();
+            if !matches!(successor_asset, Ok(Ok(asset)) if asset == self.asset_token()) {
+                return Err(Error::InvalidSuccessor);
+            }
+
+            self.finalized = true;
+            self.update_state();
+            self.release_held_payments()?;
+
+            let unsold = self.unsold();
+            self.give_asset(new_auction, unsold, &[])?;
+            let seq = self.next_event_seq();
+            self.env().emit_event(InventoryRolledOver {
+                new_auction,
+                amount: unsold,
+                seq,
+            });
+            self.notify(AuctionNotification::RolledOver { new_auction });
+
+            Ok(())
+        }
+
+        /// The block from which the forced termination scheduled by `schedule_termination` can
+        /// be executed, if any.
+        #[ink(message)]
+        pub fn termination_at(&self) -> Option<BlockNumber> {
+            self.termination_at.get().flatten()
+        }
+
+        /// Announce a forced termination of the live auction, executable with `terminate(true)`
+        /// once `timelock()` blocks have passed. Buyers get the timelock to react, e.g. by
+        /// claiming or refunding.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn schedule_termination(&mut self) -> Result<(), Error> {
+            self.check_owner(self.env().caller())?;
+            self.check_not_finalized()?;
+            let executable_at = self.env().block_number().saturating_add(self.timelock());
+            self.termination_at.set(&Some(executable_at));
+            let seq = self.next_event_seq();
+            self.env()
+                .emit_event(TerminationScheduled { executable_at, seq });
+
+            Ok(())
+        }
+
+        /// Terminates the contract
+        ///
+        /// Only a finalized or cancelled auction can be terminated, unless `force` is passed
+        /// after the timelock of a termination announced with `schedule_termination`.
+        ///
+        /// Any asset or payment tokens held by the contract are returned to the owner first, and the
+        /// call fails if that isn't possible. Pass `force` to terminate regardless, destroying
+        /// whatever couldn't be returned. The auction can't be terminated while buyers still have
+        /// allocations or reservations, or payments to claim from an all-or-nothing sale.
+        ///
+        /// Requires auction_owner to execute.
+        #[ink(message)]
+        pub fn terminate(&mut self, force: bool) -> Result<(), Error> {
+            let caller = self.env().caller();
+            self.check_owner(caller)?;
+            if self.open_orders > 0
+                || self.credited > 0
+                || !self.open_bids().is_empty()
+                || self.allocated > 0
+                || self.reserved > 0
+                || self.config.raise_goal.is_some()
+                    && self.total_raised > 0
+                    && !self.raise_withdrawn
+            {
+                return Err(Error::FundsInEscrow);
+            }
+            if !self.finalized {
+                match self.termination_at() {
+                    Some(at) if force && self.env().block_number() >= at => {}
+                    Some(_) if force => return Err(Error::TimelockActive),
+                    _ => return Err(Error::NotTerminable),
+                }
+            }
+
+            let swept = self.sweep();
+            if !force {
+                swept?;
+            }
+
+            self.env().terminate_contract(caller)
+        }
+
+        fn current_price(&self) -> u128 {
+            self.price_at_block(self.env().block_number())
+        }
+
+        fn price_at_block(&self, block: BlockNumber) -> u128 {
+            let price =
+                Self::to_payment(self.reference_price_at(block), self.oracle_rate().as_ref());
+            Self::round_to_tick(price, self.config.tick_size)
+        }
+
+        /// Rounds `price` up to the next multiple of `tick_size`, so buyers never pay less than
+        /// the curve. Saturates at the largest multiple representable in `u128`.
+        fn curve_params(&self) -> CurveParams {
+            CurveParams {
+                start_price: self.config.start_price,
+                min_price: self.config.min_price,
+                data: self.strategy_data(),
+            }
+        }
+
+        fn round_to_tick(price: u128, tick_size: u128) -> u128 {
+            if tick_size <= 1 {
+                return price;
+            }
+
+            price
+                .div_ceil(tick_size)
+                .checked_mul(tick_size)
+                .unwrap_or(u128::MAX - u128::MAX % tick_size)
+        }
+
+        fn reference_price_at(&self, block: BlockNumber) -> u128 {
+            if let Some(perpetual) = &self.config.perpetual {
+                let tranche = self.tranche_at(perpetual, block);
+                return Self::linear_decrease(
+                    tranche.start_block.into(),
+                    tranche.start_price,
+                    tranche
+                        .start_block
+                        .saturating_add(perpetual.duration)
+                        .into(),
+                    self.config.min_price,
+                    block.into(),
+                )
+                .max(self.config.min_price);
+            }
+
+            if let Some((index, round)) = self.round_at(block) {
+                return Self::linear_decrease(
+                    round.start_block.into(),
+                    self.round_start_price(index, &round),
+                    self.round_end(index).into(),
+                    round.min_price,
+                    block.into(),
+                )
+                .max(round.min_price);
+            }
+
+            if let Some(strategy) = self.config.price_strategy {
+                let strategy: contract_ref!(PriceStrategy) = strategy.into();
+                let price = strategy
+                    .call()
+                    .price_at(
+                        self.config.start_time,
+                        self.config.end_time,
+                        block,
+                        self.curve_params(),
+                    )
+                    .try_invoke();
+                // A failing strategy falls back to the built-in curve instead of trapping every
+                // purchase, quote and finalization.
+                if let Ok(Ok(price)) = price {
+                    return price.max(self.config.min_price);
+                }
+            }
+
+            if let Some(weights) = &self.config.weights {
+                return Self::weighted_price(
+                    weights,
+                    self.config.start_time,
+                    self.config.end_time,
+                    self.config.start_price,
+                    block,
+                )
+                .max(self.config.min_price);
+            }
+
+            Self::linear_decrease(
+                self.config.start_time.into(),
+                self.config.start_price,
+                self.config.end_time.into(),
+                self.config.min_price,
+                block.into(),
+            )
+            .max(self.config.min_price)
+        }
+
+        fn weighted_price(
+            weights: &WeightSchedule,
+            start_time: BlockNumber,
+            end_time: BlockNumber,
+            start_price: u128,
+            block: BlockNumber,
+        ) -> u128 {
+            let w0 = u128::from(weights.start_asset_weight_bps);
+            let w = Self::linear_decrease(
+                start_time.into(),
+                w0,
+                end_time.into(),
+                weights.end_asset_weight_bps.into(),
+                block.into(),
+            );
+            // start_price * (w / (BPS - w)) / (w0 / (BPS - w0)), split to avoid overflow.
+            let num = w * (BPS - w0);
+            let den = (BPS - w) * w0;
+
+            (start_price / den)
+                .saturating_mul(num)
+                .saturating_add((start_price % den) * num / den)
+        }
+
+        fn quote(&self, amount: u128) -> Result<Quote, Error> {
+            self.quote_for(amount, false)
+        }
+
+        /// Quote a purchase of `amount` by a buyer who may have proven allowlist membership,
+        /// which admits purchases in allowlist-only phases and at the minimum price during the
+        /// grace period.
+        fn quote_for(&self, amount: u128, allowlisted: bool) -> Result<Quote, Error> {
+            self.check_access(allowlisted)?;
+            self.check_not_finalized()?;
+            self.check_not_paused()?;
+            if self.env().block_number() < self.config.start_time {
+                return Err(Error::AuctionNotStarted);
+            }
+            let grace =
+                self.env().block_number() > self.config.end_time && self.config.perpetual.is_none();
+            if grace && (!allowlisted || self.env().block_number() > self.closes_at()) {
+                return Err(Error::AuctionEnded);
+            }
+            if amount == 0 {
+                return Err(Error::ZeroAmount);
+            }
+            self.check_lot_size(amount)?;
+            if !self.rounds().is_empty() && self.current_round().is_none() {
+                return Err(Error::AuctionNotStarted);
+            }
+            let bonus = self.bonus_for(amount);
+            if self.purchasable() < amount.saturating_add(bonus) {
+                return Err(Error::InsufficientSupplyToken);
+            }
+
+            let now = self.env().block_number();
+            let rate = self.oracle_rate();
+            if let Some(rate) = &rate {
+                if now.saturating_sub(rate.updated_at) > self.config.max_rate_age {
+                    return Err(Error::StaleOracleRate);
+                }
+            }
+
+            let reference = if grace {
+                self.config.min_price
+            } else {
+                self.reference_price_at(now)
+            };
+            let price = Self::to_payment(reference, rate.as_ref());
+            let total = Self::round_to_tick(price, self.config.tick_size)
+                .checked_mul(amount)
+                .ok_or(Error::ArithmeticOverflow)?;
+            let total = self.apply_volume_discount(total, amount);
+            if self
+                .config
+                .proceeds_cap
+                .is_some_and(|cap| self.total_paid().saturating_add(total) > cap)
+            {
+                return Err(Error::CapReached);
+            }
+
+            Ok(Quote { total, bonus })
+        }
+
+        /// Quote a purchase of `amount` by `buyer`, discounted by the largest of the active
+        /// phase's, the buyer's loyalty and the given `discount_bps` discounts.
+        fn discounted_quote(
+            &self,
+            buyer: AccountId,
+            amount: u128,
+            allowlisted: bool,
+            discount_bps: u16,
+        ) -> Result<Quote, Error> {
+            let Quote { total, bonus } = self.quote_for(amount, allowlisted)?;
+            let discount_bps = self.discount_bps_for(buyer, discount_bps);
+            let total = total.saturating_sub(total.saturating_mul(discount_bps.into()) / BPS);
+
+            Ok(Quote { total, bonus })
+        }
+
+        /// The largest of the active phase's, the loyalty discount of `buyer` and `discount_bps`.
+        fn discount_bps_for(&self, buyer: AccountId, discount_bps: u16) -> u16 {
+            self.active_phase()
+                .map_or(discount_bps, |(_, phase)| {
+                    phase.discount_bps.max(discount_bps)
+                })
+                .max(self.loyalty_discount_bps(buyer))
+        }
+
+        fn bonus_for(&self, amount: u128) -> u128 {
+            let now = self.env().block_number();
+            let bps = self
+                .bonus_tiers
+                .get()
+                .unwrap_or_default()
+                .iter()
+                .filter(|tier| amount >= tier.min_amount)
+                .filter(|tier| tier.before_block.is_none_or(|block| now < block))
+                .map(|tier| u128::from(tier.bonus_bps))
+                .max()
+                .unwrap_or_default();
+
+            amount.saturating_mul(bps) / BPS
+        }
+
+        fn apply_volume_discount(&self, total: u128, amount: u128) -> u128 {
+            let discount_bps = self
+                .volume_tiers()
+                .iter()
+                .filter(|tier| amount >= tier.min_amount)
+                .map(|tier| u128::from(tier.discount_bps))
+                .max()
+                .unwrap_or_default();
+
+            total - total.saturating_mul(discount_bps) / BPS
+        }
+
+        fn loyalty_discount_bps(&self, account: AccountId) -> u16 {
+            let tiers = self.loyalty_tiers();
+            if tiers.is_empty() {
+                return 0;
+            }
+            let bought = self.buyer_totals_of(account).bought;
+
+            tiers
+                .iter()
+                .filter(|tier| bought >= tier.min_bought)
+                .map(|tier| tier.discount_bps)
+                .max()
+                .unwrap_or_default()
+        }
+
+        fn redeem_voucher(&mut self, voucher: Voucher, signature: [u8; 65]) -> Result<(), Error> {
+            if voucher.beneficiary != self.env().caller() {
+                return Err(Error::NotVoucherBeneficiary);
+            }
+            if self.env().block_number() > voucher.expires_at {
+                return Err(Error::VoucherExpired);
+            }
+            if self.used_vouchers.contains(voucher.nonce) {
+                return Err(Error::VoucherUsed);
+            }
+            if u128::from(voucher.discount_bps) > BPS {
+                return Err(InvalidParameter::InvalidBasisPoints.into());
+            }
+
+            let signer = self.voucher_signer().ok_or(Error::InvalidSignature)?;
+            let public_key = self
+                .env()
+                .ecdsa_recover(&signature, &self.voucher_hash(voucher.clone()))
+                .map_err(|_| Error::InvalidSignature)?;
+            if self.env().ecdsa_to_eth_address(&public_key) != Ok(signer) {
+                return Err(Error::InvalidSignature);
+            }
+
+            self.used_vouchers.insert(voucher.nonce, &());
+
+            Ok(())
+        }
+
+        fn verify_relayed(
+            &mut self,
+            request: &RelayedBuy,
+            signature: [u8; 65],
+        ) -> Result<(), Error> {
+            if self.trusted_forwarder() != Some(self.env().caller()) {
+                return Err(Error::NotTrustedForwarder);
+            }
+            if self.env().block_number() > request.deadline {
+                return Err(Error::RequestExpired);
+            }
+            let nonce = self.relay_nonce(request.buyer);
+            if request.nonce != nonce {
+                return Err(Error::InvalidNonce);
+            }
+
+            let public_key = self
+                .env()
+                .ecdsa_recover(&signature, &self.relayed_buy_hash(request.clone()))
+                .map_err(|_| Error::InvalidSignature)?;
+            let mut signer = [0; 32];
+            ink::env::hash_bytes::<ink::env::hash::Blake2x256>(&public_key, &mut signer);
+            if AccountId::from(signer) != request.buyer {
+                return Err(Error::InvalidSignature);
+            }
+
+            self.relay_nonces.insert(request.buyer, &(nonce + 1));
+
+            Ok(())
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn execute_purchase(
+            &mut self,
+            buyer: AccountId,
+            payer: Payer,
+            recipients: &[(AccountId, u128)],
+            amount: u128,
+            max_price: Option<Balance>,
+            discount_bps: u16,
+            allowlisted: bool,
+            data: &[u8],
+        ) -> Result<u128, Error> {
+            if self.config.collect_orders {
+                return Err(Error::OrderCollectionActive);
+            }
+            self.check_not_blacklisted(buyer)?;
+            let this = self.env().account_id();
+            if let Payer::Account(account) = payer {
+                if account != this {
+                    self.check_not_blacklisted(account)?;
+                }
+            }
+            for (recipient, _) in recipients
+                .iter()
+                .filter(|(recipient, _)| *recipient != buyer)
+            {
+                // Refunds return the purchase from the buyer.
+                if self.refunds_enabled() {
+                    return Err(Error::DeliveryNotRefundable);
+                }
+                self.check_not_blacklisted(*recipient)?;
+            }
+            self.lock()?;
+            let Quote { total, bonus } =
+                self.discounted_quote(buyer, amount, allowlisted, discount_bps)?;
+            if let Some(max_price) = max_price {
+                if total > max_price {
+                    return Err(Error::MaxPriceExceeded);
+                }
+            }
+            self.check_purchase(buyer, amount, allowlisted)?;
+            self.admit_purchase(buyer, amount, bonus, total)?;
+            let deferred = self.config.raise_goal.is_some() || self.config.deferred_delivery;
+            self.record_purchase(buyer, amount, bonus, total, deferred, data);
+
+            match (payer, self.config.raise_goal.is_some()) {
+                (Payer::Account(payer), true) => self.escrow_purchase(payer, buyer, total, data)?,
+                (Payer::Incoming, true) => self.escrow_purchase(this, buyer, total, data)?,
+                (Payer::Account(payer), false) => self.collect_payment(payer, total, data)?,
+                // The incoming tokens are only credited once the transfer completes, so they
+                // can't be forwarded yet.
+                (Payer::Incoming, false) => {
+                    self.held_payments = self.held_payments.saturating_add(total);
+                }
+            }
+            for (recipient, share) in Self::share_bonus(recipients, amount, bonus) {
+                if deferred {
+                    self.allocate(recipient, share);
+                } else {
+                    self.deliver_asset(recipient, share, data)?;
+                }
+            }
+            if !deferred {
+                self.reconcile_escrow();
+            }
+            self.unlock();
+            self.update_state();
+
+            self.notify_listeners(buyer, amount, total);
+
+            Ok(total)
+        }
+
+        fn simulate_purchase(
+            &self,
+            account: AccountId,
+            amount: u128,
+            simulation: &mut BuySimulation,
+        ) -> Result<(), Error> {
+            if self.config.collect_orders {
+                return Err(Error::OrderCollectionActive);
+            }
+            let phase = self.active_phase();
+            let Quote { total, bonus } = self.quote(amount)?;
+            let discount_bps = phase
+                .as_ref()
+                .map_or(0, |(_, phase)| phase.discount_bps)
+                .max(self.loyalty_discount_bps(account));
+            let discount = total.saturating_mul(discount_bps.into()) / BPS;
+            let total = total - discount;
+            let (donation, held_back, bounty) = self.split_payment(total);
+            let held_back = held_back - bounty;
+            let liquidity = if self.config.burn_proceeds {
+                held_back.saturating_mul(self.liquidity_bps()) / BPS
+            } else {
+                held_back
+            };
+            *simulation = BuySimulation {
+                unit_price: (total + discount) / amount,
+                total,
+                discount,
+                bonus,
+                donation,
+                liquidity,
+                bounty,
+                burned: held_back - liquidity,
+                proceeds: total - donation - held_back - bounty,
+                error: None,
+            };
+
+            self.check_purchase(account, amount, false)?;
+            self.check_payment(account, total)
+        }
+
+        fn round_at(&self, block: BlockNumber) -> Option<(u32, Round)> {
+            let rounds = self.rounds();
+            let index = rounds
+                .iter()
+                .rposition(|round| round.start_block <= block)?;
+
+            Some((index as u32, rounds[index].clone()))
+        }
+
+        fn tranche_at(&self, perpetual: &Perpetual, block: BlockNumber) -> Tranche {
+            let timeouts = block.saturating_sub(self.tranche.start_block) / perpetual.duration;
+            if timeouts == 0 {
+                return self.tranche.clone();
+            }
+
+            Tranche {
+                index: self.tranche.index.saturating_add(timeouts),
+                start_block: self.tranche.start_block + timeouts * perpetual.duration,
+                start_price: self.tranche.start_price,
+                sold: 0,
+            }
+        }
+
+        fn record_tranche(&mut self, sold: u128) {
+            let Some(perpetual) = self.config.perpetual.clone() else {
+                return;
+            };
+            let mut tranche = self.tranche_at(&perpetual, self.env().block_number());
+            tranche.sold = tranche.sold.saturating_add(sold);
+            if tranche.sold >= perpetual.tranche_size {
+                tranche = Tranche {
+                    index: tranche.index.saturating_add(1),
+                    start_block: self.env().block_number(),
+                    start_price: (self
+                        .last_sale_price
+                        .saturating_mul(perpetual.restart_bps.into())
+                        / BPS)
+                        .max(self.config.min_price),
+                    sold: 0,
+                };
+                let seq = self.next_event_seq();
+                self.env().emit_event(TrancheStarted {
+                    index: tranche.index,
+                    start_price: tranche.start_price,
+                    seq,
+                });
+            }
+            self.tranche = tranche;
+        }
+
+        /// The start price of round `index`. An anchored round takes it from the last sale
+        /// price until its first purchase fixes it.
+        fn round_start_price(&self, index: u32, round: &Round) -> u128 {
+            let Some(anchor_bps) = round.anchor_bps else {
+                return round.start_price;
+            };
+            if let Some(price) = self.round_start_prices.get(index) {
+                return price;
+            }
+            if self.last_sale_price == 0 {
+                return round.start_price;
+            }
+
+            (self.last_sale_price.saturating_mul(anchor_bps.into()) / BPS).max(round.min_price)
+        }
+
+        /// The block at which round `index` ends, i.e. the next round starts.
+        fn round_end(&self, index: u32) -> BlockNumber {
+            self.rounds()
+                .get(index as usize + 1)
+                .map_or(self.config.end_time, |round| round.start_block)
+        }
+
+        /// The asset tokens available to buyers, within the supply of the current round or
+        /// tranche.
+        fn purchasable(&self) -> u128 {
+            let available = self.available_asset();
+            if let Some(perpetual) = &self.config.perpetual {
+                let tranche = self.tranche_at(perpetual, self.env().block_number());
+                return available.min(perpetual.tranche_size.saturating_sub(tranche.sold));
+            }
+            match self.current_round() {
+                Some((index, round)) => {
+                    available.min(round.supply.saturating_sub(self.round_stats(index).sold))
+                }
+                None => available,
+            }
+        }
+
+        fn phase_at(&self, block: BlockNumber) -> Option<(u32, Phase)> {
+            let phases = self.phases();
+            let index = phases
+                .iter()
+                .rposition(|phase| phase.start_block <= block)?;
+
+            Some((index as u32, phases[index].clone()))
+        }
+
+        fn allowlist_leaf(account: AccountId) -> [u8; 32] {
+            let mut leaf = [0; 32];
+            ink::env::hash_encoded::<ink::env::hash::Blake2x256, _>(&account, &mut leaf);
+            leaf
+        }
+
+        fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+            let mut parent = [0; 32];
+            ink::env::hash_encoded::<ink::env::hash::Blake2x256, _>(
+                &(a.min(b), a.max(b)),
+                &mut parent,
+            );
+            parent
+        }
+
+        fn check_allowlist_proof(
+            &self,
+            account: AccountId,
+            proof: &[[u8; 32]],
+        ) -> Result<(), Error> {
+            let root = self.allowlist_root().ok_or(Error::NotAllowlisted)?;
+            if !Self::verify_proof(root, Self::allowlist_leaf(account), proof) {
+                return Err(Error::NotAllowlisted);
+            }
+
+            Ok(())
+        }
+
+        fn verify_proof(root: [u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+            proof
+                .iter()
+                .fold(leaf, |node, sibling| Self::hash_pair(node, *sibling))
+                == root
+        }
+
+        fn oracle_rate(&self) -> Option<RateData> {
+            let oracle: contract_ref!(PriceOracle) = self.config.price_oracle?.into();
+            Some(oracle.latest_rate())
+        }
+
+        fn to_payment(reference: u128, rate: Option<&RateData>) -> u128 {
+            let Some(rate) = rate else {
+                return reference;
+            };
+
+            (reference / RATE_PRECISION)
+                .saturating_mul(rate.rate)
+                .saturating_add(
+                    (reference % RATE_PRECISION).saturating_mul(rate.rate) / RATE_PRECISION,
+                )
+        }
+
+        fn check_payment(&self, from: AccountId, amount: u128) -> Result<(), Error> {
+            let payment = self.payment();
+            let allowance = payment
+                .call()
+                .allowance(from, self.env().account_id())
+                .try_invoke();
+            if Self::token_call(allowance)? < amount {
+                return Err(Error::InsufficientAllowance);
+            }
+            if Self::token_call(payment.call().balance_of(from).try_invoke())? < amount {
+                return Err(Error::InsufficientBuyerBalance);
+            }
+
+            Ok(())
+        }
+
+        fn collect_payment(
+            &mut self,
+            payer: AccountId,
+            amount: u128,
+            data: &[u8],
+        ) -> Result<(), Error> {
+            if self.cooling_off.get().unwrap_or_default().window == 0 {
+                return self.take_payment(payer, amount, data);
+            }
+
+            let received = self.pull_payment(payer, amount, data)?;
+            self.held_payments = self.held_payments.saturating_add(received);
+
+            Ok(())
+        }
+
+        fn close_bid(&mut self, id: u32) {
+            self.bids.remove(id);
+            let mut open_bids = self.open_bids();
+            open_bids.retain(|open| *open != id);
+            self.open_bids.set(&open_bids);
+        }
+
+        /// Returns the asset tokens filled and the payment kept for `order`, when `total` asset
+        /// tokens are ordered against `supply`.
+        fn pro_rata(order: &Order, supply: u128, total: u128) -> (u128, u128) {
+            if total <= supply {
+                return (order.amount, order.paid);
+            }
+
+            let filled = Self::mul_div(order.amount, supply, total);
+            let cost = Self::mul_div(order.paid, filled, order.amount);
+
+            (filled, cost.min(order.paid))
+        }
+
+        /// Returns `value * num / den` without overflowing in the intermediate product, rounding
+        /// down. `den` must not be zero.
+        fn mul_div(value: u128, num: u128, den: u128) -> u128 {
+            (value / den)
+                .saturating_mul(num)
+                .saturating_add((value % den).saturating_mul(num) / den)
+        }
+
+        /// Transfers `amount` payment tokens from `from` to the contract. Returns the amount
+        /// received, which a fee-on-transfer token may reduce within the configured tolerance.
+        fn pull_payment(&self, from: AccountId, amount: u128, data: &[u8]) -> Result<u128, Error> {
+            let this = self.env().account_id();
+            if from == this {
+                return Ok(amount);
+            }
+
+            // Tokens report a shortfall inconsistently, if at all, so check before transferring.
+            self.check_payment(from, amount)?;
+            let Some(tolerance) = self.config.payment_fee_tolerance_bps else {
+                Self::transfer_token_from(self.payment(), from, this, amount, data)?;
+                return Ok(amount);
+            };
+
+            let before = self.payment().balance_of(this);
+            Self::transfer_token_from(self.payment(), from, this, amount, data)?;
+            let received = self.payment().balance_of(this).saturating_sub(before);
+            if received < amount.saturating_sub(amount.saturating_mul(tolerance.into()) / BPS) {
+                return Err(Error::TransferFeeExceeded);
+            }
+
+            Ok(received)
+        }
+
+        fn wrap_native(&self, value: Balance) -> Result<(), Error> {
+            if !self.config.wrapped_native {
+                return Err(Error::NativePaymentNotAccepted);
+            }
+
+            let mut wrapper: contract_ref!(WrappedNative) = self.config.payment_token.into();
+            let call = wrapper
+                .call_mut()
+                .deposit()
+                .transferred_value(value)
+                .try_invoke();
+            Ok(Self::token_call(call)??)
+        }
+
+        fn release_held_payments(&mut self) -> Result<(), Error> {
+            let held = core::mem::take(&mut self.held_payments);
+            if held == 0 {
+                return Ok(());
+            }
+
+            self.take_payment(self.env().account_id(), held, &[])
+        }
+
+        fn escrow_purchase(
+            &mut self,
+            payer: AccountId,
+            buyer: AccountId,
+            price: u128,
+            data: &[u8],
+        ) -> Result<(), Error> {
+            let received = self.pull_payment(payer, price, data)?;
+
+            self.total_raised = self.total_raised.saturating_add(received);
+            self.contributions
+                .insert(buyer, &self.contribution_of(buyer).saturating_add(received));
+
+            Ok(())
+        }
+
+        fn buy_on_receipt(
+            &mut self,
+            from: AccountId,
+            value: u128,
+            data: &[u8],
+        ) -> Result<(), Error> {
+            let order = <BuyOrder as scale::Decode>::decode(&mut &data[..])
+                .map_err(|_| Error::InvalidBuyOrder)?;
+            let max_price = order
+                .max_price
+                .map_or(value, |max_price| max_price.min(value));
+            let paid = self.execute_purchase(
+                from,
+                Payer::Incoming,
+                &[(from, order.amount)],
+                order.amount,
+                Some(max_price),
+                0,
+                false,
+                data,
+            )?;
+            if value > paid {
+                self.credited = self.credited.saturating_add(value - paid);
+                self.payment_credits.insert(
+                    from,
+                    &self.payment_credit_of(from).saturating_add(value - paid),
+                );
+            }
+
+            Ok(())
+        }
+
+        /// Adds the bonus to the recipients' shares of a purchase in proportion to their shares,
+        /// the first recipient receiving the rounding remainder.
+        fn share_bonus(
+            recipients: &[(AccountId, u128)],
+            amount: u128,
+            bonus: u128,
+        ) -> Vec<(AccountId, u128)> {
+            let mut shares: Vec<_> = recipients
+                .iter()
+                .map(|(recipient, share)| {
+                    let share_bonus = bonus.saturating_mul(*share).checked_div(amount);
+                    (
+                        *recipient,
+                        share.saturating_add(share_bonus.unwrap_or_default()),
+                    )
+                })
+                .collect();
+            let shared = shares
+                .iter()
+                .fold(0u128, |total, (_, share)| total.saturating_add(*share));
+            if let Some((_, first)) = shares.first_mut() {
+                *first = first.saturating_add(amount.saturating_add(bonus).saturating_sub(shared));
+            }
+
+            shares
+        }
+
+        fn refunds_enabled(&self) -> bool {
+            self.config.raise_goal.is_none()
+                && self.cooling_off.get().is_some_and(|terms| terms.window > 0)
+        }
+
+        fn allocate(&mut self, buyer: AccountId, amount: u128) {
+            self.allocated = self.allocated.saturating_add(amount);
+            self.allocations
+                .insert(buyer, &self.allocation_of(buyer).saturating_add(amount));
+            if self.flip_restricted() {
+                self.allocation_blocks
+                    .insert(buyer, &self.env().block_number());
+            }
+        }
+
+        fn goal_reached(&self) -> Result<bool, Error> {
+            let goal = self.config.raise_goal.ok_or(Error::NothingToClaim)?;
+            self.check_ended()?;
+
+            Ok(self.total_raised >= goal)
+        }
+
+        fn unsold(&self) -> u128 {
+            let owed = match self.goal_reached() {
+                Ok(true) => self.allocated,
+                // Allocations of a deferred-delivery sale stay in escrow until claimed.
+                _ if self.config.raise_goal.is_none() => self.allocated,
+                _ => 0,
+            };
+
+            self.escrow_balance().saturating_sub(owed)
+        }
+
+        fn liquidity_bps(&self) -> u128 {
+            self.liquidity_config()
+                .map_or(0, |config| u128::from(config.proceeds_bps))
+        }
+
+        /// Returns the donation, the share held back by the contract and the part of the latter
+        /// topping up the finalize bounty, for a payment of `amount`.
+        fn split_payment(&self, amount: u128) -> (u128, u128, u128) {
+            let donated = self.donation().map_or(0, |donation| {
+                amount.saturating_mul(donation.bps.into()) / BPS
+            });
+            // The remainder is split between the owner, the liquidity share and burning.
+            let amount = amount - donated;
+            // Proceeds to be burned are collected by the contract along with the liquidity share.
+            let held_back = if self.config.burn_proceeds {
+                amount
+            } else {
+                amount.saturating_mul(self.liquidity_bps()) / BPS
+            };
+            // The finalize bounty is held back from the first proceeds until it is covered.
+            let to_bounty = self
+                .finalize_bounty()
+                .saturating_sub(self.bounty_reserve)
+                .min(amount - held_back);
+
+            (donated, held_back + to_bounty, to_bounty)
+        }
+
+        fn take_payment(
+            &mut self,
+            from: AccountId,
+            amount: u128,
+            data: &[u8],
+        ) -> Result<(), Error> {
+            let this = self.env().account_id();
+            if from != this {
+                self.check_payment(from, amount)?;
+            }
+            let (donated, held_back, to_bounty) = self.split_payment(amount);
+            let recipient = self.donation().map(|donation| donation.recipient);
+            let amount = amount - donated;
+            let liquidity_bps = self.liquidity_bps();
+
+            // Payments made from the contract's own balance, e.g. swap output, are already
+            // holding back the liquidity share.
+            let owner = self.proceeds_recipient();
+            let balances_before = self.config.payment_fee_tolerance_bps.map(|_| {
+                (
+                    self.payment().balance_of(owner),
+                    self.payment().balance_of(this),
+                )
+            });
+
+            let mut call = if from == this {
+                Self::transfer_token(self.payment(), owner, amount - held_back, data)
+            } else {
+                Self::transfer_token_from(self.payment(), from, owner, amount - held_back, data)
+            };
+            if call.is_ok() && held_back > 0 && from != this {
+                call = Self::transfer_token_from(self.payment(), from, this, held_back, data);
+            }
+            if let Some(recipient) = recipient.filter(|_| call.is_ok() && donated > 0) {
+                call = if from == this {
+                    Self::transfer_token(self.payment(), recipient, donated, data)
+                } else {
+                    Self::transfer_token_from(self.payment(), from, recipient, donated, data)
+                };
+            }
+            call?;
+
+            let (forwarded, held_back) = match balances_before {
+                Some((owner_before, this_before)) => {
+                    let forwarded = self
+                        .payment()
+                        .balance_of(owner)
+                        .saturating_sub(owner_before);
+                    let held = if from == this {
+                        held_back
+                    } else {
+                        self.payment().balance_of(this).saturating_sub(this_before)
+                    };
+                    let tolerance = self.config.payment_fee_tolerance_bps.unwrap_or_default();
+                    let minimum =
+                        amount.saturating_sub(amount.saturating_mul(tolerance.into()) / BPS);
+                    if forwarded.saturating_add(held) < minimum {
+                        return Err(Error::TransferFeeExceeded);
+                    }
+                    (forwarded, held)
+                }
+                None => (amount - held_back, held_back),
+            };
+            let bounty = to_bounty.min(held_back);
+            self.bounty_reserve = self.bounty_reserve.saturating_add(bounty);
+            let held_back = held_back - bounty;
+            let to_liquidity = if self.config.burn_proceeds {
+                held_back.saturating_mul(liquidity_bps) / BPS
+            } else {
+                held_back
+            };
+            self.liquidity_proceeds = self.liquidity_proceeds.saturating_add(to_liquidity);
+            let burned = held_back - to_liquidity;
+            if burned > 0 {
+                let mut payment: contract_ref!(PSP22Burnable) = self.config.payment_token.into();
+                Self::token_call(payment.call_mut().burn(burned).try_invoke())??;
+                let seq = self.next_event_seq();
+                self.env().emit_event(ProceedsBurned {
+                    amount: burned,
+                    seq,
+                });
+            }
+            if let Some(recipient) = recipient.filter(|_| donated > 0) {
+                let seq = self.next_event_seq();
+                self.env().emit_event(DonationSent {
+                    recipient,
+                    amount: donated,
+                    seq,
+                });
+            }
+            self.record_proceeds(
+                forwarded
+                    .saturating_add(held_back)
+                    .saturating_add(bounty)
+                    .saturating_add(donated),
+            );
+
+            Ok(())
+        }
+
+        fn record_proceeds(&mut self, amount: u128) {
+            let token = self.config.payment_token;
+            match self.proceeds.get(token) {
+                Some(total) => self.proceeds.insert(token, &total.saturating_add(amount)),
+                None => {
+                    let mut tokens = self.proceeds_tokens.get().unwrap_or_default();
+                    tokens.push(token);
+                    self.proceeds_tokens.set(&tokens);
+                    self.proceeds.insert(token, &amount)
+                }
+            };
+        }
+
+        fn seed_liquidity(&mut self) -> Result<(), Error> {
+            let Some(config) = self.liquidity_config() else {
+                return Ok(());
+            };
+            let payment_amount = self.liquidity_proceeds;
+            if payment_amount == 0 || config.asset_amount == 0 {
+                return Ok(());
+            }
+            // Asset tokens reserved or allocated to buyers are still owed to them.
+            let asset_amount = config.asset_amount.min(
+                self.escrow_balance()
+                    .saturating_sub(self.allocated)
+                    .saturating_sub(self.reserved),
+            );
+
+            Self::approve_token(self.asset(), config.router, asset_amount)?;
+            Self::approve_token(self.payment(), config.router, payment_amount)?;
+            let mut router: contract_ref!(DexRouter) = config.router.into();
+            let (asset_amount, payment_amount, liquidity) = router.add_liquidity(
+                self.config.asset_token,
+                self.config.payment_token,
+                asset_amount,
+                payment_amount,
+                asset_amount,
+                payment_amount,
+                self.proceeds_recipient(),
+                self.env().block_timestamp(),
+            )?;
+            // Don't leave allowances behind if the router deposited less than approved.
+            Self::approve_token(self.asset(), config.router, 0)?;
+            Self::approve_token(self.payment(), config.router, 0)?;
+            self.liquidity_proceeds = 0;
+            self.tracked_escrow = self.tracked_escrow.saturating_sub(asset_amount);
+
+            let seq = self.next_event_seq();
+            self.env().emit_event(LiquiditySeeded {
+                router: config.router,
+                asset_amount,
+                payment_amount,
+                liquidity,
+                seq,
+            });
+
+            Ok(())
+        }
+
+        fn deliver_asset(&mut self, to: AccountId, amount: u128, data: &[u8]) -> Result<(), Error> {
+            if self.config.mint_on_demand {
+                let mut asset: contract_ref!(PSP22Mintable) = self.config.asset_token.into();
+                Self::token_call(asset.call_mut().mint(amount).try_invoke())??;
+                self.tracked_escrow = self.tracked_escrow.saturating_add(amount);
+            }
+
+            self.give_asset(to, amount, data)
+        }
+
+        fn give_asset(&mut self, to: AccountId, amount: u128, data: &[u8]) -> Result<(), Error> {
+            Self::transfer_token(self.asset(), to, amount, data)?;
+            self.tracked_escrow = self.tracked_escrow.saturating_sub(amount);
+
+            Ok(())
+        }
+
+        fn reconcile_escrow(&mut self) -> u128 {
+            let actual = self.escrow_balance();
+            if actual != self.tracked_escrow {
+                let seq = self.next_event_seq();
+                self.env().emit_event(EscrowReconciled {
+                    expected: self.tracked_escrow,
+                    actual,
+                    seq,
+                });
+                self.tracked_escrow = actual;
+            }
+
+            actual
+        }
+
+        fn record_purchase(
+            &mut self,
+            buyer: AccountId,
+            amount: u128,
+            bonus: u128,
+            price: u128,
+            allocated: bool,
+            data: &[u8],
+        ) {
+            self.total_sold = self.total_sold.saturating_add(amount.saturating_add(bonus));
+            if let Some((index, round)) = self.current_round() {
+                if round.anchor_bps.is_some() && !self.round_start_prices.contains(index) {
+                    self.round_start_prices
+                        .insert(index, &self.round_start_price(index, &round));
+                }
+            }
+            if let Some(unit_price) = price.checked_div(amount) {
+                self.last_sale_price = unit_price;
+                self.record_price_bucket(unit_price, amount);
+            }
+            self.record_tranche(amount.saturating_add(bonus));
+
+            let block = self.env().block_number();
+            let last = self
+                .observation_count
+                .checked_sub(1)
+                .and_then(|index| self.observations.get(index));
+            let (paid, bought) = last
+                .as_ref()
+                .map(|o| (o.cumulative_paid, o.cumulative_amount))
+                .unwrap_or_default();
+            let observation = Observation {
+                block,
+                cumulative_paid: paid.saturating_add(price),
+                cumulative_amount: bought.saturating_add(amount),
+            };
+            if last.is_some_and(|o| o.block == block) {
+                self.observations
+                    .insert(self.observation_count - 1, &observation);
+            } else {
+                self.observations
+                    .insert(self.observation_count, &observation);
+                self.observation_count += 1;
+            }
+
+            self.record_checkpoint();
+
+            let id = self.next_purchase_id;
+            self.next_purchase_id = id.saturating_add(1);
+            let index = self.purchase_count_of(buyer);
+            self.purchase_ids.insert(id, &(buyer, index));
+            self.purchases.insert(
+                (buyer, index),
+                &Purchase {
+                    id,
+                    block,
+                    amount,
+                    price,
+                    bonus,
+                    allocated,
+                    refunded: false,
+                },
+            );
+            self.purchase_counts.insert(buyer, &(index + 1));
+
+            let mut totals = match self.buyer_totals.get(buyer) {
+                Some(totals) => totals,
+                None => {
+                    let count = self.buyer_count();
+                    self.buyers.insert(count, &buyer);
+                    self.buyer_count.set(&(count + 1));
+                    BuyerTotals {
+                        first_block: block,
+                        ..Default::default()
+                    }
+                }
+            };
+            totals.bought = totals.bought.saturating_add(amount.saturating_add(bonus));
+            totals.paid = totals.paid.saturating_add(price);
+            self.buyer_totals.insert(buyer, &totals);
+            if self.config.track_leaderboard {
+                self.update_leaderboard(buyer, totals.bought);
+            }
+
+            let seq = self.next_event_seq();
+            self.env().emit_event(AssetBought {
+                price,
+                by: buyer,
+                purchase_id: id,
+                amount,
+                bonus,
+                data: data.to_vec(),
+                seq,
+            });
+        }
+
+        fn update_leaderboard(&mut self, buyer: AccountId, bought: u128) {
+            let mut leaderboard = self.leaderboard();
+            leaderboard.retain(|(entry, _)| *entry != buyer);
+            // Earlier buyers keep their rank on ties.
+            let rank = leaderboard.partition_point(|(_, amount)| *amount >= bought);
+            if rank < LEADERBOARD_SIZE && bought > 0 {
+                leaderboard.insert(rank, (buyer, bought));
+                leaderboard.truncate(LEADERBOARD_SIZE);
+            }
+            self.leaderboard.set(&leaderboard);
+        }
+
+        fn notify_listeners(&self, buyer: AccountId, amount: u128, price: u128) {
+            for listener in self.listeners() {
+                let mut listener: contract_ref!(AuctionListener) = listener.into();
+                // Failures are deliberately ignored, a listener can't block purchases.
+                let _ = listener
+                    .call_mut()
+                    .on_purchase(buyer, amount, price)
+                    .gas_limit(LISTENER_GAS_LIMIT)
+                    .try_invoke();
+            }
+        }
+
+        fn observation_at(&self, block: BlockNumber) -> Option<Observation> {
+            let (mut low, mut high) = (0, self.observation_count);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if self.observations.get(mid)?.block <= block {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+
+            self.observations.get(low.checked_sub(1)?)
+        }
+
+        fn record_checkpoint(&mut self) -> Checkpoint {
+            let checkpoint = Checkpoint {
+                block: self.env().block_number(),
+                price: self.current_price(),
+                cumulative_sold: self.total_sold,
+            };
+            let latest = self.checkpoint_count.checked_sub(1);
+            let slot = match latest.and_then(|n| self.checkpoint_slot(n)) {
+                Some(last) if last.block == checkpoint.block => latest.unwrap_or_default(),
+                _ => {
+                    self.checkpoint_count = self.checkpoint_count.saturating_add(1);
+                    self.checkpoint_count - 1
+                }
+            };
+            self.checkpoints
+                .insert(slot % CHECKPOINT_CAPACITY, &checkpoint);
+
+            checkpoint
+        }
+
+        fn checkpoint_slot(&self, n: u32) -> Option<Checkpoint> {
+            if n >= self.checkpoint_count
+                || n < self.checkpoint_count.saturating_sub(CHECKPOINT_CAPACITY)
+            {
+                return None;
+            }
+
+            self.checkpoints.get(n % CHECKPOINT_CAPACITY)
+        }
+
+        fn record_price_bucket(&mut self, unit_price: u128, amount: u128) {
+            let bucket = Self::price_bucket(unit_price);
+            let mut histogram = self.price_histogram.get().unwrap_or_default();
+            match histogram.binary_search_by_key(&bucket, |(bucket, _)| *bucket) {
+                Ok(index) => histogram[index].1 = histogram[index].1.saturating_add(amount),
+                Err(index) => histogram.insert(index, (bucket, amount)),
+            }
+            self.price_histogram.set(&histogram);
+        }
+
+        /// Buckets are numbered in price order. Prices below `2^(PRICE_BUCKET_BITS + 1)` have a
+        /// bucket each, larger ones share a bucket with the prices agreeing on their highest
+        /// `PRICE_BUCKET_BITS + 1` bits.
+        fn price_bucket(price: u128) -> u16 {
+            let limit = 1u128 << (PRICE_BUCKET_BITS + 1);
+            if price < limit {
+                return price as u16;
+            }
+
+            let shift = u128::BITS - price.leading_zeros() - PRICE_BUCKET_BITS - 1;
+            ((shift as u16) << PRICE_BUCKET_BITS) + (price >> shift) as u16
+        }
+
+        fn bucket_midpoint(bucket: u16) -> u128 {
+            let limit = 1u16 << (PRICE_BUCKET_BITS + 1);
+            if bucket < limit {
+                return bucket.into();
+            }
+
+            let shift = u32::from(bucket >> PRICE_BUCKET_BITS) - 1;
+            let mantissa = u128::from(bucket % (1 << PRICE_BUCKET_BITS) + (1 << PRICE_BUCKET_BITS));
+            (mantissa << shift) + ((1u128 << shift) >> 1)
+        }
+
+        fn notify(&self, notification: AuctionNotification) {
+            for subscriber in self.subscribers() {
+                let mut observer: contract_ref!(AuctionObserver) = subscriber.into();
+                // Failures are deliberately ignored, an observer can't block the auction.
+                let _ = observer
+                    .call_mut()
+                    .on_auction_event(notification.clone())
+                    .gas_limit(LISTENER_GAS_LIMIT)
+                    .try_invoke();
+            }
+        }
+
+        fn sweep(&mut self) -> Result<(), Error> {
+            let assets = self.escrow_balance();
+            if assets > 0 {
+                self.give_asset(self.proceeds_recipient(), assets, &[])?;
+            }
+
+            let proceeds = self.payment().balance_of(self.env().account_id());
+            if proceeds > 0 {
+                Self::transfer_token(self.payment(), self.proceeds_recipient(), proceeds, &[])?;
+            }
+
+            Ok(())
+        }
+
+        fn token_metadata(&self, token: AccountId) -> TokenMetadata {
+            let metadata: contract_ref!(PSP22Metadata) = token.into();
+            // The extension is optional, so failed calls are reported as missing fields.
+            TokenMetadata {
+                token,
+                name: metadata
+                    .call()
+                    .token_name()
+                    .try_invoke()
+                    .ok()
+                    .and_then(Result::ok)
+                    .flatten(),
+                symbol: metadata
+                    .call()
+                    .token_symbol()
+                    .try_invoke()
+                    .ok()
+                    .and_then(Result::ok)
+                    .flatten(),
+                decimals: metadata
+                    .call()
+                    .token_decimals()
+                    .try_invoke()
+                    .ok()
+                    .and_then(Result::ok),
+            }
+        }
+
+        fn proceeds_recipient(&self) -> AccountId {
+            self.treasury.unwrap_or(self.auction_owner)
+        }
+
+        /// Unwraps the outcome of a token call made with `try_invoke`, so that a token trapping
+        /// or returning unexpected data fails the message with `TokenCallFailed` instead of
+        /// trapping it.
+        fn token_call<T>(result: ink::env::Result<ink::MessageResult<T>>) -> Result<T, Error> {
+            match result {
+                Ok(Ok(value)) => Ok(value),
+                Ok(Err(lang_error)) => Err(Error::TokenCallFailed {
+                    lang_error: Some(lang_error),
+                }),
+                Err(_) => Err(Error::TokenCallFailed { lang_error: None }),
+            }
+        }
+
+        fn transfer_token(
+            mut token: contract_ref!(PSP22),
+            to: AccountId,
+            value: u128,
+            data: &[u8],
+        ) -> Result<(), Error> {
+            let call = token
+                .call_mut()
+                .transfer(to, value, data.to_vec())
+                .try_invoke();
+            Ok(Self::token_call(call)??)
+        }
+
+        fn transfer_token_from(
+            mut token: contract_ref!(PSP22),
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+            data: &[u8],
+        ) -> Result<(), Error> {
+            let call = token
+                .call_mut()
+                .transfer_from(from, to, value, data.to_vec())
+                .try_invoke();
+            Ok(Self::token_call(call)??)
+        }
+
+        fn approve_token(
+            mut token: contract_ref!(PSP22),
+            spender: AccountId,
+            value: u128,
+        ) -> Result<(), Error> {
+            let call = token.call_mut().approve(spender, value).try_invoke();
+            Ok(Self::token_call(call)??)
+        }
+
+        fn asset(&self) -> contract_ref!(PSP22) {
+            self.config.asset_token.into()
+        }
+
+        fn payment(&self) -> contract_ref!(PSP22) {
+            self.config.payment_token.into()
+        }
+
+        fn escrow_balance(&self) -> u128 {
+            self.asset().balance_of(self.env().account_id())
+        }
+
+        /// Returns (an approximation of) the linear function passing through `(x_start, y_start)` and `(x_end, y_end)` at
+        /// `x`. If `x` is outside the range of `x_start` and `x_end`, the value of `y` at the closest endpoint is returned.
+        fn linear_decrease(
+            x_start: u128,
+            y_start: u128,
+            x_end: u128,
+            y_end: u128,
+            x: u128,
+        ) -> u128 {
+            let steps = x.saturating_sub(x_start);
+            let x_span = x_end.saturating_sub(x_start);
+            let y_span = y_start.saturating_sub(y_end);
+
+            if x >= x_end {
+                y_end
+            } else if x <= x_start || y_span == 0 {
+                y_start
+            } else if y_span > x_span {
+                let y_per_x = y_span.saturating_div(x_span);
+                y_start.saturating_sub(steps.saturating_mul(y_per_x))
+            } else {
+                let x_per_y = x_span.saturating_div(y_span);
+                y_start.saturating_sub(steps.saturating_div(x_per_y))
+            }
+        }
+
+        fn check_change(&self, change: &ParameterChange) -> Result<(), Error> {
+            match change {
+                ParameterChange::MinPrice(min_price) => {
+                    Self::check_price_range(self.config.start_price, *min_price)?;
+                    let current = self.config.min_price;
+                    match self.min_price_increase_limit() {
+                        Some(limit)
+                            if *min_price
+                                > current.saturating_add(
+                                    current.saturating_mul(limit.max_increase_bps.into()) / BPS,
+                                ) =>
+                        {
+                            Err(InvalidParameter::MinPriceIncreaseTooLarge.into())
+                        }
+                        _ => Ok(()),
+                    }
+                }
+                ParameterChange::EndTime(end_time) => {
+                    Self::check_time_range(self.config.start_time, *end_time)
+                }
+                ParameterChange::LotSize(_)
+                    if self.total_sold > 0 || self.reserved > 0 || self.open_orders > 0 =>
+                {
+                    Err(Error::SaleAlreadyStarted)
+                }
+                ParameterChange::PaymentFeeTolerance(Some(bps))
+                | ParameterChange::KeeperFee(bps)
+                    if u128::from(*bps) > BPS =>
+                {
+                    Err(InvalidParameter::InvalidBasisPoints.into())
+                }
+                ParameterChange::Phases(phases) => {
+                    if phases
+                        .iter()
+                        .any(|phase| u128::from(phase.discount_bps) > BPS)
+                    {
+                        return Err(InvalidParameter::InvalidBasisPoints.into());
+                    }
+                    if phases
+                        .windows(2)
+                        .any(|pair| pair[0].start_block >= pair[1].start_block)
+                    {
+                        return Err(InvalidParameter::UnorderedPhases.into());
+                    }
+                    Ok(())
+                }
+                ParameterChange::VolumeTiers(tiers)
+                    if tiers.iter().any(|tier| u128::from(tier.discount_bps) > BPS) =>
+                {
+                    Err(InvalidParameter::InvalidBasisPoints.into())
+                }
+                ParameterChange::LoyaltyTiers(tiers)
+                    if tiers.iter().any(|tier| u128::from(tier.discount_bps) > BPS) =>
+                {
+                    Err(InvalidParameter::InvalidBasisPoints.into())
+                }
+                ParameterChange::Donation(donation) => {
+                    self.check_not_finalized()?;
+                    if donation
+                        .as_ref()
+                        .is_some_and(|donation| u128::from(donation.bps) > BPS)
+                    {
+                        return Err(InvalidParameter::InvalidBasisPoints.into());
+                    }
+                    Ok(())
+                }
+                ParameterChange::LiquidityConfig(config) => {
+                    self.check_not_finalized()?;
+                    if config
+                        .as_ref()
+                        .is_some_and(|config| u128::from(config.proceeds_bps) > BPS)
+                    {
+                        return Err(InvalidParameter::InvalidBasisPoints.into());
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+
+        fn apply_change(&mut self, change: ParameterChange) {
+            match change {
+                ParameterChange::MinPrice(min_price) => self.config.min_price = min_price,
+                ParameterChange::EndTime(end_time) => self.config.end_time = end_time,
+                ParameterChange::GracePeriod(blocks) => self.config.grace_period = blocks,
+                ParameterChange::PriceStrategy { strategy, data } => {
+                    self.config.price_strategy = strategy;
+                    self.strategy_data.set(&data);
+                }
+                ParameterChange::PriceOracle {
+                    oracle,
+                    max_rate_age,
+                } => {
+                    self.config.price_oracle = oracle;
+                    self.config.max_rate_age = max_rate_age;
+                }
+                ParameterChange::PaymentFeeTolerance(tolerance_bps) => {
+                    self.config.payment_fee_tolerance_bps = tolerance_bps;
+                }
+                ParameterChange::TickSize(tick_size) => self.config.tick_size = tick_size,
+                ParameterChange::LotSize(lot_size) => self.config.lot_size = lot_size,
+                ParameterChange::ProceedsCap(cap) => self.config.proceeds_cap = cap,
+                ParameterChange::ReleaseRate(rate) => self.config.release_rate = rate,
+                ParameterChange::FinalizeBounty(bounty) => self.finalize_bounty.set(&bounty),
+                ParameterChange::KeeperFee(fee_bps) => self.keeper_fee_bps.set(&fee_bps),
+                ParameterChange::Timelock(blocks) => self.timelock.set(&blocks),
+                ParameterChange::MinPriceIncreaseLimit(limit) => {
+                    self.min_price_increase_limit.set(&limit)
+                }
+                ParameterChange::Phases(phases) => self.phases.set(&phases),
+                ParameterChange::VolumeTiers(tiers) => self.volume_tiers.set(&tiers),
+                ParameterChange::LoyaltyTiers(tiers) => self.loyalty_tiers.set(&tiers),
+                ParameterChange::Donation(donation) => self.donation.set(&donation),
+                ParameterChange::LiquidityConfig(config) => self.liquidity_config.set(&config),
+            }
+        }
+
+        fn check_parameters_unlocked(&self) -> Result<(), Error> {
+            if self.is_locked() {
+                return Err(Error::ParametersLocked);
+            }
+
+            Ok(())
+        }
+
+        fn lock(&mut self) -> Result<(), Error> {
+            if self.locked {
+                return Err(Error::ReentrancyDetected);
+            }
+            self.locked = true;
+
+            Ok(())
+        }
+
+        fn unlock(&mut self) {
+            self.locked = false;
+        }
+
+        fn check_owner(&self, account: AccountId) -> Result<(), Error> {
+            if account != self.auction_owner {
+                return Err(Error::NotAuctionOwner);
+            }
+
+            Ok(())
+        }
+
+        fn check_price_range(start_price: u128, min_price: u128) -> Result<(), Error> {
+            if min_price > start_price {
+                return Err(InvalidParameter::MinPriceAboveStartPrice.into());
+            }
+
+            Ok(())
+        }
+
+        fn check_time_range(start_time: BlockNumber, end_time: BlockNumber) -> Result<(), Error> {
+            if end_time <= start_time {
+                return Err(InvalidParameter::InvalidTimeRange.into());
+            }
+
+            Ok(())
+        }
+
+        /// Returns the largest whole number of lots up to `amount` that can be bought together
+        /// with its bonus, or `amount` if none can.
+        fn fillable_amount(&self, amount: u128) -> u128 {
+            let available = self.purchasable();
+            let round_down = |amount: u128| match self.config.lot_size {
+                0 => amount,
+                lot_size => amount - amount % lot_size,
+            };
+            let mut fill = round_down(amount.min(available));
+            let bonus = self.bonus_for(fill);
+            if fill.saturating_add(bonus) > available {
+                // A smaller purchase never earns a larger bonus.
+                fill = round_down(available.saturating_sub(bonus));
+            }
+            if fill == 0 {
+                return amount;
+            }
+
+            fill
+        }
+
+        fn check_lot_size(&self, amount: u128) -> Result<(), Error> {
+            let lot_size = self.config.lot_size;
+            if lot_size > 0 && !amount.is_multiple_of(lot_size) {
+                return Err(Error::InvalidLotSize);
+            }
+
+            Ok(())
+        }
+
+        fn inventory(&self) -> u128 {
+            if self.config.mint_on_demand {
+                return self
+                    .sale_cap
+                    .saturating_sub(self.total_sold)
+                    .saturating_sub(self.reserved);
+            }
+
+            // Reserved and allocated asset tokens stay in escrow until they are delivered, and
+            // the liquidity asset tokens until `finalize` seeds the pair.
+            self.escrow_balance()
+                .saturating_sub(self.reserved)
+                .saturating_sub(self.allocated)
+                .saturating_sub(self.liquidity_assets())
+        }
+
+        fn liquidity_assets(&self) -> u128 {
+            match self.liquidity_config() {
+                Some(config) if !self.finalized && !self.config.mint_on_demand => {
+                    config.asset_amount
+                }
+                _ => 0,
+            }
+        }
+
+        fn closes_at(&self) -> BlockNumber {
+            if self.config.perpetual.is_some() {
+                return BlockNumber::MAX;
+            }
+            self.config
+                .end_time
+                .saturating_add(self.config.grace_period)
+        }
+
+        fn check_not_finalized(&self) -> Result<(), Error> {
+            if self.finalized {
+                return Err(Error::AuctionEnded);
+            }
+
+            Ok(())
+        }
+
+        fn check_not_paused(&self) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::AuctionPaused);
+            }
+
+            Ok(())
+        }
+
+        fn check_not_blacklisted(&self, account: AccountId) -> Result<(), Error> {
+            if self.is_blacklisted(account) {
+                return Err(Error::Blacklisted);
+            }
+
+            Ok(())
+        }
+
+        fn check_access(&self, allowlisted: bool) -> Result<(), Error> {
+            let restricted = match self.active_phase() {
+                Some((_, phase)) => phase.allowlist_only,
+                None => self.allowlist_root().is_some(),
+            };
+            if restricted && !allowlisted {
+                return Err(Error::NotAllowlisted);
+            }
+
+            Ok(())
+        }
+
+        /// Checks that `buyer` may buy `amount` asset tokens at the current block. Purchases,
+        /// reservations, orders and bid fills all go through here, so none of them bypasses the
+        /// restrictions of the sale.
+        fn check_purchase(
+            &self,
+            buyer: AccountId,
+            amount: u128,
+            allowlisted: bool,
+        ) -> Result<(), Error> {
+            self.check_not_blacklisted(buyer)?;
+            self.check_access(allowlisted)?;
+            if let Some((index, phase)) = self.active_phase() {
+                let bought = self
+                    .phase_purchases
+                    .get((index, buyer))
+                    .unwrap_or_default()
+                    .saturating_add(amount);
+                if phase.max_per_account.is_some_and(|cap| bought > cap) {
+                    return Err(Error::PhaseCapExceeded);
+                }
+            }
+            if let Some((index, round)) = self.current_round() {
+                let bought = self
+                    .round_purchases
+                    .get((index, buyer))
+                    .unwrap_or_default()
+                    .saturating_add(amount);
+                if round.max_per_account.is_some_and(|cap| bought > cap) {
+                    return Err(Error::RoundCapExceeded);
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Has the sale policy approve a sale of `amount` asset tokens plus `bonus` for `total`,
+        /// then counts it against the caps checked by `check_purchase` and the statistics of the
+        /// current round. Called right before recording every purchase, as the policy may record
+        /// it too.
+        fn admit_purchase(
+            &mut self,
+            buyer: AccountId,
+            amount: u128,
+            bonus: u128,
+            total: u128,
+        ) -> Result<(), Error> {
+            if let Some(policy) = self.config.sale_policy {
+                let mut policy: contract_ref!(SalePolicy) = policy.into();
+                policy.check(buyer, amount, total)?;
+            }
+
+            if let Some((index, _)) = self.active_phase() {
+                let bought = self.phase_purchases.get((index, buyer)).unwrap_or_default();
+                self.phase_purchases
+                    .insert((index, buyer), &bought.saturating_add(amount));
+            }
+            if let Some((index, _)) = self.current_round() {
+                let bought = self.round_purchases.get((index, buyer)).unwrap_or_default();
+                self.round_purchases
+                    .insert((index, buyer), &bought.saturating_add(amount));
+                let mut stats = self.round_stats(index);
+                stats.sold = stats.sold.saturating_add(amount.saturating_add(bonus));
+                stats.raised = stats.raised.saturating_add(total);
+                self.round_stats.insert(index, &stats);
+            }
+
+            Ok(())
+        }
+
+        fn update_state(&mut self) {
+            let state = self.state();
+            if state != self.state {
+                let seq = self.next_event_seq();
+                self.env().emit_event(StateChanged {
+                    from: self.state,
+                    to: state,
+                    seq,
+                });
+                self.state = state;
+            }
+        }
+
+        fn check_ended(&self) -> Result<(), Error> {
+            if self.env().block_number() <= self.closes_at()
+                && self.inventory() > 0
+                && !self.cap_reached()
+            {
+                return Err(Error::AuctionNotEnded);
+            }
+
+            Ok(())
+        }
+
+        fn cap_reached(&self) -> bool {
+            self.config.proceeds_cap.is_some_and(|cap| {
+                let next = self.current_price().max(1);
+                self.total_paid().saturating_add(next) > cap
+            })
+        }
+
+        fn set_owner(&mut self, new_owner: AccountId) -> Result<(), OwnableError> {
+            self.check_owner(self.env().caller())
+                .map_err(|_| OwnableError::CallerIsNotOwner)?;
+
+            let previous_owner = self.owner();
+            self.auction_owner = new_owner;
+            let seq = self.next_event_seq();
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner: self.owner(),
+                seq,
+            });
+            self.notify(AuctionNotification::OwnershipTransferred {
+                new_owner: self.owner(),
+            });
+
+            Ok(())
+        }
+    }
+
+    impl DutchAuctionTrait for DutchAuction {
+        #[ink(message)]
+        fn asset_token(&self) -> AccountId {
+            self.config.asset_token
+        }
+
+        #[ink(message)]
+        fn payment_token(&self) -> AccountId {
+            self.config.payment_token
+        }
+
+        #[ink(message)]
+        fn end_time(&self) -> BlockNumber {
+            self.config.end_time
+        }
+
+        #[ink(message)]
+        fn start_block(&self) -> BlockNumber {
+            self.config.start_time
+        }
+
+        #[ink(message)]
+        fn price(&self) -> u128 {
+            self.current_price()
+        }
+
+        #[ink(message)]
+        fn price_at(&self, block: BlockNumber) -> u128 {
+            self.price_at_block(block)
+        }
+
+        #[ink(message)]
+        fn available_asset(&self) -> u128 {
+            let inventory = self.inventory();
+            let Some(rate) = self.config.release_rate else {
+                return inventory;
+            };
+            let elapsed = self
+                .env()
+                .block_number()
+                .saturating_sub(self.config.start_time);
+
+            rate.saturating_mul(elapsed.into())
+                .saturating_sub(self.total_sold)
+                .saturating_sub(self.reserved)
+                .min(inventory)
+        }
+
+        #[ink(message)]
+        fn min_price(&self) -> u128 {
+            self.config.min_price
+        }
+
+        #[ink(message)]
+        fn sold(&self) -> u128 {
+            self.total_sold
+        }
+
+        #[ink(message, payable)]
+        fn buy(
+            &mut self,
+            amount: u128,
+            max_price: Option<Balance>,
+            allow_partial: bool,
+            deliver_to: Option<AccountId>,
+            data: Vec<u8>,
+        ) -> Result<u128, Error> {
+            let amount = if allow_partial {
+                self.fillable_amount(amount)
+            } else {
+                amount
+            };
+            let caller = self.env().caller();
+            let recipient = deliver_to.unwrap_or(caller);
+            let value = self.env().transferred_value();
+            if value == 0 {
+                self.execute_purchase(
+                    caller,
+                    Payer::Account(caller),
+                    &[(recipient, amount)],
+                    amount,
+                    max_price,
+                    0,
+                    false,
+                    &data,
+                )?;
+                return Ok(amount);
+            }
+
+            self.wrap_native(value)?;
+            let max_price = max_price.map_or(value, |max_price| max_price.min(value));
+            let this = self.env().account_id();
+            let paid = self.execute_purchase(
+                caller,
+                Payer::Account(this),
+                &[(recipient, amount)],
+                amount,
+                Some(max_price),
+                0,
+                false,
+                &data,
+            )?;
+            if value > paid {
+                Self::transfer_token(self.payment(), caller, value - paid, &[])?;
+            }
+
+            Ok(amount)
+        }
+    }
+
+    impl Ownable for DutchAuction {
+        #[ink(message)]
+        fn owner(&self) -> Option<AccountId> {
+            if self.auction_owner == AccountId::from(RENOUNCED_OWNER) {
+                return None;
+            }
+
+            Some(self.auction_owner)
+        }
+
+        /// Permanently disables the owner-only messages. Purchases continue and proceeds go to
+        /// the treasury, which is fixed to the renouncing owner if none was set.
+        #[ink(message)]
+        fn renounce_ownership(&mut self) -> Result<(), OwnableError> {
+            let treasury = self.proceeds_recipient();
+            self.set_owner(AccountId::from(RENOUNCED_OWNER))?;
+            self.treasury = Some(treasury);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn transfer_ownership(&mut self, new_owner: Option<AccountId>) -> Result<(), OwnableError> {
+            let new_owner = new_owner.ok_or(OwnableError::NewOwnerIsNotSet)?;
+            self.set_owner(new_owner)
+        }
+    }
+
+    impl PSP22Receiver for DutchAuction {
+        /// Records asset tokens deposited by the auction owner as inventory for sale, and buys
+        /// asset tokens with payment tokens transferred along with an encoded `BuyOrder`.
+        ///
+        /// Only deposits of the asset token coming from the auction owner are accepted. A payment
+        /// token transfer buys `BuyOrder::amount` asset tokens for the sender, without a prior
+        /// approval. The tokens transferred cap the price, the remainder is credited to the
+        /// sender for `withdraw_payment_credit`. As the tokens only arrive once the transfer
+        /// completes, they are held until finalization like cooling-off payments. Failed
+        /// purchases reject the transfer.
+        #[ink(message)]
+        fn before_received(
+            &mut self,
+            _operator: AccountId,
+            from: AccountId,
+            value: u128,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22ReceiverError> {
+            if self.env().caller() == self.config.payment_token {
+                return self
+                    .buy_on_receipt(from, value, &data)
+                    .map_err(|error| PSP22ReceiverError::TransferRejected(format!("{error:?}")));
+            }
+            if self.env().caller() != self.config.asset_token {
+                return Err(PSP22ReceiverError::TransferRejected(String::from(
+                    "Unsupported token",
+                )));
+            }
+            if self.check_owner(from).is_err() {
+                return Err(PSP22ReceiverError::TransferRejected(String::from(
+                    "Only the auction owner can deposit assets",
+                )));
+            }
+
+            // In mint-on-demand mode the cap is fixed by the owner, deposits don't extend it.
+            if !self.config.mint_on_demand {
+                self.sale_cap = self.sale_cap.saturating_add(value);
+            }
+            self.tracked_escrow = self.tracked_escrow.saturating_add(value);
+            let seq = self.next_event_seq();
+            self.env().emit_event(AssetDeposited {
+                from,
+                amount: value,
+                sale_cap: self.sale_cap,
+                seq,
+            });
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// `(start_time, start_price, end_time, min_price)`
+        type Curve = (BlockNumber, u128, BlockNumber, u128);
+
+        /// Mirrors `current_price` for the built-in linear curve.
+        fn price(
+            (start_time, start_price, end_time, min_price): Curve,
+            block: BlockNumber,
+        ) -> u128 {
+            DutchAuction::linear_decrease(
+                start_time.into(),
+                start_price,
+                end_time.into(),
+                min_price,
+                block.into(),
+            )
+            .max(min_price)
+        }
+
+        /// Proposes `change` and executes it, as without a timelock.
+        fn apply(auction: &mut DutchAuction, change: ParameterChange) -> Result<(), Error> {
+            let id = auction.propose_change(change)?;
+            auction.execute_change(id)
+        }
+
+        /// Blocks, biased towards the extremes and short windows.
+        fn blocks() -> impl Strategy<Value = BlockNumber> {
+            prop_oneof![
+                Just(0),
+                Just(BlockNumber::MAX),
+                0..100 as BlockNumber,
+                any::<BlockNumber>(),
+            ]
+        }
+
+        /// Prices, biased towards the extremes.
+        fn prices() -> impl Strategy<Value = u128> {
+            prop_oneof![Just(0), Just(u128::MAX), 0..1_000u128, any::<u128>()]
+        }
+
+        fn curves() -> impl Strategy<Value = Curve> {
+            let window = prop_oneof![
+                (blocks(), blocks()).prop_map(|(start_time, length)| (
+                    start_time,
+                    start_time.saturating_add(length)
+                )),
+                // Degenerate window: `end_time <= start_time`.
+                (blocks(), 0..10 as BlockNumber)
+                    .prop_map(|(start_time, back)| (start_time, start_time.saturating_sub(back))),
+            ];
+            let range = prop_oneof![
+                prices().prop_map(|min_price| (min_price, min_price)),
+                (prices(), prices()),
+                (prices(), prices())
+                    .prop_map(|(min_price, range)| (min_price.saturating_add(range), min_price)),
+            ];
+
+            (window, range).prop_map(|((start_time, end_time), (start_price, min_price))| {
+                (start_time, start_price, end_time, min_price)
+            })
+        }
+
+        fn sample_blocks(curve: Curve) -> [BlockNumber; 8] {
+            let (start_time, _, end_time, _) = curve;
+            let mid = start_time / 2 + end_time / 2;
+            [
+                0,
+                start_time.saturating_sub(1),
+                start_time,
+                start_time.saturating_add(1),
+                mid,
+                end_time.saturating_sub(1),
+                end_time,
+                end_time.saturating_add(1),
+            ]
+        }
+
+        fn tokens() -> (AccountId, AccountId) {
+            (AccountId::from([1; 32]), AccountId::from([2; 32]))
+        }
+
+        fn params(
+            asset_token: AccountId,
+            payment_token: AccountId,
+            start_price: u128,
+            min_price: u128,
+            end_time: BlockNumber,
+        ) -> AuctionParams {
+            AuctionParams {
+                asset_token,
+                payment_token,
+                start_price,
+                min_price,
+                end_time,
+                ..Default::default()
+            }
+        }
+
+        #[ink::test]
+        fn new_rejects_inconsistent_parameters() {
+            let (asset, payment) = tokens();
+
+            assert_eq!(
+                DutchAuction::new(params(asset, payment, 10, 11, 100)).err(),
+                Some(InvalidParameter::MinPriceAboveStartPrice.into())
+            );
+            assert_eq!(
+                DutchAuction::new(params(asset, payment, 10, 1, 0)).err(),
+                Some(InvalidParameter::InvalidTimeRange.into())
+            );
+            assert_eq!(
+                DutchAuction::new(params(asset, asset, 10, 1, 100)).err(),
+                Some(InvalidParameter::IdenticalTokens.into())
+            );
+            assert_eq!(
+                DutchAuction::new(AuctionParams {
+                    end_time: 100,
+                    ..Default::default()
+                })
+                .err(),
+                Some(InvalidParameter::IdenticalTokens.into())
+            );
+            let weights = WeightSchedule {
+                start_asset_weight_bps: 5_000,
+                end_asset_weight_bps: 9_000,
+            };
+            assert_eq!(
+                DutchAuction::new(AuctionParams {
+                    weights: Some(weights),
+                    ..params(asset, payment, 10, 1, 100)
+                })
+                .err(),
+                Some(InvalidParameter::InvalidWeights.into())
+            );
+            assert_eq!(
+                DutchAuction::new(AuctionParams {
+                    payment_fee_tolerance_bps: Some(10_001),
+                    ..params(asset, payment, 10, 1, 100)
+                })
+                .err(),
+                Some(InvalidParameter::InvalidBasisPoints.into())
+            );
+            assert!(DutchAuction::new(params(asset, payment, 10, 10, 1)).is_ok());
+
+            let auction = DutchAuction::new(AuctionParams {
+                tick_size: 5,
+                lot_size: 10,
+                proceeds_cap: Some(1_000),
+                grace_period: 20,
+                ..params(asset, payment, 10, 1, 100)
+            })
+            .unwrap();
+            let config = auction.config();
+            assert_eq!((config.tick_size, config.lot_size), (5, 10));
+            assert_eq!(config.proceeds_cap, Some(1_000));
+            assert_eq!(config.grace_period, 20);
+        }
+
+        #[ink::test]
+        fn setters_keep_parameters_consistent() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+
+            assert_eq!(
+                auction.propose_change(ParameterChange::MinPrice(11)),
+                Err(InvalidParameter::MinPriceAboveStartPrice.into())
+            );
+            assert_eq!(
+                auction.propose_change(ParameterChange::EndTime(0)),
+                Err(InvalidParameter::InvalidTimeRange.into())
+            );
+            assert_eq!(
+                auction.propose_change(ParameterChange::PaymentFeeTolerance(Some(10_001))),
+                Err(InvalidParameter::InvalidBasisPoints.into())
+            );
+            for change in [
+                ParameterChange::MinPrice(10),
+                ParameterChange::EndTime(1),
+                ParameterChange::PaymentFeeTolerance(Some(100)),
+            ] {
+                let id = auction.propose_change(change).unwrap();
+                assert_eq!(auction.execute_change(id), Ok(()));
+            }
+            assert_eq!(auction.min_price(), 10);
+            assert_eq!(auction.end_time(), 1);
+            assert_eq!(auction.config().payment_fee_tolerance_bps, Some(100));
+        }
+
+        #[ink::test]
+        fn changes_wait_for_timelock() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+
+            let id = auction
+                .propose_change(ParameterChange::Timelock(3))
+                .unwrap();
+            assert_eq!(auction.execute_change(id), Ok(()));
+            assert_eq!(auction.timelock(), 3);
+
+            let id = auction
+                .propose_change(ParameterChange::KeeperFee(100))
+                .unwrap();
+            assert_eq!(auction.proposal(id).unwrap().executable_at, 3);
+            for _ in 0..2 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+                assert_eq!(auction.execute_change(id), Err(Error::TimelockActive));
+            }
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(auction.execute_change(id), Ok(()));
+            assert_eq!(auction.keeper_fee_bps(), 100);
+            assert_eq!(
+                auction.propose_change(ParameterChange::KeeperFee(10_001)),
+                Err(InvalidParameter::InvalidBasisPoints.into())
+            );
+
+            // So do changes of how payments are split or discounted.
+            let donation = Donation {
+                recipient: AccountId::from([6; 32]),
+                bps: 100,
+            };
+            let id = auction
+                .propose_change(ParameterChange::Donation(Some(donation)))
+                .unwrap();
+            assert_eq!(auction.execute_change(id), Err(Error::TimelockActive));
+            assert_eq!(auction.donation(), None);
+        }
+
+        #[ink::test]
+        fn locked_parameters_reject_changes() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let id = auction
+                .propose_change(ParameterChange::MinPrice(5))
+                .unwrap();
+
+            assert!(!auction.is_locked());
+            assert_eq!(auction.lock_parameters(), Ok(()));
+            assert!(auction.is_locked());
+            assert_eq!(auction.lock_parameters(), Err(Error::ParametersLocked));
+
+            assert_eq!(auction.execute_change(id), Err(Error::ParametersLocked));
+            assert_eq!(
+                auction.propose_change(ParameterChange::MinPrice(5)),
+                Err(Error::ParametersLocked)
+            );
+            assert_eq!(auction.set_burn_unsold(true), Err(Error::ParametersLocked));
+            assert_eq!(
+                apply(&mut auction, ParameterChange::Phases(Vec::new())),
+                Err(Error::ParametersLocked)
+            );
+            assert_eq!(auction.cancel_change(id), Ok(()));
+            assert_eq!(auction.min_price(), 1);
+        }
+
+        #[ink::test]
+        fn renouncing_fixes_the_treasury() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let owner = auction.auction_owner;
+            let treasury = AccountId::from([8; 32]);
+
+            assert_eq!(auction.proceeds_recipient(), owner);
+            assert_eq!(auction.set_treasury(Some(treasury)), Ok(()));
+            assert_eq!(auction.proceeds_recipient(), treasury);
+            assert_eq!(auction.renounce_ownership(), Ok(()));
+            assert_eq!(auction.owner(), None);
+            assert_eq!(auction.treasury(), Some(treasury));
+            assert_eq!(auction.set_treasury(None), Err(Error::NotAuctionOwner));
+
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            assert_eq!(auction.renounce_ownership(), Ok(()));
+            assert_eq!(auction.proceeds_recipient(), owner);
+        }
+
+        #[ink::test]
+        fn metadata_is_bounded() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let metadata = Metadata {
+                name: String::from("Launch"),
+                terms_uri: String::from("ipfs://terms"),
+                ..Default::default()
+            };
+
+            assert_eq!(auction.set_metadata(metadata.clone()), Ok(()));
+            assert_eq!(auction.metadata(), metadata);
+            assert_eq!(
+                auction.set_metadata(Metadata {
+                    logo_uri: "x".repeat(MAX_URI_LEN + 1),
+                    ..metadata.clone()
+                }),
+                Err(InvalidParameter::MetadataTooLong.into())
+            );
+            assert_eq!(auction.metadata(), metadata);
+        }
+
+        #[ink::test]
+        fn changes_apply_only_once_proposed() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+
+            let id = auction
+                .propose_change(ParameterChange::MinPrice(5))
+                .unwrap();
+            assert_eq!(
+                auction.proposal(id).map(|proposal| proposal.change),
+                Some(ParameterChange::MinPrice(5))
+            );
+            assert_eq!(auction.min_price(), 1);
+
+            assert_eq!(auction.cancel_change(id), Ok(()));
+            assert_eq!(auction.execute_change(id), Err(Error::NoProposal));
+            assert_eq!(auction.min_price(), 1);
+
+            // A proposal valid when made is rejected if the parameters moved since.
+            let id = auction
+                .propose_change(ParameterChange::EndTime(50))
+                .unwrap();
+            auction.config.start_time = 60;
+            assert_eq!(
+                auction.execute_change(id),
+                Err(InvalidParameter::InvalidTimeRange.into())
+            );
+            assert!(auction.proposal(id).is_some());
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([7; 32]));
+            assert_eq!(
+                auction.propose_change(ParameterChange::MinPrice(5)),
+                Err(Error::NotAuctionOwner)
+            );
+        }
+
+        #[ink::test]
+        fn reservations_require_terms() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+
+            assert_eq!(auction.reserve(1), Err(Error::ReservationsDisabled));
+            assert_eq!(
+                auction.set_reservation_terms(10, 10_001),
+                Err(InvalidParameter::InvalidBasisPoints.into())
+            );
+            assert_eq!(auction.set_reservation_terms(10, 500), Ok(()));
+            assert_eq!(auction.reservation_terms(), (10, 500));
+        }
+
+        #[ink::test]
+        fn liquidity_config_is_validated() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let config = LiquidityConfig {
+                router: AccountId::from([9; 32]),
+                proceeds_bps: 10_001,
+                asset_amount: 1_000,
+            };
+
+            assert_eq!(
+                apply(
+                    &mut auction,
+                    ParameterChange::LiquidityConfig(Some(config.clone()))
+                ),
+                Err(InvalidParameter::InvalidBasisPoints.into())
+            );
+            let config = LiquidityConfig {
+                proceeds_bps: 2_000,
+                ..config
+            };
+            assert_eq!(
+                apply(
+                    &mut auction,
+                    ParameterChange::LiquidityConfig(Some(config.clone()))
+                ),
+                Ok(())
+            );
+            assert_eq!(auction.liquidity_config(), Some(config));
+            assert_eq!(auction.liquidity_assets(), 1_000);
+            assert_eq!(auction.seed_liquidity(), Ok(()));
+            auction.finalized = true;
+            assert_eq!(auction.liquidity_assets(), 0);
+        }
+
+        #[ink::test]
+        fn zap_in_requires_router_and_route_to_payment_token() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let token_in = AccountId::from([5; 32]);
+
+            assert_eq!(
+                auction.buy_with_any_token(token_in, 100, 1, vec![token_in, payment]),
+                Err(Error::SwapRouterNotSet)
+            );
+            auction
+                .set_swap_router(Some(AccountId::from([9; 32])))
+                .unwrap();
+            for route in [
+                vec![],
+                vec![payment],
+                vec![token_in, asset],
+                vec![asset, payment],
+            ] {
+                assert_eq!(
+                    auction.buy_with_any_token(token_in, 100, 1, route),
+                    Err(InvalidParameter::InvalidRoute.into())
+                );
+            }
+        }
+
+        #[ink::test]
+        fn listener_registry_is_bounded() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+
+            for byte in 0..MAX_LISTENERS as u8 {
+                assert_eq!(auction.add_listener(AccountId::from([byte; 32])), Ok(()));
+            }
+            assert_eq!(auction.add_listener(AccountId::from([0; 32])), Ok(()));
+            assert_eq!(
+                auction.add_listener(AccountId::from([0xff; 32])),
+                Err(Error::TooManyListeners)
+            );
+            assert_eq!(auction.remove_listener(AccountId::from([0; 32])), Ok(()));
+            assert_eq!(auction.listeners().len(), MAX_LISTENERS - 1);
+            assert_eq!(auction.add_listener(AccountId::from([0xff; 32])), Ok(()));
+        }
+
+        #[ink::test]
+        fn subscriptions_hold_refundable_deposits() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let observer = AccountId::from([5; 32]);
+            auction.set_subscription_deposit(100).unwrap();
+            ink::env::test::set_caller::<Environment>(observer);
+
+            ink::env::test::set_value_transferred::<Environment>(99);
+            assert_eq!(auction.subscribe(), Err(Error::InsufficientDeposit));
+            ink::env::test::set_value_transferred::<Environment>(100);
+            assert_eq!(auction.subscribe(), Ok(()));
+            assert_eq!(auction.subscribe(), Err(Error::AlreadySubscribed));
+            assert_eq!(auction.subscribers(), vec![observer]);
+
+            let before = ink::env::test::get_account_balance::<Environment>(observer).unwrap();
+            assert_eq!(auction.unsubscribe(), Ok(()));
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(observer),
+                Ok(before + 100)
+            );
+            assert_eq!(auction.unsubscribe(), Err(Error::NotSubscribed));
+            assert!(auction.subscribers().is_empty());
+        }
+
+        #[ink::test]
+        fn purchases_are_rejected_while_locked() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+
+            assert_eq!(auction.lock(), Ok(()));
+            assert_eq!(auction.lock(), Err(Error::ReentrancyDetected));
+            assert_eq!(
+                auction.buy(1, None, false, None, vec![]),
+                Err(Error::ReentrancyDetected)
+            );
+            auction.unlock();
+            assert_eq!(auction.lock(), Ok(()));
+        }
+
+        #[ink::test]
+        fn owner_deposits_are_tracked() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let owner = auction.auction_owner;
+            let stranger = AccountId::from([5; 32]);
+            ink::env::test::set_caller::<Environment>(asset);
+
+            assert_eq!(auction.before_received(owner, owner, 100, vec![]), Ok(()));
+            assert!(auction
+                .before_received(stranger, stranger, 50, vec![])
+                .is_err());
+            assert_eq!(auction.before_received(owner, owner, 20, vec![]), Ok(()));
+            assert_eq!(auction.sale_cap(), 120);
+            assert_eq!(auction.tracked_escrow, 120);
+        }
+
+        #[ink::test]
+        fn all_or_nothing_sale_settles_on_goal() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 1)).unwrap();
+            let this = ink::env::account_id::<Environment>();
+            let buyer = AccountId::from([5; 32]);
+            assert_eq!(auction.set_raise_goal(Some(100)), Ok(()));
+
+            auction.escrow_purchase(this, buyer, 60, &[]).unwrap();
+            auction.allocate(buyer, 6);
+            auction.escrow_purchase(this, buyer, 30, &[]).unwrap();
+            auction.allocate(buyer, 3);
+            assert_eq!(auction.contribution_of(buyer), 90);
+            assert_eq!(auction.allocation_of(buyer), 9);
+            auction.total_sold = 9;
+            assert_eq!(auction.set_raise_goal(None), Err(Error::SaleAlreadyStarted));
+            assert_eq!(auction.terminate(false), Err(Error::FundsInEscrow));
+
+            ink::env::test::advance_block::<Environment>();
+            ink::env::test::advance_block::<Environment>();
+            assert_eq!(auction.goal_reached(), Ok(false));
+            assert_eq!(auction.withdraw_raise(), Err(Error::GoalNotReached));
+            assert_eq!(auction.claim_assets(), Err(Error::GoalNotReached));
+            auction.total_raised = 100;
+            assert_eq!(auction.goal_reached(), Ok(true));
+            assert_eq!(auction.claim_refund(), Err(Error::GoalReached));
+        }
+
+        #[ink::test]
+        fn refunds_respect_cooling_off_window() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = AccountId::from([5; 32]);
+
+            assert_eq!(auction.refund(0), Err(Error::RefundWindowClosed));
+            assert_eq!(
+                auction.set_cooling_off(2, 10_001),
+                Err(InvalidParameter::InvalidBasisPoints.into())
+            );
+            assert_eq!(auction.set_cooling_off(2, 500), Ok(()));
+            auction.record_purchase(buyer, 1, 0, 10, false, &[]);
+            assert_eq!(
+                auction.set_cooling_off(0, 0),
+                Err(Error::SaleAlreadyStarted)
+            );
+
+            // Purchase IDs are contract-wide, only the buyer can refund theirs.
+            auction.record_purchase(AccountId::from([6; 32]), 1, 0, 10, false, &[]);
+            assert_eq!(auction.refund(0), Err(Error::NoPurchase));
+            ink::env::test::set_caller::<Environment>(buyer);
+            assert_eq!(auction.refund(1), Err(Error::NoPurchase));
+            for _ in 0..3 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            assert_eq!(auction.refund(0), Err(Error::RefundWindowClosed));
+        }
+
+        #[ink::test]
+        fn allocated_purchases_are_refunded_from_the_allocation() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let (buyer, other) = (AccountId::from([5; 32]), AccountId::from([6; 32]));
+            auction.set_deferred_delivery(true).unwrap();
+            // The whole price is kept, so no payment tokens are returned.
+            auction.set_cooling_off(2, 10_000).unwrap();
+            auction.record_purchase(buyer, 6, 0, 60, true, &[]);
+            auction.allocate(buyer, 6);
+            auction.record_purchase(buyer, 4, 0, 40, true, &[]);
+            auction.allocate(buyer, 4);
+
+            ink::env::test::set_caller::<Environment>(buyer);
+            assert_eq!(auction.transfer_allocation(other, 5), Ok(()));
+            assert_eq!(auction.refund(0), Err(Error::InsufficientAllocation));
+            assert_eq!(auction.refund(1), Ok(()));
+            assert_eq!(auction.allocation_of(buyer), 1);
+            assert_eq!(auction.allocated, 6);
+            assert_eq!(auction.sold(), 6);
+            assert!(auction.purchase(1).unwrap().1.refunded);
+        }
+
+        #[ink::test]
+        fn refundable_purchases_are_delivered_to_the_buyer() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let other = AccountId::from([6; 32]);
+            auction.set_cooling_off(2, 500).unwrap();
+
+            assert_eq!(
+                auction.buy(1, None, false, Some(other), vec![]),
+                Err(Error::DeliveryNotRefundable)
+            );
+            assert_eq!(
+                auction.buy_split(2, vec![(other, 1), (AccountId::from([1; 32]), 1)]),
+                Err(Error::DeliveryNotRefundable)
+            );
+        }
+
+        #[test]
+        fn oversubscribed_orders_fill_pro_rata() {
+            let order = Order {
+                amount: 30,
+                paid: 300,
+            };
+
+            assert_eq!(DutchAuction::pro_rata(&order, 100, 60), (30, 300));
+            assert_eq!(DutchAuction::pro_rata(&order, 100, 300), (10, 100));
+            assert_eq!(DutchAuction::pro_rata(&order, 0, 300), (0, 0));
+            let order = Order {
+                amount: 7,
+                paid: 100,
+            };
+            assert_eq!(DutchAuction::pro_rata(&order, 10, 21), (3, 42));
+        }
+
+        #[ink::test]
+        fn orders_replace_immediate_purchases() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            assert_eq!(auction.place_order(1), Err(Error::NoOrder));
+
+            assert_eq!(auction.set_order_collection(true), Ok(()));
+            assert_eq!(
+                auction.buy(1, None, false, None, vec![]),
+                Err(Error::OrderCollectionActive)
+            );
+            assert_eq!(auction.place_order(0), Err(Error::ZeroAmount));
+            assert_eq!(
+                auction.settle_order(AccountId::from([5; 32])),
+                Err(Error::AuctionNotEnded)
+            );
+        }
+
+        #[ink::test]
+        fn bids_are_bounded_and_cancellable_by_bidder() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let bidder = AccountId::from([5; 32]);
+            let bid = Bid {
+                bidder,
+                amount: 1,
+                limit_price: 5,
+                escrow: 5,
+            };
+            auction.bids.insert(7, &bid);
+            auction.open_bids.set(&vec![3, 7]);
+
+            assert_eq!(auction.place_bid(0, 5), Err(Error::ZeroAmount));
+            assert_eq!(auction.terminate(false), Err(Error::FundsInEscrow));
+            assert_eq!(auction.cancel_bid(7), Err(Error::NoBid));
+            auction.close_bid(7);
+            assert_eq!(auction.open_bids(), vec![3]);
+            assert_eq!(auction.bid(7), None);
+
+            auction.open_bids.set(&(0..MAX_OPEN_BIDS as u32).collect());
+            assert_eq!(auction.place_bid(1, 5), Err(Error::TooManyBids));
+        }
+
+        #[ink::test]
+        fn prices_round_up_to_tick() {
+            assert_eq!(DutchAuction::round_to_tick(1234, 0), 1234);
+            assert_eq!(DutchAuction::round_to_tick(1234, 1), 1234);
+            assert_eq!(DutchAuction::round_to_tick(1234, 100), 1300);
+            assert_eq!(DutchAuction::round_to_tick(1200, 100), 1200);
+            assert_eq!(DutchAuction::round_to_tick(0, 100), 0);
+            assert_eq!(DutchAuction::round_to_tick(u128::MAX, 10), u128::MAX - 5);
+
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 1000, 1, 100)).unwrap();
+            let id = auction
+                .propose_change(ParameterChange::TickSize(250))
+                .unwrap();
+            auction.execute_change(id).unwrap();
+            // 703 on the curve, charged as the next tick up.
+            assert_eq!(auction.price_at(33), 750);
+            assert_eq!(auction.price_at(100), 250);
+            assert_eq!(auction.blocks_until_price(500), Some(56));
+        }
+
+        #[ink::test]
+        fn purchases_are_whole_lots() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let id = auction
+                .propose_change(ParameterChange::LotSize(1_000))
+                .unwrap();
+            auction.execute_change(id).unwrap();
+
+            assert_eq!(
+                auction.buy(1_500, None, false, None, vec![]),
+                Err(Error::InvalidLotSize)
+            );
+            assert_eq!(auction.place_bid(999, 10), Err(Error::InvalidLotSize));
+            assert_eq!(auction.check_lot_size(3_000), Ok(()));
+
+            auction.total_sold = 3_500;
+            assert_eq!(auction.lots_sold(), 3);
+            assert_eq!(
+                auction.propose_change(ParameterChange::LotSize(10)),
+                Err(Error::SaleAlreadyStarted)
+            );
+        }
+
+        #[ink::test]
+        fn mint_on_demand_is_bounded_by_sale_cap() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+
+            assert_eq!(auction.set_mint_on_demand(Some(1_000)), Ok(()));
+            assert!(auction.config().mint_on_demand);
+            assert_eq!(auction.sale_cap(), 1_000);
+            assert_eq!(auction.available_asset(), 1_000);
+
+            auction.total_sold = 300;
+            auction.reserved = 100;
+            assert_eq!(auction.available_asset(), 600);
+            assert_eq!(
+                auction.set_mint_on_demand(None),
+                Err(Error::SaleAlreadyStarted)
+            );
+        }
+
+        #[ink::test]
+        fn burning_proceeds_is_fixed_at_construction() {
+            let (asset, payment) = tokens();
+            let auction = DutchAuction::new(AuctionParams {
+                burn_proceeds: true,
+                ..params(asset, payment, 10, 1, 100)
+            })
+            .unwrap();
+            assert!(auction.config().burn_proceeds);
+            assert!(
+                !DutchAuction::new(params(asset, payment, 10, 1, 100))
+                    .unwrap()
+                    .config()
+                    .burn_proceeds
+            );
+            assert_eq!(
+                DutchAuction::new(AuctionParams {
+                    burn_proceeds: true,
+                    ..params(asset, payment, 10, 11, 100)
+                })
+                .err(),
+                Some(InvalidParameter::MinPriceAboveStartPrice.into())
+            );
+        }
+
+        #[ink::test]
+        fn donation_share_is_validated() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let donation = Donation {
+                recipient: AccountId::from([6; 32]),
+                bps: 250,
+            };
+
+            assert_eq!(auction.donation(), None);
+            assert_eq!(
+                apply(
+                    &mut auction,
+                    ParameterChange::Donation(Some(Donation {
+                        bps: 10_001,
+                        ..donation.clone()
+                    }))
+                ),
+                Err(InvalidParameter::InvalidBasisPoints.into())
+            );
+            assert_eq!(
+                apply(
+                    &mut auction,
+                    ParameterChange::Donation(Some(donation.clone()))
+                ),
+                Ok(())
+            );
+            assert_eq!(auction.donation(), Some(donation));
+            assert_eq!(apply(&mut auction, ParameterChange::Donation(None)), Ok(()));
+            assert_eq!(auction.donation(), None);
+        }
+
+        #[ink::test]
+        fn proceeds_cap_ends_the_auction() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let id = auction
+                .propose_change(ParameterChange::ProceedsCap(Some(100)))
+                .unwrap();
+            auction.execute_change(id).unwrap();
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+
+            auction.record_purchase(AccountId::from([5; 32]), 9, 0, 90, false, &[]);
+            assert_eq!(auction.total_paid(), 90);
+            assert_eq!(
+                auction.buy(2, None, false, None, vec![]),
+                Err(Error::CapReached)
+            );
+            assert_eq!(auction.check_ended(), Err(Error::AuctionNotEnded));
+
+            auction.record_purchase(AccountId::from([5; 32]), 1, 0, 5, false, &[]);
+            assert!(auction.cap_reached());
+            assert_eq!(auction.check_ended(), Ok(()));
+        }
+
+        #[ink::test]
+        fn anyone_can_finalize_for_a_bounty() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let id = auction
+                .propose_change(ParameterChange::FinalizeBounty(50))
+                .unwrap();
+            auction.execute_change(id).unwrap();
+            auction.set_mint_on_demand(Some(10)).unwrap();
+            assert_eq!(auction.finalize_bounty(), 50);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([7; 32]));
+            assert_eq!(auction.finalize(), Err(Error::AuctionNotEnded));
+        }
+
+        #[ink::test]
+        fn state_follows_the_auction_lifecycle() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(10)).unwrap();
+            assert_eq!(auction.state(), AuctionState::Active);
+
+            assert_eq!(auction.pause(), Ok(()));
+            assert_eq!(auction.state, AuctionState::Paused);
+            assert_eq!(
+                auction.buy(1, None, false, None, vec![]),
+                Err(Error::AuctionPaused)
+            );
+            assert_eq!(auction.place_bid(1, 10), Err(Error::AuctionPaused));
+            assert_eq!(auction.unpause(), Ok(()));
+            assert_eq!(auction.state, AuctionState::Active);
+
+            auction.total_sold = 10;
+            assert_eq!(auction.state(), AuctionState::SoldOut);
+            assert_eq!(auction.cancel(), Err(Error::SaleAlreadyStarted));
+            auction.total_sold = 0;
+
+            auction.config.end_time = 0;
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(auction.state(), AuctionState::Ended);
+            assert_eq!(auction.quote(1).err(), Some(Error::AuctionEnded));
+        }
+
+        #[ink::test]
+        fn simulation_breaks_down_the_payment() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = AccountId::from([1; 32]);
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+
+            assert_eq!(
+                auction.simulate_buy(buyer, 0),
+                BuySimulation {
+                    error: Some(Error::ZeroAmount),
+                    ..Default::default()
+                }
+            );
+
+            apply(
+                &mut auction,
+                ParameterChange::Donation(Some(Donation {
+                    recipient: AccountId::from([6; 32]),
+                    bps: 250,
+                })),
+            )
+            .unwrap();
+            apply(
+                &mut auction,
+                ParameterChange::Phases(vec![Phase {
+                    start_block: 0,
+                    allowlist_only: false,
+                    max_per_account: Some(5),
+                    discount_bps: 1_000,
+                }]),
+            )
+            .unwrap();
+            assert_eq!(
+                auction.simulate_buy(buyer, 10),
+                BuySimulation {
+                    unit_price: 10,
+                    total: 90,
+                    discount: 10,
+                    donation: 2,
+                    proceeds: 88,
+                    error: Some(Error::PhaseCapExceeded),
+                    ..Default::default()
+                }
+            );
+        }
+
+        #[ink::test]
+        fn partial_fills_are_bounded_by_supply_and_bonus() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+            auction.total_sold = 950;
+
+            assert_eq!(auction.fillable_amount(30), 30);
+            assert_eq!(auction.fillable_amount(100), 50);
+            auction
+                .set_bonus_tiers(vec![BonusTier {
+                    min_amount: 10,
+                    bonus_bps: 1_000,
+                    before_block: None,
+                }])
+                .unwrap();
+            assert_eq!(auction.fillable_amount(100), 45);
+
+            auction.config.lot_size = 20;
+            assert_eq!(auction.fillable_amount(100), 40);
+            auction.total_sold = 990;
+            assert_eq!(auction.fillable_amount(100), 100);
+            assert_eq!(
+                auction.buy(100, None, true, None, vec![]),
+                Err(Error::InsufficientSupplyToken)
+            );
+        }
+
+        #[ink::test]
+        fn rounds_replace_the_curve_and_bound_supply() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+            let round = |start_block, start_price, min_price, supply| Round {
+                start_block,
+                start_price,
+                anchor_bps: None,
+                min_price,
+                supply,
+                max_per_account: None,
+            };
+
+            assert_eq!(
+                auction.set_rounds(vec![round(5, 1_000, 500, 100), round(5, 100, 10, 900)]),
+                Err(InvalidParameter::UnorderedRounds.into())
+            );
+            assert_eq!(
+                auction.set_rounds(vec![round(5, 1_000, 500, 100), round(100, 100, 10, 900)]),
+                Err(InvalidParameter::UnorderedRounds.into())
+            );
+            assert_eq!(
+                auction.set_rounds(vec![round(5, 500, 1_000, 100)]),
+                Err(InvalidParameter::MinPriceAboveStartPrice.into())
+            );
+            assert_eq!(
+                auction.set_rounds(vec![round(5, 1_000, 500, 100), round(55, 100, 10, 900)]),
+                Ok(())
+            );
+
+            assert_eq!(auction.current_round(), None);
+            assert_eq!(auction.quote(1).err(), Some(Error::AuctionNotStarted));
+            assert_eq!(auction.reference_price_at(5), 1_000);
+            assert_eq!(auction.reference_price_at(30), 750);
+            assert_eq!(auction.reference_price_at(55), 100);
+            assert_eq!(auction.reference_price_at(100), 10);
+
+            for _ in 0..5 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            assert_eq!(
+                auction.current_round(),
+                Some((0, round(5, 1_000, 500, 100)))
+            );
+            assert_eq!(auction.quote(100).map(|quote| quote.total), Ok(100_000));
+            assert_eq!(
+                auction.quote(101).err(),
+                Some(Error::InsufficientSupplyToken)
+            );
+            assert_eq!(auction.round_stats(0), RoundStats::default());
+        }
+
+        #[ink::test]
+        fn deferred_allocations_wait_for_open_claims() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = AccountId::from([5; 32]);
+
+            assert_eq!(auction.set_deferred_delivery(true), Ok(()));
+            assert!(auction.config().deferred_delivery);
+            assert_eq!(auction.claim_assets(), Err(Error::ClaimsNotOpen));
+            auction.allocate(buyer, 10);
+            assert_eq!(auction.allocation_of(buyer), 10);
+            auction.total_sold = 10;
+            assert_eq!(
+                auction.set_deferred_delivery(false),
+                Err(Error::SaleAlreadyStarted)
+            );
+
+            assert_eq!(auction.open_claims(), Ok(()));
+            assert!(auction.claims_open());
+
+            ink::env::test::set_caller::<Environment>(buyer);
+            assert_eq!(auction.open_claims(), Err(Error::NotAuctionOwner));
+        }
+
+        #[ink::test]
+        fn allocations_transfer_between_buyers() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let (buyer, other) = (AccountId::from([5; 32]), AccountId::from([6; 32]));
+
+            assert_eq!(
+                auction.transfer_allocation(other, 1),
+                Err(Error::AllocationsNotTransferable)
+            );
+            auction.set_deferred_delivery(true).unwrap();
+            auction.allocate(buyer, 10);
+
+            ink::env::test::set_caller::<Environment>(buyer);
+            assert_eq!(
+                auction.transfer_allocation(other, 11),
+                Err(Error::InsufficientAllocation)
+            );
+            assert_eq!(auction.transfer_allocation(other, 4), Ok(()));
+            assert_eq!(auction.transfer_allocation(other, 6), Ok(()));
+            assert_eq!(auction.allocation_of(buyer), 0);
+            assert_eq!(auction.allocation_of(other), 10);
+            assert_eq!(auction.allocated, 10);
+
+            ink::env::test::set_caller::<Environment>(AccountId::from([1; 32]));
+            assert_eq!(auction.set_allocation_transfers(false), Ok(()));
+            ink::env::test::set_caller::<Environment>(other);
+            assert_eq!(
+                auction.transfer_allocation(buyer, 1),
+                Err(Error::AllocationsNotTransferable)
+            );
+        }
+
+        #[ink::test]
+        fn flip_restriction_delays_claims_to_the_next_block() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let (buyer, other) = (AccountId::from([5; 32]), AccountId::from([6; 32]));
+            auction.set_deferred_delivery(true).unwrap();
+            auction.open_claims().unwrap();
+            assert!(!auction.flip_restricted());
+            assert_eq!(auction.set_flip_restriction(true), Ok(()));
+            auction.allocate(buyer, 10);
+
+            ink::env::test::set_caller::<Environment>(buyer);
+            assert_eq!(
+                auction.set_flip_restriction(false),
+                Err(Error::NotAuctionOwner)
+            );
+            assert_eq!(auction.claim_assets(), Err(Error::SameBlockClaim));
+            // Moving the allocation doesn't get around the restriction.
+            assert_eq!(auction.transfer_allocation(other, 4), Ok(()));
+            ink::env::test::set_caller::<Environment>(other);
+            assert_eq!(auction.claim_assets(), Err(Error::SameBlockClaim));
+
+            // The restriction only holds for the block of the purchase.
+            ink::env::test::advance_block::<Environment>();
+            assert_eq!(auction.allocation_blocks.get(other), Some(0));
+        }
+
+        #[ink::test]
+        fn buyers_are_recorded_once_with_totals() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let (first, second) = (AccountId::from([5; 32]), AccountId::from([6; 32]));
+
+            auction.record_purchase(first, 2, 1, 20, false, &[]);
+            ink::env::test::advance_block::<Environment>();
+            auction.record_purchase(second, 1, 0, 9, false, &[]);
+            auction.record_purchase(first, 3, 0, 27, false, &[]);
+
+            assert_eq!(auction.buyer_count(), 2);
+            assert_eq!(
+                auction.buyer_totals_of(first),
+                BuyerTotals {
+                    first_block: 0,
+                    bought: 6,
+                    paid: 47,
+                }
+            );
+            assert_eq!(
+                auction.buyers(1, 5),
+                vec![(
+                    second,
+                    BuyerTotals {
+                        first_block: 1,
+                        bought: 1,
+                        paid: 9,
+                    }
+                )]
+            );
+            assert_eq!(auction.buyers(2, 5), Vec::new());
+        }
+
+        #[ink::test]
+        fn grace_period_admits_allowlisted_buyers_at_min_price() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+            let id = auction
+                .propose_change(ParameterChange::GracePeriod(2))
+                .unwrap();
+            auction.execute_change(id).unwrap();
+            auction.config.end_time = 1;
+            auction.config.min_price = 4;
+
+            ink::env::test::advance_block::<Environment>();
+            ink::env::test::advance_block::<Environment>();
+            assert_eq!(auction.quote(1).err(), Some(Error::AuctionEnded));
+            assert_eq!(auction.quote_for(5, true).map(|quote| quote.total), Ok(20));
+            assert_eq!(auction.state(), AuctionState::Active);
+            assert_eq!(auction.check_ended(), Err(Error::AuctionNotEnded));
+
+            ink::env::test::advance_block::<Environment>();
+            ink::env::test::advance_block::<Environment>();
+            assert_eq!(auction.quote_for(5, true).err(), Some(Error::AuctionEnded));
+            assert_eq!(auction.state(), AuctionState::Ended);
+            assert_eq!(auction.check_ended(), Ok(()));
+        }
+
+        #[ink::test]
+        fn leaderboard_ranks_largest_buyers() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = |seed: u8| AccountId::from([seed; 32]);
+
+            for seed in 10..22 {
+                auction.record_purchase(buyer(seed), seed.into(), 0, 1, false, &[]);
+            }
+            let leaderboard = auction.leaderboard();
+            assert_eq!(leaderboard.len(), 10);
+            assert_eq!(leaderboard[0], (buyer(21), 21));
+            assert_eq!(leaderboard[9], (buyer(12), 12));
+
+            auction.record_purchase(buyer(10), 5, 0, 1, false, &[]);
+            auction.record_purchase(buyer(12), 4, 0, 1, false, &[]);
+            let leaderboard = auction.leaderboard();
+            assert_eq!(leaderboard[5], (buyer(16), 16));
+            assert_eq!(leaderboard[6], (buyer(12), 16));
+            assert_eq!(leaderboard[8], (buyer(10), 15));
+            assert_eq!(leaderboard[9], (buyer(14), 14));
+
+            assert_eq!(
+                auction.set_leaderboard(false),
+                Err(Error::SaleAlreadyStarted)
+            );
+            auction.config.track_leaderboard = false;
+            auction.record_purchase(buyer(30), 100, 0, 1, false, &[]);
+            assert_eq!(auction.leaderboard()[0], (buyer(21), 21));
+        }
+
+        #[ink::test]
+        fn returning_buyers_get_loyalty_discount() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 1_000, 1, 100)).unwrap();
+            let buyer = AccountId::from([5; 32]);
+            let tier = |min_bought, discount_bps| LoyaltyTier {
+                min_bought,
+                discount_bps,
+            };
+
+            assert_eq!(
+                apply(
+                    &mut auction,
+                    ParameterChange::LoyaltyTiers(vec![tier(1, 10_001)])
+                ),
+                Err(InvalidParameter::InvalidBasisPoints.into())
+            );
+            assert_eq!(
+                apply(
+                    &mut auction,
+                    ParameterChange::LoyaltyTiers(vec![tier(10, 500), tier(100, 1_000)])
+                ),
+                Ok(())
+            );
+            assert_eq!(auction.price_for(buyer), 1_000);
+
+            auction.record_purchase(buyer, 10, 0, 1, false, &[]);
+            assert_eq!(auction.loyalty_discount_bps(buyer), 500);
+            assert_eq!(auction.price_for(buyer), 950);
+            auction.record_purchase(buyer, 90, 0, 1, false, &[]);
+            assert_eq!(auction.price_for(buyer), 900);
+            assert_eq!(auction.price_for(AccountId::from([6; 32])), 1_000);
+        }
+
+        #[ink::test]
+        fn volume_discount_is_part_of_the_quote() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(1_000_000)).unwrap();
+            let tier = |min_amount, discount_bps| VolumeTier {
+                min_amount,
+                discount_bps,
+            };
+
+            assert_eq!(
+                apply(
+                    &mut auction,
+                    ParameterChange::VolumeTiers(vec![tier(1, 10_001)])
+                ),
+                Err(InvalidParameter::InvalidBasisPoints.into())
+            );
+            assert_eq!(
+                apply(
+                    &mut auction,
+                    ParameterChange::VolumeTiers(vec![tier(10_000, 200), tier(100_000, 500)])
+                ),
+                Ok(())
+            );
+            assert_eq!(auction.price_for_amount(9_999), 99_990);
+            assert_eq!(auction.price_for_amount(10_000), 98_000);
+            assert_eq!(auction.price_for_amount(100_000), 950_000);
+            assert_eq!(
+                auction.quote(100_000).map(|quote| quote.total),
+                Ok(auction.price_for_amount(100_000))
+            );
+        }
+
+        #[ink::test]
+        fn anchored_round_starts_from_last_sale() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let round = |start_block, anchor_bps| Round {
+                start_block,
+                start_price: 1_000,
+                anchor_bps,
+                min_price: 100,
+                supply: 1_000,
+                max_per_account: None,
+            };
+            auction
+                .set_rounds(vec![round(0, None), round(50, Some(12_000))])
+                .unwrap();
+
+            assert_eq!(auction.last_sale_price(), 0);
+            assert_eq!(auction.reference_price_at(50), 1_000);
+            auction.record_purchase(AccountId::from([5; 32]), 2, 0, 1_000, false, &[]);
+            assert_eq!(auction.last_sale_price(), 500);
+            assert_eq!(auction.reference_price_at(50), 600);
+
+            for _ in 0..50 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            auction.record_purchase(AccountId::from([5; 32]), 1, 0, 50, false, &[]);
+            assert_eq!(auction.last_sale_price(), 50);
+            assert_eq!(auction.round_start_price(1, &round(50, Some(12_000))), 600);
+            assert_eq!(auction.reference_price_at(50), 600);
+        }
+
+        #[ink::test]
+        fn perpetual_auction_restarts_tranche_by_tranche() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 1_000, 100, 100)).unwrap();
+            auction.set_mint_on_demand(Some(1_000_000)).unwrap();
+            let perpetual = Perpetual {
+                tranche_size: 10,
+                duration: 10,
+                restart_bps: 15_000,
+            };
+            assert_eq!(
+                auction.set_perpetual(Some(Perpetual {
+                    tranche_size: 0,
+                    ..perpetual.clone()
+                })),
+                Err(InvalidParameter::InvalidTranche.into())
+            );
+            assert_eq!(auction.set_perpetual(Some(perpetual)), Ok(()));
+            assert_eq!(auction.reference_price_at(5), 550);
+            assert_eq!(
+                auction.quote(11).err(),
+                Some(Error::InsufficientSupplyToken)
+            );
+
+            for _ in 0..25 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            let tranche = auction.current_tranche().unwrap();
+            assert_eq!((tranche.index, tranche.start_block), (2, 20));
+            assert_eq!(auction.current_price(), 550);
+
+            auction.record_purchase(AccountId::from([5; 32]), 10, 0, 5_000, false, &[]);
+            let tranche = auction.current_tranche().unwrap();
+            assert_eq!((tranche.index, tranche.start_block), (3, 25));
+            assert_eq!(tranche.start_price, 750);
+            assert_eq!(auction.current_price(), 750);
+
+            for _ in 0..200 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            assert_eq!(auction.quote(10).map(|quote| quote.total), Ok(7_500));
+            assert_eq!(auction.check_ended(), Err(Error::AuctionNotEnded));
+        }
+
+        #[ink::test]
+        fn release_rate_caps_available_supply() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+            let id = auction
+                .propose_change(ParameterChange::ReleaseRate(Some(100)))
+                .unwrap();
+            auction.execute_change(id).unwrap();
+
+            assert_eq!(auction.available_asset(), 0);
+            assert_eq!(auction.state(), AuctionState::Active);
+            for _ in 0..3 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            assert_eq!(auction.available_asset(), 300);
+            auction.total_sold = 250;
+            assert_eq!(auction.available_asset(), 50);
+            assert_eq!(auction.sold_percentage(), 25);
+
+            for _ in 0..20 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            assert_eq!(auction.available_asset(), 750);
+        }
+
+        #[test]
+        fn failed_token_calls_are_errors() {
+            assert_eq!(DutchAuction::token_call::<u128>(Ok(Ok(5))), Ok(5));
+            assert_eq!(
+                DutchAuction::token_call::<u128>(Ok(Err(ink::LangError::CouldNotReadInput))),
+                Err(Error::TokenCallFailed {
+                    lang_error: Some(ink::LangError::CouldNotReadInput)
+                })
+            );
+            assert_eq!(
+                DutchAuction::token_call::<u128>(Err(ink::env::Error::CalleeTrapped)),
+                Err(Error::TokenCallFailed { lang_error: None })
+            );
+        }
+
+        #[ink::test]
+        fn native_value_requires_wrapped_payment_token() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            ink::env::test::set_value_transferred::<Environment>(100);
+            assert_eq!(
+                auction.buy(1, None, false, None, vec![]),
+                Err(Error::NativePaymentNotAccepted)
+            );
+
+            ink::env::test::set_caller::<Environment>(AccountId::from([5; 32]));
+            assert_eq!(
+                auction.set_wrapped_native(true),
+                Err(Error::NotAuctionOwner)
+            );
+            ink::env::test::set_caller::<Environment>(AccountId::from([1; 32]));
+            assert_eq!(auction.set_wrapped_native(true), Ok(()));
+            assert!(auction.config().wrapped_native);
+        }
+
+        #[ink::test]
+        fn operators_buy_within_their_allowance() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let principal = AccountId::from([5; 32]);
+            let operator = AccountId::from([6; 32]);
+            ink::env::test::set_caller::<Environment>(principal);
+            assert_eq!(auction.approve_operator(operator, 3), Ok(()));
+            assert_eq!(auction.operator_allowance(principal, operator), 3);
+            assert_eq!(auction.operator_allowance(operator, principal), 0);
+
+            ink::env::test::set_caller::<Environment>(operator);
+            assert_eq!(
+                auction.buy_as(principal, 4, None),
+                Err(Error::OperatorAllowanceExceeded)
+            );
+
+            ink::env::test::set_caller::<Environment>(principal);
+            assert_eq!(auction.approve_operator(operator, 0), Ok(()));
+            assert_eq!(auction.operator_allowance(principal, operator), 0);
+            ink::env::test::set_caller::<Environment>(operator);
+            assert_eq!(
+                auction.buy_as(principal, 1, None),
+                Err(Error::OperatorAllowanceExceeded)
+            );
+        }
+
+        #[ink::test]
+        fn relayed_purchases_require_forwarder_signature_and_nonce() {
+            use secp256k1::{Message, PublicKey, SecretKey, SECP256K1};
+
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let key = SecretKey::from_slice(&[7; 32]).unwrap();
+            let sign = |hash: [u8; 32]| {
+                let (id, bytes) = SECP256K1
+                    .sign_ecdsa_recoverable(&Message::from_slice(&hash).unwrap(), &key)
+                    .serialize_compact();
+                let mut signature = [0; 65];
+                signature[..64].copy_from_slice(&bytes);
+                signature[64] = id.to_i32() as u8;
+                signature
+            };
+            let mut buyer = [0; 32];
+            ink::env::hash_bytes::<ink::env::hash::Blake2x256>(
+                &PublicKey::from_secret_key(SECP256K1, &key).serialize(),
+                &mut buyer,
+            );
+            let request = RelayedBuy {
+                buyer: AccountId::from(buyer),
+                amount: 1,
+                max_price: None,
+                nonce: 0,
+                deadline: 10,
+            };
+            let signature = sign(auction.relayed_buy_hash(request.clone()));
+
+            assert_eq!(
+                auction.verify_relayed(&request, signature),
+                Err(Error::NotTrustedForwarder)
+            );
+            let forwarder = AccountId::from([6; 32]);
+            auction.set_trusted_forwarder(Some(forwarder)).unwrap();
+            ink::env::test::set_caller::<Environment>(forwarder);
+            let forged = RelayedBuy {
+                amount: 2,
+                ..request.clone()
+            };
+            assert_eq!(
+                auction.verify_relayed(&forged, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(auction.verify_relayed(&request, signature), Ok(()));
+            assert_eq!(auction.relay_nonce(request.buyer), 1);
+            assert_eq!(
+                auction.verify_relayed(&request, signature),
+                Err(Error::InvalidNonce)
+            );
+
+            let request = RelayedBuy {
+                nonce: 1,
+                ..request
+            };
+            for _ in 0..11 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            assert_eq!(
+                auction.verify_relayed(&request, sign(auction.relayed_buy_hash(request.clone()))),
+                Err(Error::RequestExpired)
+            );
+        }
+
+        #[ink::test]
+        fn constructors_emit_full_configuration() {
+            let (asset, payment) = tokens();
+            let weights = WeightSchedule {
+                start_asset_weight_bps: 9_000,
+                end_asset_weight_bps: 5_000,
+            };
+            let auction = DutchAuction::new(AuctionParams {
+                weights: Some(weights),
+                ..params(asset, payment, 10, 1, 100)
+            })
+            .unwrap();
+
+            let events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(events.len(), 1);
+            type Event = <DutchAuction as ink::reflect::ContractEventBase>::Type;
+            let Ok(Event::AuctionInitialized(event)) =
+                <Event as scale::Decode>::decode(&mut &events[0].data[..])
+            else {
+                panic!("expected AuctionInitialized");
+            };
+            assert_eq!(
+                event,
+                AuctionInitialized {
+                    owner: AccountId::from([1; 32]),
+                    config: auction.config(),
+                    seq: 0,
+                }
+            );
+            assert!(auction.config().weights.is_some());
+        }
+
+        #[ink::test]
+        fn events_carry_consecutive_sequence_numbers() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            assert_eq!(auction.events_emitted(), 1);
+            auction.set_metadata(Metadata::default()).unwrap();
+            auction.lock_parameters().unwrap();
+            assert_eq!(auction.events_emitted(), 3);
+
+            let events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            type Event = <DutchAuction as ink::reflect::ContractEventBase>::Type;
+            let seqs = events
+                .iter()
+                .map(|event| {
+                    match <Event as scale::Decode>::decode(&mut &event.data[..]).unwrap() {
+                        Event::AuctionInitialized(event) => event.seq,
+                        Event::MetadataUpdated(event) => event.seq,
+                        Event::ParametersLocked(event) => event.seq,
+                        _ => panic!("unexpected event"),
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(seqs, vec![0, 1, 2]);
+        }
+
+        #[ink::test]
+        fn price_follows_curve_from_start_to_beyond_end() {
+            let (asset, payment) = tokens();
+            let auction = DutchAuction::new(params(asset, payment, 1000, 100, 90)).unwrap();
+
+            assert_eq!(auction.price(), 1000);
+            assert_eq!(auction.price_at(0), 1000);
+            assert_eq!(auction.price_at(1), 990);
+            assert_eq!(auction.price_at(45), 550);
+            assert_eq!(auction.price_at(89), 110);
+            assert_eq!(auction.price_at(90), 100);
+            assert_eq!(auction.price_at(91), 100);
+            assert_eq!(auction.price_at(BlockNumber::MAX), 100);
+
+            for _ in 0..10 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            assert_eq!(auction.price(), 900);
+            for _ in 0..100 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            assert_eq!(auction.price(), 100);
+        }
+
+        #[ink::test]
+        fn price_before_start_is_start_price() {
+            for _ in 0..5 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            let (asset, payment) = tokens();
+            let auction = DutchAuction::new(params(asset, payment, 1000, 100, 95)).unwrap();
+
+            assert_eq!(auction.price_at(0), 1000);
+            assert_eq!(auction.price_at(5), 1000);
+            assert_eq!(auction.price_at(6), 990);
+        }
+
+        #[ink::test]
+        fn zero_span_curves_are_flat() {
+            let (asset, payment) = tokens();
+            let flat = DutchAuction::new(params(asset, payment, 500, 500, 90)).unwrap();
+            for block in [0, 1, 45, 90, 91] {
+                assert_eq!(flat.price_at(block), 500);
+            }
+
+            // `new` rejects an empty window, unvalidated storage ends it at once at the minimum
+            // price.
+            let instant = DutchAuction::build(asset, payment, 1000, 100, 0);
+            for block in [0, 1, 90] {
+                assert_eq!(instant.price_at(block), 100);
+            }
+
+            // A decrease shallower than one unit per block steps down every few blocks.
+            let shallow = DutchAuction::new(params(asset, payment, 13, 10, 90)).unwrap();
+            assert_eq!(shallow.price_at(29), 13);
+            assert_eq!(shallow.price_at(30), 12);
+            assert_eq!(shallow.price_at(89), 11);
+            assert_eq!(shallow.price_at(90), 10);
+        }
+
+        #[ink::test]
+        fn huge_price_spans_do_not_overflow() {
+            let (asset, payment) = tokens();
+            let mut auction =
+                DutchAuction::new(params(asset, payment, u128::MAX, 0, BlockNumber::MAX)).unwrap();
+            let per_block = u128::MAX / u128::from(BlockNumber::MAX);
+
+            assert_eq!(auction.price_at(0), u128::MAX);
+            assert_eq!(auction.price_at(1), u128::MAX - per_block);
+            assert_eq!(auction.price_at(BlockNumber::MAX - 1), per_block);
+            assert_eq!(auction.price_at(BlockNumber::MAX), 0);
+
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+            assert_eq!(
+                auction.can_buy(AccountId::from([5; 32]), 2),
+                Err(Error::ArithmeticOverflow)
+            );
+        }
+
+        #[ink::test]
+        fn max_price_applies_to_rounded_total() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 1000, 1, 100)).unwrap();
+            let id = auction
+                .propose_change(ParameterChange::TickSize(250))
+                .unwrap();
+            auction.execute_change(id).unwrap();
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+            for _ in 0..33 {
+                ink::env::test::advance_block::<Environment>();
+            }
+
+            // 703 on the curve, charged as 750.
+            assert_eq!(auction.price(), 750);
+            assert_eq!(auction.price_for_amount(2), 1500);
+            assert_eq!(
+                auction.buy(2, Some(1406), false, None, vec![]),
+                Err(Error::MaxPriceExceeded)
+            );
+            // Failed calls aren't reverted off-chain, so release the reentrancy lock.
+            auction.unlock();
+            assert_eq!(
+                auction.buy(2, Some(1499), false, None, vec![]),
+                Err(Error::MaxPriceExceeded)
+            );
+        }
+
+        #[ink::test]
+        fn cloned_config_keeps_duration_and_applies_overrides() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 1000, 100, 50)).unwrap();
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+            let template = auction.config();
+
+            let config =
+                DutchAuction::cloned_config(template.clone(), ConfigOverrides::default(), 200)
+                    .unwrap();
+            assert_eq!((config.start_time, config.end_time), (200, 250));
+            assert_eq!((config.start_price, config.min_price), (1000, 100));
+            assert!(config.mint_on_demand);
+
+            let overrides = ConfigOverrides {
+                start_price: Some(2000),
+                end_time: Some(300),
+                ..Default::default()
+            };
+            let config = DutchAuction::cloned_config(template.clone(), overrides, 200).unwrap();
+            assert_eq!((config.start_price, config.min_price), (2000, 100));
+            assert_eq!((config.start_time, config.end_time), (200, 300));
+
+            let outdated = Config {
+                version: CONFIG_VERSION - 1,
+                ..template
+            };
+            assert_eq!(
+                DutchAuction::cloned_config(outdated, ConfigOverrides::default(), 200),
+                Err(Error::InvalidTemplate)
+            );
+        }
+
+        #[ink::test]
+        fn clones_keep_the_price_strategy_data() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 1000, 100, 50)).unwrap();
+            auction.apply_change(ParameterChange::PriceStrategy {
+                strategy: Some(AccountId::from([9; 32])),
+                data: vec![1, 2, 3],
+            });
+
+            let config =
+                DutchAuction::cloned_config(auction.config(), ConfigOverrides::default(), 0)
+                    .unwrap();
+            let clone = DutchAuction::from_template(config, auction.strategy_data()).unwrap();
+            // The strategy is queried with the same parameters, so it quotes the same price.
+            assert_eq!(clone.price_strategy(), auction.price_strategy());
+            assert_eq!(clone.curve_params(), auction.curve_params());
+            assert_eq!(clone.strategy_data(), vec![1, 2, 3]);
+        }
+
+        #[ink::test]
+        fn live_auctions_terminate_only_after_scheduled_timelock() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.timelock.set(&10);
+            assert_eq!(auction.terminate(false), Err(Error::NotTerminable));
+            assert_eq!(auction.terminate(true), Err(Error::NotTerminable));
+
+            ink::env::test::set_caller::<Environment>(AccountId::from([5; 32]));
+            assert_eq!(auction.schedule_termination(), Err(Error::NotAuctionOwner));
+            ink::env::test::set_caller::<Environment>(AccountId::from([1; 32]));
+            assert_eq!(auction.schedule_termination(), Ok(()));
+            assert_eq!(auction.termination_at(), Some(10));
+
+            assert_eq!(auction.terminate(false), Err(Error::NotTerminable));
+            assert_eq!(auction.terminate(true), Err(Error::TimelockActive));
+        }
+
+        #[ink::test]
+        fn open_allocations_and_reservations_block_termination() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_deferred_delivery(true).unwrap();
+            auction.allocate(AccountId::from([5; 32]), 10);
+            auction.finalized = true;
+            assert_eq!(auction.terminate(false), Err(Error::FundsInEscrow));
+            assert_eq!(auction.terminate(true), Err(Error::FundsInEscrow));
+
+            auction.allocated = 0;
+            auction.reserved = 5;
+            assert_eq!(auction.terminate(false), Err(Error::FundsInEscrow));
+        }
+
+        #[ink::test]
+        fn min_price_increases_are_delayed_and_capped() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 1000, 100, 100)).unwrap();
+            auction.timelock.set(&5);
+            let limit = MinPriceIncreaseLimit {
+                delay: 20,
+                max_increase_bps: 1_000,
+            };
+            auction.min_price_increase_limit.set(&Some(limit));
+
+            // Decreases apply at once.
+            let id = auction
+                .propose_change(ParameterChange::MinPrice(80))
+                .unwrap();
+            assert_eq!(auction.min_price(), 80);
+            assert_eq!(auction.proposal(id), None);
+
+            assert_eq!(
+                auction.propose_change(ParameterChange::MinPrice(89)),
+                Err(InvalidParameter::MinPriceIncreaseTooLarge.into())
+            );
+            let id = auction
+                .propose_change(ParameterChange::MinPrice(88))
+                .unwrap();
+            assert_eq!(auction.proposal(id).unwrap().executable_at, 20);
+            for _ in 0..19 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            assert_eq!(auction.execute_change(id), Err(Error::TimelockActive));
+            ink::env::test::advance_block::<Environment>();
+            assert_eq!(auction.execute_change(id), Ok(()));
+            assert_eq!(auction.min_price(), 88);
+
+            // Other changes only wait for the timelock.
+            let id = auction
+                .propose_change(ParameterChange::GracePeriod(3))
+                .unwrap();
+            assert_eq!(auction.proposal(id).unwrap().executable_at, 25);
+        }
+
+        #[ink::test]
+        fn blacklisted_accounts_cannot_buy() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(100)).unwrap();
+            auction.set_raise_goal(Some(1_000)).unwrap();
+            let this = ink::env::account_id::<Environment>();
+            let buyer = AccountId::from([5; 32]);
+
+            ink::env::test::set_caller::<Environment>(buyer);
+            assert_eq!(
+                auction.set_blacklisted(buyer, true),
+                Err(Error::NotAuctionOwner)
+            );
+            ink::env::test::set_caller::<Environment>(AccountId::from([1; 32]));
+            assert_eq!(auction.set_blacklisted(buyer, true), Ok(()));
+            assert!(auction.is_blacklisted(buyer));
+
+            assert_eq!(
+                auction.execute_purchase(
+                    buyer,
+                    Payer::Account(this),
+                    &[(buyer, 1)],
+                    1,
+                    None,
+                    0,
+                    false,
+                    &[]
+                ),
+                Err(Error::Blacklisted)
+            );
+            ink::env::test::set_caller::<Environment>(buyer);
+            assert_eq!(auction.place_bid(1, 10), Err(Error::Blacklisted));
+
+            ink::env::test::set_caller::<Environment>(AccountId::from([1; 32]));
+            assert_eq!(auction.set_blacklisted(buyer, false), Ok(()));
+            assert!(auction
+                .execute_purchase(
+                    buyer,
+                    Payer::Account(this),
+                    &[(buyer, 1)],
+                    1,
+                    None,
+                    0,
+                    false,
+                    &[]
+                )
+                .is_ok());
+        }
+
+        #[ink::test]
+        fn sale_policy_is_set_by_owner_until_locked() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let policy = AccountId::from([7; 32]);
+
+            ink::env::test::set_caller::<Environment>(AccountId::from([5; 32]));
+            assert_eq!(
+                auction.set_sale_policy(Some(policy)),
+                Err(Error::NotAuctionOwner)
+            );
+            ink::env::test::set_caller::<Environment>(AccountId::from([1; 32]));
+            assert_eq!(auction.set_sale_policy(Some(policy)), Ok(()));
+            assert_eq!(auction.config().sale_policy, Some(policy));
+
+            auction.lock_parameters().unwrap();
+            assert_eq!(auction.set_sale_policy(None), Err(Error::ParametersLocked));
+        }
+
+        #[ink::test]
+        fn checkpoints_keep_a_bounded_price_history() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = AccountId::from([3; 32]);
+            assert_eq!(auction.checkpoints(0, 10, 10), vec![]);
+
+            auction.checkpoint().unwrap();
+            auction.record_purchase(buyer, 2, 0, 20, false, &[]);
+            assert_eq!(auction.checkpoint_count(), 1);
+            assert_eq!(
+                auction.checkpoints(0, 0, 10),
+                vec![Checkpoint {
+                    block: 0,
+                    price: 10,
+                    cumulative_sold: 2,
+                }]
+            );
+
+            for _ in 0..300 {
+                ink::env::test::advance_block::<Environment>();
+                auction.checkpoint().unwrap();
+            }
+            assert_eq!(auction.checkpoint_count(), 301);
+            let page = auction.checkpoints(0, 1_000, 500);
+            assert_eq!(page.len(), MAX_PAGE_SIZE as usize);
+            assert_eq!(page[0].block, 301 - CHECKPOINT_CAPACITY);
+            assert_eq!(page[0].cumulative_sold, 2);
+            let blocks: Vec<_> = auction
+                .checkpoints(100, 102, 10)
+                .iter()
+                .map(|checkpoint| checkpoint.block)
+                .collect();
+            assert_eq!(blocks, vec![100, 101, 102]);
+            assert_eq!(auction.checkpoints(100, 300, 10)[0].price, 1);
+            assert_eq!(auction.checkpoints(301, 400, 10), vec![]);
+        }
+
+        #[ink::test]
+        fn realized_price_statistics_weigh_amounts() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = AccountId::from([3; 32]);
+            assert_eq!(auction.average_realized_price(), None);
+            assert_eq!(auction.median_realized_price(), None);
+
+            auction.record_purchase(buyer, 1, 0, 10, false, &[]);
+            auction.record_purchase(buyer, 1, 0, 8, false, &[]);
+            auction.record_purchase(buyer, 2, 0, 6, false, &[]);
+            assert_eq!(auction.average_realized_price(), Some(6));
+            assert_eq!(auction.median_realized_price(), Some(3));
+
+            auction.record_purchase(buyer, 5, 0, 5_000, false, &[]);
+            assert_eq!(auction.median_realized_price(), Some(992));
+            for price in [0, 15, 16, 1_000, u128::MAX] {
+                let midpoint = DutchAuction::bucket_midpoint(DutchAuction::price_bucket(price));
+                assert!(midpoint.abs_diff(price) <= price / 16);
+            }
+        }
+
+        #[ink::test]
+        fn purchase_data_is_included_in_the_event() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = AccountId::from([3; 32]);
+            auction.record_purchase(buyer, 2, 0, 20, false, b"order-7");
+
+            let events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            type Event = <DutchAuction as ink::reflect::ContractEventBase>::Type;
+            let Ok(Event::AssetBought(event)) =
+                <Event as scale::Decode>::decode(&mut &events[events.len() - 1].data[..])
+            else {
+                panic!("expected AssetBought");
+            };
+            assert_eq!(event.by, buyer);
+            assert_eq!(event.data, b"order-7".to_vec());
+        }
+
+        #[ink::test]
+        fn purchases_can_deliver_to_another_account() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(100)).unwrap();
+            auction.set_raise_goal(Some(1_000)).unwrap();
+            let this = ink::env::account_id::<Environment>();
+            let buyer = AccountId::from([5; 32]);
+            let cold_wallet = AccountId::from([6; 32]);
+
+            auction.set_blacklisted(cold_wallet, true).unwrap();
+            assert_eq!(
+                auction.execute_purchase(
+                    buyer,
+                    Payer::Account(this),
+                    &[(cold_wallet, 2)],
+                    2,
+                    None,
+                    0,
+                    false,
+                    &[]
+                ),
+                Err(Error::Blacklisted)
+            );
+            auction.set_blacklisted(cold_wallet, false).unwrap();
+
+            let paid = auction
+                .execute_purchase(
+                    buyer,
+                    Payer::Account(this),
+                    &[(cold_wallet, 2)],
+                    2,
+                    None,
+                    0,
+                    false,
+                    &[],
+                )
+                .unwrap();
+            assert_eq!(auction.contribution_of(buyer), paid);
+            assert_eq!(auction.allocation_of(buyer), 0);
+            assert_eq!(auction.allocation_of(cold_wallet), 2);
+            assert_eq!(auction.purchase_count_of(buyer), 1);
+            assert_eq!(auction.purchase_count_of(cold_wallet), 0);
+        }
+
+        #[ink::test]
+        fn split_purchases_share_amount_and_bonus() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(100)).unwrap();
+            auction.set_raise_goal(Some(1_000)).unwrap();
+            auction
+                .set_bonus_tiers(vec![BonusTier {
+                    min_amount: 0,
+                    before_block: None,
+                    bonus_bps: 1_000,
+                }])
+                .unwrap();
+            let this = ink::env::account_id::<Environment>();
+            let (first, second) = (AccountId::from([5; 32]), AccountId::from([6; 32]));
+
+            assert_eq!(auction.buy_split(10, vec![]), Err(Error::InvalidSplit));
+            assert_eq!(
+                auction.buy_split(10, vec![(first, 7), (second, 2)]),
+                Err(Error::InvalidSplit)
+            );
+            assert_eq!(
+                auction.buy_split(10, vec![(first, 10), (second, 0)]),
+                Err(Error::InvalidSplit)
+            );
+            assert_eq!(
+                auction.buy_split(10, vec![(first, 1); MAX_SPLIT_RECIPIENTS + 1]),
+                Err(Error::InvalidSplit)
+            );
+
+            let buyer = AccountId::from([1; 32]);
+            auction
+                .execute_purchase(
+                    buyer,
+                    Payer::Account(this),
+                    &[(first, 7), (second, 3)],
+                    10,
+                    None,
+                    0,
+                    false,
+                    &[],
+                )
+                .unwrap();
+            assert_eq!(auction.allocation_of(first), 8);
+            assert_eq!(auction.allocation_of(second), 3);
+            assert_eq!(auction.allocation_of(buyer), 0);
+            assert_eq!(auction.purchase_count_of(buyer), 1);
+        }
+
+        #[ink::test]
+        fn payment_transfers_with_an_order_buy_assets() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(100)).unwrap();
+            auction.set_raise_goal(Some(1_000)).unwrap();
+            let buyer = AccountId::from([5; 32]);
+            let order = |max_price| {
+                scale::Encode::encode(&BuyOrder {
+                    amount: 2,
+                    max_price,
+                })
+            };
+
+            ink::env::test::set_caller::<Environment>(payment);
+            assert_eq!(
+                auction.before_received(buyer, buyer, 25, vec![1]),
+                Err(PSP22ReceiverError::TransferRejected(String::from(
+                    "InvalidBuyOrder"
+                )))
+            );
+            assert_eq!(
+                auction.before_received(buyer, buyer, 25, order(None)),
+                Ok(())
+            );
+            assert_eq!(auction.allocation_of(buyer), 2);
+            assert_eq!(auction.contribution_of(buyer), 20);
+            assert_eq!(auction.payment_credit_of(buyer), 5);
+            assert_eq!(auction.credited, 5);
+
+            assert_eq!(
+                auction.before_received(buyer, buyer, 25, order(Some(19))),
+                Err(PSP22ReceiverError::TransferRejected(String::from(
+                    "MaxPriceExceeded"
+                )))
+            );
+        }
+
+        #[ink::test]
+        fn progress_reports_time_decay_and_sales() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 101, 1, 100)).unwrap();
+            auction.set_mint_on_demand(Some(100)).unwrap();
+            assert_eq!(
+                auction.progress(),
+                Progress {
+                    blocks_elapsed: 0,
+                    blocks_remaining: 100,
+                    price_decay_percentage: 0,
+                    sold_percentage: 0,
+                }
+            );
+
+            for _ in 0..25 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            auction.record_purchase(AccountId::from([5; 32]), 10, 0, 760, false, &[]);
+            assert_eq!(
+                auction.progress(),
+                Progress {
+                    blocks_elapsed: 25,
+                    blocks_remaining: 75,
+                    price_decay_percentage: 25,
+                    sold_percentage: 10,
+                }
+            );
+
+            for _ in 0..100 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            let progress = auction.progress();
+            assert_eq!(progress.blocks_remaining, 0);
+            assert_eq!(progress.price_decay_percentage, 100);
+        }
+
+        #[ink::test]
+        fn allowlist_only_phases_restrict_reservations_and_orders() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = AccountId::from([2; 32]);
+            auction.set_reservation_terms(10, 1_000).unwrap();
+            apply(
+                &mut auction,
+                ParameterChange::Phases(vec![Phase {
+                    start_block: 0,
+                    allowlist_only: true,
+                    max_per_account: None,
+                    discount_bps: 0,
+                }]),
+            )
+            .unwrap();
+
+            assert_eq!(auction.can_buy(buyer, 1), Err(Error::NotAllowlisted));
+            ink::env::test::set_caller::<Environment>(buyer);
+            assert_eq!(auction.reserve(1), Err(Error::NotAllowlisted));
+
+            ink::env::test::set_caller::<Environment>(AccountId::from([1; 32]));
+            auction.set_order_collection(true).unwrap();
+            ink::env::test::set_caller::<Environment>(buyer);
+            assert_eq!(auction.place_order(1), Err(Error::NotAllowlisted));
+        }
+
+        #[ink::test]
+        fn proven_orders_are_allowlisted_and_discounted() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let this = ink::env::account_id::<Environment>();
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+            auction.set_order_collection(true).unwrap();
+            auction
+                .set_allowlist_root(Some(DutchAuction::allowlist_leaf(this)))
+                .unwrap();
+            apply(
+                &mut auction,
+                ParameterChange::Phases(vec![Phase {
+                    start_block: 0,
+                    allowlist_only: true,
+                    max_per_account: None,
+                    discount_bps: 1_000,
+                }]),
+            )
+            .unwrap();
+
+            // Orders from the contract's own account skip the payment transfer.
+            ink::env::test::set_caller::<Environment>(this);
+            assert_eq!(auction.place_order(10), Err(Error::NotAllowlisted));
+            assert_eq!(
+                auction.place_order_with_proof(10, vec![[7; 32]]),
+                Err(Error::NotAllowlisted)
+            );
+            assert_eq!(auction.place_order_with_proof(10, vec![]), Ok(()));
+            assert_eq!(auction.order_of(this).map(|order| order.paid), Some(90));
+        }
+
+        #[ink::test]
+        fn discounted_quotes_apply_the_largest_discount() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = AccountId::from([2; 32]);
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+            apply(
+                &mut auction,
+                ParameterChange::Phases(vec![Phase {
+                    start_block: 0,
+                    allowlist_only: false,
+                    max_per_account: None,
+                    discount_bps: 1_000,
+                }]),
+            )
+            .unwrap();
+
+            // Reservations lock this total and take their deposit from it.
+            assert_eq!(auction.quote(10).map(|quote| quote.total), Ok(100));
+            assert_eq!(
+                auction
+                    .discounted_quote(buyer, 10, false, 0)
+                    .map(|quote| quote.total),
+                Ok(90)
+            );
+            assert_eq!(
+                auction
+                    .discounted_quote(buyer, 10, false, 2_000)
+                    .map(|quote| quote.total),
+                Ok(80)
+            );
+        }
+
+        #[ink::test]
+        fn round_caps_cover_reservations_and_every_sale() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = AccountId::from([2; 32]);
+            auction.set_mint_on_demand(Some(1_000)).unwrap();
+            auction.set_reservation_terms(10, 1_000).unwrap();
+            auction
+                .set_rounds(vec![Round {
+                    start_block: 0,
+                    start_price: 10,
+                    anchor_bps: None,
+                    min_price: 1,
+                    supply: 100,
+                    max_per_account: Some(5),
+                }])
+                .unwrap();
+
+            ink::env::test::set_caller::<Environment>(buyer);
+            assert_eq!(auction.reserve(6), Err(Error::RoundCapExceeded));
+
+            // As filled by `match_bids`, `exercise_reservation` or `settle_order`.
+            assert_eq!(auction.admit_purchase(buyer, 5, 1, 50), Ok(()));
+            assert_eq!(
+                auction.round_stats(0),
+                RoundStats {
+                    sold: 6,
+                    raised: 50
+                }
+            );
+            assert_eq!(auction.can_buy(buyer, 1), Err(Error::RoundCapExceeded));
+        }
+
+        #[ink::test]
+        fn blocks_until_price_finds_first_crossing() {
+            let (asset, payment) = tokens();
+            let auction = DutchAuction::new(params(asset, payment, 100, 10, 90)).unwrap();
+
+            assert_eq!(auction.remaining_blocks(), 90);
+            assert_eq!(auction.blocks_until_price(100), Some(0));
+            assert_eq!(auction.blocks_until_price(50), Some(50));
+            assert_eq!(auction.blocks_until_price(49), Some(51));
+            assert_eq!(auction.blocks_until_price(10), Some(90));
+            assert_eq!(auction.blocks_until_price(9), None);
+        }
+
+        #[ink::test]
+        fn purchases_feed_twap_and_buyer_index() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let buyer = AccountId::from([3; 32]);
+            assert_eq!(auction.twap(10), None);
+
+            auction.record_purchase(buyer, 1, 0, 10, false, &[]);
+            auction.record_purchase(buyer, 1, 0, 8, false, &[]);
+            for _ in 0..5 {
+                ink::env::test::advance_block::<Environment>();
+            }
+            auction.record_purchase(buyer, 2, 0, 6, false, &[]);
+            auction.record_proceeds(24);
+
+            assert_eq!(auction.twap(10), Some(6));
+            assert_eq!(auction.purchase_count_of(buyer), 3);
+            assert_eq!(auction.proceeds(), vec![(payment, 24)]);
+            let page = auction.purchases_of(buyer, 1, 5);
+            assert_eq!(page.len(), 2);
+            assert_eq!(
+                page[1],
+                Purchase {
+                    id: 2,
+                    block: 5,
+                    amount: 2,
+                    price: 6,
+                    bonus: 0,
+                    allocated: false,
+                    refunded: false,
+                }
+            );
+            assert_eq!(auction.purchase(2), Some((buyer, page[1].clone())));
+            assert_eq!(auction.purchase(3), None);
+            assert_eq!(auction.twap(2), Some(3));
+            ink::env::test::advance_block::<Environment>();
+            assert_eq!(auction.twap(0), None);
+        }
+
+        #[ink::test]
+        fn bonus_uses_best_matching_tier() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            assert_eq!(auction.bonus_for(1_000), 0);
+
+            assert_eq!(
+                auction.set_bonus_tiers(vec![BonusTier {
+                    min_amount: 0,
+                    before_block: None,
+                    bonus_bps: 10_001,
+                }]),
+                Err(InvalidParameter::InvalidBasisPoints.into())
+            );
+            let tiers = vec![
+                BonusTier {
+                    min_amount: 0,
+                    before_block: Some(2),
+                    bonus_bps: 500,
+                },
+                BonusTier {
+                    min_amount: 1_000,
+                    before_block: None,
+                    bonus_bps: 1_000,
+                },
+            ];
+            assert_eq!(auction.set_bonus_tiers(tiers.clone()), Ok(()));
+            assert_eq!(auction.bonus_tiers(), tiers);
+
+            assert_eq!(auction.bonus_for(100), 5);
+            assert_eq!(auction.bonus_for(1_000), 100);
+            ink::env::test::advance_block::<Environment>();
+            ink::env::test::advance_block::<Environment>();
+            assert_eq!(auction.bonus_for(100), 0);
+            assert_eq!(auction.bonus_for(2_000), 200);
+        }
+
+        #[ink::test]
+        fn vouchers_require_signature_and_single_use() {
+            use secp256k1::{Message, PublicKey, SecretKey, SECP256K1};
+
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let key = SecretKey::from_slice(&[7; 32]).unwrap();
+            let sign = |hash: [u8; 32]| {
+                let (id, bytes) = SECP256K1
+                    .sign_ecdsa_recoverable(&Message::from_slice(&hash).unwrap(), &key)
+                    .serialize_compact();
+                let mut signature = [0; 65];
+                signature[..64].copy_from_slice(&bytes);
+                signature[64] = id.to_i32() as u8;
+                signature
+            };
+            let voucher = Voucher {
+                beneficiary: AccountId::from([1; 32]),
+                discount_bps: 1_000,
+                expires_at: 10,
+                nonce: 1,
+            };
+            let signature = sign(auction.voucher_hash(voucher.clone()));
+
+            assert_eq!(
+                auction.redeem_voucher(voucher.clone(), signature),
+                Err(Error::InvalidSignature)
+            );
+            let mut signer = [0; 20];
+            ink::env::ecdsa_to_eth_address(
+                &PublicKey::from_secret_key(SECP256K1, &key).serialize(),
+                &mut signer,
+            )
+            .unwrap();
+            auction.set_voucher_signer(Some(signer)).unwrap();
+            let forged = Voucher {
+                discount_bps: 10_000,
+                ..voucher.clone()
+            };
+            assert_eq!(
+                auction.redeem_voucher(forged, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(auction.redeem_voucher(voucher.clone(), signature), Ok(()));
+            assert_eq!(
+                auction.redeem_voucher(voucher, signature),
+                Err(Error::VoucherUsed)
+            );
+        }
+
+        #[ink::test]
+        fn phases_are_ordered_and_looked_up_by_block() {
+            let (asset, payment) = tokens();
+            let mut auction = DutchAuction::new(params(asset, payment, 10, 1, 100)).unwrap();
+            let phase = |start_block, allowlist_only| Phase {
+                start_block,
+                allowlist_only,
+                max_per_account: None,
+                discount_bps: 0,
+            };
+            assert_eq!(auction.active_phase(), None);
+
+            assert_eq!(
+                apply(
+                    &mut auction,
+                    ParameterChange::Phases(vec![phase(5, true), phase(5, false)])
+                ),
+                Err(InvalidParameter::UnorderedPhases.into())
+            );
+            assert_eq!(
+                apply(
+                    &mut auction,
+                    ParameterChange::Phases(vec![phase(0, true), phase(5, false)])
+                ),
+                Ok(())
+            );
+            assert_eq!(auction.active_phase(), Some((0, phase(0, true))));
+            assert_eq!(auction.phase_at(4), Some((0, phase(0, true))));
+            assert_eq!(auction.phase_at(5), Some((1, phase(5, false))));
+            assert_eq!(
+                auction.phase_at(BlockNumber::MAX),
+                Some((1, phase(5, false)))
+            );
+        }
+
+        #[test]
+        fn weighted_price_follows_weight_ratio() {
+            let weights = WeightSchedule {
+                start_asset_weight_bps: 9_000,
+                end_asset_weight_bps: 5_000,
+            };
+            let price = |block| DutchAuction::weighted_price(&weights, 0, 100, 900, block);
+
+            assert_eq!(price(0), 900);
+            // 50/50 weights: 900 / 9
+            assert_eq!(price(100), 100);
+            assert_eq!(price(200), 100);
+            // 80/20 weights: 900 * 4 / 9
+            assert_eq!(price(25), 400);
+            assert!((0..100).all(|block| price(block) >= price(block + 1)));
+            assert_eq!(
+                DutchAuction::weighted_price(&weights, 0, 100, u128::MAX, 0),
+                u128::MAX
+            );
+        }
+
+        #[test]
+        fn merkle_proofs_verify_membership() {
+            let leaves: Vec<_> = (1..=3)
+                .map(|byte| DutchAuction::allowlist_leaf(AccountId::from([byte; 32])))
+                .collect();
+            let left = DutchAuction::hash_pair(leaves[0], leaves[1]);
+            let root = DutchAuction::hash_pair(left, leaves[2]);
+
+            assert!(DutchAuction::verify_proof(
+                root,
+                leaves[0],
+                &[leaves[1], leaves[2]]
+            ));
+            assert!(DutchAuction::verify_proof(
+                root,
+                leaves[1],
+                &[leaves[0], leaves[2]]
+            ));
+            assert!(DutchAuction::verify_proof(root, leaves[2], &[left]));
+            assert!(!DutchAuction::verify_proof(root, leaves[2], &[leaves[0]]));
+            let outsider = DutchAuction::allowlist_leaf(AccountId::from([4; 32]));
+            assert!(!DutchAuction::verify_proof(
+                root,
+                outsider,
+                &[leaves[1], leaves[2]]
+            ));
+        }
+
+        #[test]
+        fn to_payment_applies_oracle_rate() {
+            let rate = RateData {
+                rate: RATE_PRECISION * 5 / 2,
+                updated_at: 0,
+            };
+
+            assert_eq!(DutchAuction::to_payment(10, None), 10);
+            assert_eq!(DutchAuction::to_payment(10, Some(&rate)), 25);
+            assert_eq!(
+                DutchAuction::to_payment(RATE_PRECISION * 4 + 2, Some(&rate)),
+                RATE_PRECISION * 10 + 5
+            );
+            assert_eq!(DutchAuction::to_payment(u128::MAX, Some(&rate)), u128::MAX);
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(2_000))]
+
+            #[test]
+            fn price_is_monotonically_non_increasing(
+                curve in curves(),
+                a in blocks(),
+                b in blocks(),
+            ) {
+                let mut blocks = sample_blocks(curve).to_vec();
+                blocks.extend([a, b]);
+                blocks.sort_unstable();
+                for pair in blocks.windows(2) {
+                    prop_assert!(
+                        price(curve, pair[0]) >= price(curve, pair[1]),
+                        "{:?} increases between blocks {} and {}",
+                        curve,
+                        pair[0],
+                        pair[1]
+                    );
+                }
+            }
+
+            #[test]
+            fn price_is_bounded_by_min_and_start_price(curve in curves(), block in blocks()) {
+                let (_, start_price, _, min_price) = curve;
+                for block in sample_blocks(curve).into_iter().chain([block]) {
+                    let price = price(curve, block);
+                    prop_assert!(price >= min_price, "{:?} below min at {}", curve, block);
+                    prop_assert!(
+                        price <= start_price.max(min_price),
+                        "{:?} above start at {}",
+                        curve,
+                        block
+                    );
+                }
+            }
+
+            #[test]
+            fn price_is_exact_at_endpoints(curve in curves()) {
+                let (start_time, start_price, end_time, min_price) = curve;
+                prop_assert_eq!(price(curve, end_time), min_price, "{:?} at end", curve);
+                if start_time < end_time {
+                    prop_assert_eq!(
+                        price(curve, start_time),
+                        start_price.max(min_price),
+                        "{:?} at start",
+                        curve
+                    );
+                }
+            }
         }
     }
 }
@@ -0,0 +1,169 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Mock PSP22 Contract
+///
+/// A minimal PSP22 token for integration tests and local devnets, usable as both the asset and
+/// the payment token of the auction.
+///
+/// With the `test-helpers` feature anyone can mint tokens - never deploy such a build to a
+/// production network.
+///
+/// Messages:
+/// - mint: Mints tokens to an arbitrary account. Requires the `test-helpers` feature.
+/// - PSP22: The standard PSP22 messages.
+/// - PSP22Burnable::burn: Burns tokens from the caller's account.
+#[ink::contract]
+pub mod mock_psp22 {
+    use ink::prelude::vec::Vec;
+    use psp22::{PSP22Burnable, PSP22Data, PSP22Error, PSP22Event, PSP22};
+
+    #[ink(storage)]
+    pub struct Token {
+        data: PSP22Data,
+    }
+
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        value: u128,
+    }
+
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        amount: u128,
+    }
+
+    impl Token {
+        /// Constructor minting the whole `supply` to the caller.
+        #[ink(constructor)]
+        pub fn new(supply: u128) -> Self {
+            let (data, events) = PSP22Data::new(supply, Self::env().caller());
+            let contract = Self { data };
+            contract.emit_events(events);
+            contract
+        }
+
+        /// Mint `value` tokens to `to`.
+        ///
+        /// Callable by anyone.
+        #[cfg(feature = "test-helpers")]
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, value: u128) -> Result<(), PSP22Error> {
+            let events = self.data.mint(to, value)?;
+            self.emit_events(events);
+            Ok(())
+        }
+
+        fn emit_events(&self, events: Vec<PSP22Event>) {
+            for event in events {
+                match event {
+                    PSP22Event::Transfer { from, to, value } => {
+                        self.env().emit_event(Transfer { from, to, value })
+                    }
+                    PSP22Event::Approval {
+                        owner,
+                        spender,
+                        amount,
+                    } => self.env().emit_event(Approval {
+                        owner,
+                        spender,
+                        amount,
+                    }),
+                }
+            }
+        }
+    }
+
+    impl PSP22 for Token {
+        #[ink(message)]
+        fn total_supply(&self) -> u128 {
+            self.data.total_supply()
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u128 {
+            self.data.balance_of(owner)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> u128 {
+            self.data.allowance(owner, spender)
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: u128,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let events = self.data.transfer(self.env().caller(), to, value)?;
+            self.emit_events(events);
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let events = self
+                .data
+                .transfer_from(self.env().caller(), from, to, value)?;
+            self.emit_events(events);
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: u128) -> Result<(), PSP22Error> {
+            let events = self.data.approve(self.env().caller(), spender, value)?;
+            self.emit_events(events);
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn increase_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: u128,
+        ) -> Result<(), PSP22Error> {
+            let events = self
+                .data
+                .increase_allowance(self.env().caller(), spender, delta_value)?;
+            self.emit_events(events);
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn decrease_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: u128,
+        ) -> Result<(), PSP22Error> {
+            let events = self
+                .data
+                .decrease_allowance(self.env().caller(), spender, delta_value)?;
+            self.emit_events(events);
+            Ok(())
+        }
+    }
+
+    impl PSP22Burnable for Token {
+        #[ink(message)]
+        fn burn(&mut self, value: u128) -> Result<(), PSP22Error> {
+            let events = self.data.burn(self.env().caller(), value)?;
+            self.emit_events(events);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,477 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+//! Dutch Auction Traits
+//!
+//! Trait definitions shared between the Dutch auction contract and the contracts it
+//! interacts with.
+//!
+//! Traits:
+//! - DutchAuction: The public interface of the Dutch auction contract, for typed cross-contract calls.
+//! - PriceStrategy: A pluggable pricing curve the auction can delegate `current_price` to.
+//! - PriceOracle: A source of conversion rates from a reference unit to the payment token.
+//! - PSP22Receiver: The PSP22 hook invoked on the recipient of a token transfer.
+//! - Ownable: The standard ownership interface understood by wallets and explorers.
+//! - AuctionListener: A contract notified by the auction after every purchase.
+//! - AuctionObserver: A subscribed contract notified of parameter changes and state transitions.
+//! - DexRouter: The subset of a Uniswap V2 style router used to seed liquidity and swap into the
+//!   payment token.
+//! - AuctionRegistry: A directory of auctions which register themselves for discovery.
+//! - SalePolicy: Compliance rules consulted by the auction before every purchase.
+
+use ink::{
+    env::{DefaultEnvironment, Environment},
+    prelude::{string::String, vec::Vec},
+    primitives::AccountId,
+    LangError,
+};
+use psp22::PSP22Error;
+
+type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+
+#[derive(Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum DutchAuctionError {
+    PSP22TokenCall(PSP22Error),
+    MaxPriceExceeded,
+    InsufficientSupplyToken,
+    NotAuctionOwner,
+    AuctionNotStarted,
+    AuctionNotEnded,
+    AuctionEnded,
+    InvalidSuccessor,
+    ArithmeticOverflow,
+    ZeroAmount,
+    InsufficientAllowance,
+    InsufficientBuyerBalance,
+    InvalidParameters {
+        reason: InvalidParameter,
+    },
+    ReservationsDisabled,
+    ReservationExists,
+    NoReservation,
+    ReservationExpired,
+    ReservationActive,
+    StaleOracleRate,
+    InvalidSignature,
+    NotVoucherBeneficiary,
+    VoucherExpired,
+    VoucherUsed,
+    NotAllowlisted,
+    PhaseCapExceeded,
+    RouterCall(RouterError),
+    SwapRouterNotSet,
+    SlippageExceeded,
+    TooManyListeners,
+    AlreadySubscribed,
+    NotSubscribed,
+    InsufficientDeposit,
+    TooManySubscribers,
+    NativeTransferFailed,
+    ReentrancyDetected,
+    TransferFeeExceeded,
+    SaleAlreadyStarted,
+    GoalNotReached,
+    GoalReached,
+    NothingToClaim,
+    FundsInEscrow,
+    RefundWindowClosed,
+    NoPurchase,
+    AlreadyRefunded,
+    OrderCollectionActive,
+    NoOrder,
+    NoBid,
+    TooManyBids,
+    NoProposal,
+    TimelockActive,
+    ParametersLocked,
+    InvalidLotSize,
+    CapReached,
+    AuctionPaused,
+    RoundCapExceeded,
+    ClaimsNotOpen,
+    AllocationsNotTransferable,
+    InsufficientAllocation,
+    /// A PSP22 call trapped or returned data that couldn't be decoded. `lang_error` is set when
+    /// the token reported an ink! language error.
+    TokenCallFailed {
+        lang_error: Option<LangError>,
+    },
+    NativePaymentNotAccepted,
+    OperatorAllowanceExceeded,
+    NotTrustedForwarder,
+    RequestExpired,
+    InvalidNonce,
+    RegistryCall(RegistryError),
+    InvalidTemplate,
+    NotTerminable,
+    Blacklisted,
+    PolicyRejected(PolicyError),
+    InvalidSplit,
+    InvalidBuyOrder,
+    SameBlockClaim,
+    DeliveryNotRefundable,
+}
+
+/// The reason an auction parameter was rejected.
+#[derive(Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum InvalidParameter {
+    /// The minimum price is greater than the starting price.
+    MinPriceAboveStartPrice,
+    /// The end time is not after the start time.
+    InvalidTimeRange,
+    /// The asset and payment tokens are the same contract.
+    IdenticalTokens,
+    /// A basis points value exceeds 10000.
+    InvalidBasisPoints,
+    /// The sale phases don't start in strictly increasing order.
+    UnorderedPhases,
+    /// The sale rounds don't start in strictly increasing order before the end of the auction.
+    UnorderedRounds,
+    /// The tranche size or duration of a perpetual auction is zero.
+    InvalidTranche,
+    /// The weight schedule has a weight outside `(0, 10000)` or a rising asset weight.
+    InvalidWeights,
+    /// The swap route doesn't lead from the input token to the payment token.
+    InvalidRoute,
+    /// A metadata field exceeds its maximum length.
+    MetadataTooLong,
+    /// The minimum price would rise by more than the configured limit.
+    MinPriceIncreaseTooLarge,
+}
+
+impl From<PSP22Error> for DutchAuctionError {
+    fn from(inner: PSP22Error) -> Self {
+        DutchAuctionError::PSP22TokenCall(inner)
+    }
+}
+
+impl From<RouterError> for DutchAuctionError {
+    fn from(inner: RouterError) -> Self {
+        DutchAuctionError::RouterCall(inner)
+    }
+}
+
+impl From<PolicyError> for DutchAuctionError {
+    fn from(inner: PolicyError) -> Self {
+        DutchAuctionError::PolicyRejected(inner)
+    }
+}
+
+impl From<RegistryError> for DutchAuctionError {
+    fn from(inner: RegistryError) -> Self {
+        DutchAuctionError::RegistryCall(inner)
+    }
+}
+
+impl From<InvalidParameter> for DutchAuctionError {
+    fn from(reason: InvalidParameter) -> Self {
+        DutchAuctionError::InvalidParameters { reason }
+    }
+}
+
+#[ink::trait_definition]
+pub trait DutchAuction {
+    /// The token being auctioned.
+    #[ink(message)]
+    fn asset_token(&self) -> AccountId;
+
+    /// The token used for payment.
+    #[ink(message)]
+    fn payment_token(&self) -> AccountId;
+
+    /// The block at which the auction starts
+    #[ink(message)]
+    fn start_block(&self) -> BlockNumber;
+
+    /// The block after which the price will no longer decrease.
+    ///
+    /// The contract will decrease the price linearly from start_price()
+    /// to `min_price()` over the period from `start_time()` to 'end_time()`.
+    /// The auction doesn't end after the period elapses -
+    /// the asset remains available for purchase at `min_price()`.
+    #[ink(message)]
+    fn end_time(&self) -> BlockNumber;
+
+    /// The price the contract would charge when buying at the current block.
+    #[ink(message)]
+    fn price(&self) -> u128;
+
+    /// The price the contract would charge when buying at `block`.
+    ///
+    /// Evaluates the same curve as `price()`, so frontends can render the full price chart.
+    #[ink(message)]
+    fn price_at(&self, block: BlockNumber) -> u128;
+
+    /// Amount of tokens available for sale.
+    #[ink(message)]
+    fn available_asset(&self) -> u128;
+
+    /// The minimal price the contract allows.
+    #[ink(message)]
+    fn min_price(&self) -> u128;
+
+    /// Total amount of asset tokens sold so far.
+    #[ink(message)]
+    fn sold(&self) -> u128;
+
+    /// Buy `asset_tokens` at the `current_price`.
+    ///
+    /// The caller should provide a positive `amount` of 'asset_tokens' to purchase.
+    ///
+    /// The caller should make an approval for at least `price()*amount` reward tokens to make sure the
+    /// call will succeed. The caller can specify a `max_price` - the call will fail if the
+    /// current price is greater than that.
+    ///
+    /// If `allow_partial` is set and fewer than `amount` asset tokens are available, as many as
+    /// are available are bought instead of failing. Returns the amount bought.
+    ///
+    /// If the payment token is the wrapped native token, the caller can transfer native value
+    /// instead of approving payment tokens. The value is wrapped and caps the price paid, any
+    /// remainder is returned as payment tokens.
+    ///
+    /// The asset tokens go to `deliver_to` if set, e.g. a cold wallet, while the caller pays and
+    /// is recorded as the buyer. Not available while the auction offers cooling-off refunds.
+    ///
+    /// `data` is passed to the PSP22 transfers of the payment and the asset tokens, e.g. for
+    /// receiver hooks, and included in the `AssetBought` event for tagging orders off-chain.
+    #[ink(message, payable)]
+    fn buy(
+        &mut self,
+        amount: u128,
+        max_price: Option<u128>,
+        allow_partial: bool,
+        deliver_to: Option<AccountId>,
+        data: Vec<u8>,
+    ) -> Result<u128, DutchAuctionError>;
+}
+
+/// Parameters the auction passes to a `PriceStrategy` on every price query.
+#[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct CurveParams {
+    /// The configured starting price of the auction.
+    pub start_price: u128,
+    /// The configured minimum price of the auction.
+    pub min_price: u128,
+    /// Strategy specific configuration, opaque to the auction.
+    pub data: Vec<u8>,
+}
+
+#[ink::trait_definition]
+pub trait PriceStrategy {
+    /// Returns the price of a single asset token at block `now` for an auction running
+    /// from block `start` to block `end`.
+    ///
+    /// The auction never charges less than `params.min_price`, regardless of the value returned.
+    #[ink(message)]
+    fn price_at(
+        &self,
+        start: BlockNumber,
+        end: BlockNumber,
+        now: BlockNumber,
+        params: CurveParams,
+    ) -> u128;
+}
+
+/// Fixed point precision of `RateData::rate`.
+pub const RATE_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+/// A conversion rate reported by a `PriceOracle`.
+#[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct RateData {
+    /// Payment token units per reference unit, scaled by `RATE_PRECISION`.
+    pub rate: u128,
+    /// The block at which the rate was last updated.
+    pub updated_at: BlockNumber,
+}
+
+#[ink::trait_definition]
+pub trait PriceOracle {
+    /// Returns the latest rate converting reference-unit prices into payment token amounts.
+    #[ink(message)]
+    fn latest_rate(&self) -> RateData;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22ReceiverError {
+    /// The recipient refused to accept the transfer.
+    TransferRejected(String),
+}
+
+#[ink::trait_definition]
+pub trait PSP22Receiver {
+    /// Called by a PSP22 token contract before `value` tokens are credited to the receiving
+    /// contract, with the `data` passed to `transfer` or `transfer_from`.
+    ///
+    /// Returning an error reverts the whole transfer.
+    #[ink(message)]
+    fn before_received(
+        &mut self,
+        operator: AccountId,
+        from: AccountId,
+        value: u128,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22ReceiverError>;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum OwnableError {
+    /// The caller is not the owner of the contract.
+    CallerIsNotOwner,
+    /// The new owner was not provided.
+    NewOwnerIsNotSet,
+}
+
+#[ink::trait_definition]
+pub trait Ownable {
+    /// Returns the current owner, or `None` if ownership was renounced.
+    #[ink(message)]
+    fn owner(&self) -> Option<AccountId>;
+
+    /// Permanently gives up ownership, disabling all owner-only messages.
+    ///
+    /// Requires the current owner to execute.
+    #[ink(message)]
+    fn renounce_ownership(&mut self) -> Result<(), OwnableError>;
+
+    /// Transfers ownership to `new_owner`.
+    ///
+    /// Requires the current owner to execute.
+    #[ink(message)]
+    fn transfer_ownership(&mut self, new_owner: Option<AccountId>) -> Result<(), OwnableError>;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum RouterError {
+    /// A call to one of the pair's tokens failed.
+    PSP22Error(PSP22Error),
+    /// The router rejected the operation.
+    Custom(String),
+}
+
+#[ink::trait_definition]
+pub trait DexRouter {
+    /// Deposits up to `amount_a_desired` of `token_a` and `amount_b_desired` of `token_b` into
+    /// their pair, creating it if needed, and mints the liquidity tokens to `to`.
+    ///
+    /// The router pulls the tokens from the caller, which must have approved them. Fails if
+    /// less than the minimum amounts would be deposited or after the `deadline` timestamp.
+    /// Returns the deposited amounts and the liquidity minted.
+    #[ink(message)]
+    #[allow(clippy::too_many_arguments)]
+    fn add_liquidity(
+        &mut self,
+        token_a: AccountId,
+        token_b: AccountId,
+        amount_a_desired: u128,
+        amount_b_desired: u128,
+        amount_a_min: u128,
+        amount_b_min: u128,
+        to: AccountId,
+        deadline: u64,
+    ) -> Result<(u128, u128, u128), RouterError>;
+
+    /// Swaps exactly `amount_in` of `path[0]` for at least `amount_out_min` of the last token
+    /// in `path`, hopping through the pairs along `path`, and sends the output to `to`.
+    ///
+    /// The router pulls the input from the caller, which must have approved it. Returns the
+    /// amounts of every token along `path`.
+    #[ink(message)]
+    fn swap_exact_tokens_for_tokens(
+        &mut self,
+        amount_in: u128,
+        amount_out_min: u128,
+        path: Vec<AccountId>,
+        to: AccountId,
+        deadline: u64,
+    ) -> Result<Vec<u128>, RouterError>;
+}
+
+#[ink::trait_definition]
+pub trait WrappedNative {
+    /// Wraps the transferred native value, minting the same amount of tokens to the caller.
+    #[ink(message, payable)]
+    fn deposit(&mut self) -> Result<(), PSP22Error>;
+}
+
+#[ink::trait_definition]
+pub trait AuctionListener {
+    /// Called by the auction after `buyer` bought `amount` asset tokens for a total of `price`
+    /// payment tokens.
+    ///
+    /// The call runs with a limited amount of gas and its failure doesn't affect the purchase.
+    #[ink(message)]
+    fn on_purchase(&mut self, buyer: AccountId, amount: u128, price: u128);
+}
+
+/// A change to an auction reported to its observers.
+#[derive(Clone, Eq, PartialEq, Debug, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum AuctionNotification {
+    /// A sale parameter affecting price, access or settlement was updated.
+    ParametersChanged,
+    /// The auction was finalized.
+    Finalized,
+    /// The unsold inventory was moved to `new_auction` and the auction finalized.
+    RolledOver { new_auction: AccountId },
+    /// The auction owner changed, `None` if ownership was renounced.
+    OwnershipTransferred { new_owner: Option<AccountId> },
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PolicyError {
+    /// The buyer isn't eligible, e.g. because of their jurisdiction.
+    NotEligible,
+    /// The purchase exceeds a limit of the policy.
+    LimitExceeded,
+    /// The policy rejected the purchase for another reason.
+    Custom(String),
+}
+
+#[ink::trait_definition]
+pub trait SalePolicy {
+    /// Called by the auction before `buyer` buys `amount` asset tokens for a total of `price`
+    /// payment tokens. An error rejects the purchase.
+    ///
+    /// The policy may record the purchase, e.g. to enforce cumulative limits, since a purchase
+    /// failing afterwards reverts the call as well.
+    #[ink(message)]
+    fn check(&mut self, buyer: AccountId, amount: u128, price: u128) -> Result<(), PolicyError>;
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum RegistryError {
+    /// The auction is already registered.
+    AlreadyRegistered,
+    /// The auction isn't registered.
+    NotRegistered,
+    /// The caller doesn't implement `DutchAuction`.
+    NotAnAuction,
+    /// The auction can't be closed while it is still selling.
+    AuctionActive,
+}
+
+#[ink::trait_definition]
+pub trait AuctionRegistry {
+    /// Registers the calling auction, listing it as active under its asset and payment token
+    /// pair.
+    ///
+    /// The pair is read from the caller through `DutchAuction`, so only auctions can register.
+    #[ink(message)]
+    fn register(&mut self) -> Result<(), RegistryError>;
+}
+
+#[ink::trait_definition]
+pub trait AuctionObserver {
+    /// Called by a subscribed-to auction after `notification` happened.
+    ///
+    /// The call runs with a limited amount of gas and its failure doesn't affect the auction.
+    #[ink(message)]
+    fn on_auction_event(&mut self, notification: AuctionNotification);
+}
@@ -0,0 +1,156 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Treasury Buyer Contract
+///
+/// An example of a contract buying from a Dutch auction: a treasury holding payment tokens which
+/// buys asset tokens once the price drops to a target. It talks to the auction through the
+/// `DutchAuction` trait only, so it works with any auction implementing it.
+///
+/// Purchases are triggered by anyone calling `try_buy`, e.g. a keeper bot watching the price.
+/// The auction is approved exactly the cost of each purchase, capped at the target price, and the
+/// approval is revoked again afterwards.
+///
+/// Messages:
+/// - try_buy: Buys asset tokens if the auction price is at or below the target.
+/// - set_target_price: Updates the target price. Owner only.
+/// - withdraw: Transfers tokens held by the treasury. Owner only.
+/// - auction, target_price, owner: Views of the configuration.
+///
+/// Errors:
+/// - NotOwner: The caller isn't the owner.
+/// - PriceAboveTarget: The auction price is above the target price.
+/// - Auction: The auction rejected the purchase.
+/// - Token: A token call failed.
+#[ink::contract]
+pub mod treasury_buyer {
+    use dutch_auction_traits::{DutchAuction, DutchAuctionError};
+    use ink::{contract_ref, prelude::vec::Vec};
+    use psp22::{PSP22Error, PSP22};
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        NotOwner,
+        PriceAboveTarget,
+        Auction(DutchAuctionError),
+        Token(PSP22Error),
+    }
+
+    impl From<DutchAuctionError> for Error {
+        fn from(error: DutchAuctionError) -> Self {
+            Error::Auction(error)
+        }
+    }
+
+    impl From<PSP22Error> for Error {
+        fn from(error: PSP22Error) -> Self {
+            Error::Token(error)
+        }
+    }
+
+    #[ink(storage)]
+    pub struct TreasuryBuyer {
+        owner: AccountId,
+        auction: AccountId,
+        target_price: u128,
+    }
+
+    /// Asset tokens were bought from the auction.
+    #[ink(event)]
+    pub struct Bought {
+        #[ink(topic)]
+        auction: AccountId,
+        amount: u128,
+        cost: u128,
+    }
+
+    impl TreasuryBuyer {
+        /// Constructor buying from `auction` at a price per asset token of at most
+        /// `target_price`. The caller becomes the owner.
+        #[ink(constructor)]
+        pub fn new(auction: AccountId, target_price: u128) -> Self {
+            Self {
+                owner: Self::env().caller(),
+                auction,
+                target_price,
+            }
+        }
+
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn auction(&self) -> AccountId {
+            self.auction
+        }
+
+        #[ink(message)]
+        pub fn target_price(&self) -> u128 {
+            self.target_price
+        }
+
+        /// Update the target price per asset token.
+        #[ink(message)]
+        pub fn set_target_price(&mut self, target_price: u128) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.target_price = target_price;
+            Ok(())
+        }
+
+        /// Transfer `value` of the treasury's `token` to `to`, e.g. to take out the bought asset
+        /// tokens or unspent payment tokens.
+        #[ink(message)]
+        pub fn withdraw(
+            &mut self,
+            token: AccountId,
+            to: AccountId,
+            value: u128,
+        ) -> Result<(), Error> {
+            self.ensure_owner()?;
+            let mut token: contract_ref!(PSP22) = token.into();
+            token.transfer(to, value, Vec::new())?;
+            Ok(())
+        }
+
+        /// Buy up to `amount` asset tokens if the auction price is at or below the target price.
+        ///
+        /// Fewer tokens are bought if the auction doesn't have `amount` left. Returns the amount
+        /// bought.
+        #[ink(message)]
+        pub fn try_buy(&mut self, amount: u128) -> Result<u128, Error> {
+            let mut auction: contract_ref!(DutchAuction) = self.auction.into();
+            if auction.price() > self.target_price {
+                return Err(Error::PriceAboveTarget);
+            }
+
+            let max_cost = amount
+                .checked_mul(self.target_price)
+                .ok_or(DutchAuctionError::ArithmeticOverflow)?;
+            let mut payment: contract_ref!(PSP22) = auction.payment_token().into();
+            let this = self.env().account_id();
+            let balance_before = payment.balance_of(this);
+            payment.approve(self.auction, max_cost)?;
+
+            // The auction rounds its price, so the rounded total is capped as well.
+            let bought = auction.buy(amount, Some(max_cost), true, None, vec![])?;
+            payment.approve(self.auction, 0)?;
+
+            let cost = balance_before.saturating_sub(payment.balance_of(this));
+            self.env().emit_event(Bought {
+                auction: self.auction,
+                amount: bought,
+                cost,
+            });
+            Ok(bought)
+        }
+
+        fn ensure_owner(&self) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+    }
+}